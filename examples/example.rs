@@ -33,12 +33,10 @@ fn run() -> Result<(), Box<dyn Error>> {
     println!("[t = 1] Sleeping for 1 second!");
     thread::sleep(Duration::from_secs(1));
 
-    match dlm.unlock(&lock1) {
+    match dlm.unlock_owned(lock1) {
         Ok(()) => println!("[t = 2] Released 1st lock after 2 seconds!"),
-        Err(RedsyncError::UnlockFailed(err)) => {
-            if err.includes(RedsyncError::InvalidLease) {
-                println!("[t = 2] Failed to release 1st lock. Lock has expired!")
-            }
+        Err(RedsyncError::LockAlreadyExpired) => {
+            println!("[t = 2] Failed to release 1st lock. Lock has expired!")
         }
         Err(err) => println!("[t = 2] Unexpected error: {}", err),
     };
@@ -50,7 +48,7 @@ fn run() -> Result<(), Box<dyn Error>> {
     println!("[t = 2] Sleeping for 1 second!");
     thread::sleep(Duration::from_secs(1));
 
-    dlm.unlock(&lock2)
+    dlm.unlock_owned(lock2)
         .map_err(|err| format!("Failed to release lock on resource: {}", err))?;
     println!("[t = 3] Released 2nd lock after 1 second!");
 
@@ -0,0 +1,79 @@
+use crate::errors::MultiError;
+use crate::instance::Instance;
+use crate::redsync::{Lock, Redsync};
+
+use std::time::Duration;
+
+/// `AttemptOutcome` is the result of a single lock attempt across the cluster.
+// Boxing the lock would cost an allocation on every successful attempt
+#[allow(clippy::large_enum_variant)]
+pub enum AttemptOutcome {
+    Acquired(Lock),
+    Failed(MultiError),
+}
+
+/// `LockAttempts` makes lock attempts on demand, leaving sleeps and retries to the caller.
+///
+/// Every attempt goes through the same checks as `Redsync::lock`, and quorum checks and rollback
+/// of partially acquired locks are handled on every attempt. As an iterator, it yields outcomes
+/// until the lock is acquired.
+pub struct LockAttempts<'a, I: Instance> {
+    dlm: &'a Redsync<I>,
+    lock: Lock,
+    attempts: u32,
+    acquired: bool,
+}
+
+impl<'a, I: Instance> LockAttempts<'a, I> {
+    pub(crate) fn new(dlm: &'a Redsync<I>, resource: &[u8], value: Vec<u8>, ttl: Duration) -> Self {
+        Self {
+            dlm,
+            lock: Lock::pending(resource, value, ttl, dlm.id),
+            attempts: 0,
+            acquired: false,
+        }
+    }
+
+    /// `next_attempt` makes a single lock attempt, going through the same checks as
+    /// `Redsync::lock`, such as pausing, quotas and single-flight, and returns its outcome.
+    /// Failures include the reasons the attempt failed, whether on the instances or locally.
+    pub fn next_attempt(&mut self) -> AttemptOutcome {
+        self.attempts += 1;
+
+        let mut errors = MultiError::with_limit(self.dlm.error_limit);
+        if !self.dlm.lock_once(&mut self.lock, &mut errors) {
+            return AttemptOutcome::Failed(errors);
+        }
+
+        self.acquired = true;
+        let pending = Lock::pending(
+            self.lock.resource.clone(),
+            self.lock.value.clone(),
+            self.lock.ttl,
+            self.lock.manager,
+        );
+        AttemptOutcome::Acquired(std::mem::replace(&mut self.lock, pending))
+    }
+
+    /// `attempts` returns the number of attempts made so far.
+    pub fn attempts(&self) -> u32 {
+        self.attempts
+    }
+
+    /// `suggested_delay` returns the delay the manager would wait before its next retry.
+    pub fn suggested_delay(&self) -> Duration {
+        self.dlm.get_retry_delay(&self.lock.resource)
+    }
+}
+
+impl<I: Instance> Iterator for LockAttempts<'_, I> {
+    type Item = AttemptOutcome;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.acquired {
+            return None;
+        }
+
+        Some(self.next_attempt())
+    }
+}
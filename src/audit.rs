@@ -0,0 +1,158 @@
+use crate::errors::RedsyncError;
+
+#[cfg(any(feature = "redis", feature = "resp", test))]
+use std::time::Duration;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// `AUDIT_STREAM` is the key of the Redis stream that audit events are appended to.
+pub const AUDIT_STREAM: &str = "redsync:audit";
+
+/// `OK` is the outcome recorded for successful actions. Failed actions record the `kind` of
+/// their error.
+const OK: &str = "ok";
+
+/// `AuditAction` is the lock operation recorded by an `AuditEvent`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AuditAction {
+    Acquire,
+    Release,
+}
+
+impl AuditAction {
+    fn as_str(&self) -> &'static str {
+        match self {
+            AuditAction::Acquire => "acquire",
+            AuditAction::Release => "release",
+        }
+    }
+
+    #[cfg(any(feature = "redis", feature = "resp", test))]
+    fn parse(action: &str) -> Option<Self> {
+        match action {
+            "acquire" => Some(AuditAction::Acquire),
+            "release" => Some(AuditAction::Release),
+            _ => None,
+        }
+    }
+}
+
+/// `AuditEvent` is an entry of the audit stream, read with `Redsync::audit_tail`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AuditEvent {
+    /// `id` is the ID of the stream entry.
+    pub id: String,
+    /// `identity` is the identity of the manager that recorded the event.
+    pub identity: String,
+    pub resource: String,
+    pub action: AuditAction,
+    /// `outcome` is "ok" if the action succeeded, or the `kind` of its error otherwise.
+    pub outcome: String,
+    pub time: SystemTime,
+}
+
+impl AuditEvent {
+    pub fn is_ok(&self) -> bool {
+        self.outcome == OK
+    }
+
+    /// `from_entry` decodes a stream entry, returning None if it is not an audit event.
+    #[cfg(any(feature = "redis", feature = "resp", test))]
+    pub(crate) fn from_entry(id: String, fields: Vec<(String, String)>) -> Option<Self> {
+        let field = |name: &str| {
+            fields
+                .iter()
+                .find(|(field, _)| field == name)
+                .map(|(_, value)| value.clone())
+        };
+
+        let millis: u64 = field("time")?.parse().ok()?;
+        Some(Self {
+            id,
+            identity: field("identity")?,
+            resource: field("resource")?,
+            action: AuditAction::parse(&field("action")?)?,
+            outcome: field("outcome")?,
+            time: UNIX_EPOCH + Duration::from_millis(millis),
+        })
+    }
+}
+
+/// `AuditLog` configures the audit events appended by a manager. See `RedsyncBuilder::audit_log`.
+pub(crate) struct AuditLog {
+    pub(crate) identity: String,
+    pub(crate) retention: usize,
+}
+
+impl AuditLog {
+    /// `fields` returns the stream fields recording `action` on `resource` with `result`.
+    pub(crate) fn fields<T>(
+        &self,
+        action: AuditAction,
+        resource: &str,
+        result: &Result<T, RedsyncError>,
+    ) -> Vec<(&'static str, String)> {
+        let time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis();
+
+        vec![
+            ("identity", self.identity.clone()),
+            ("resource", String::from(resource)),
+            ("action", String::from(action.as_str())),
+            ("outcome", String::from(outcome(result))),
+            ("time", time.to_string()),
+        ]
+    }
+}
+
+fn outcome<T>(result: &Result<T, RedsyncError>) -> &'static str {
+    match result {
+        Ok(_) => OK,
+        Err(e) => e.name(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_entry() {
+        let log = AuditLog {
+            identity: String::from("worker-1"),
+            retention: 100,
+        };
+        let fields = log.fields(AuditAction::Acquire, "test", &Ok::<(), RedsyncError>(()));
+        let fields = fields
+            .into_iter()
+            .map(|(field, value)| (String::from(field), value))
+            .collect();
+
+        let event = AuditEvent::from_entry(String::from("1-0"), fields).unwrap();
+        assert_eq!(event.identity, "worker-1");
+        assert_eq!(event.resource, "test");
+        assert_eq!(event.action, AuditAction::Acquire);
+        assert!(event.is_ok());
+
+        let fields = log.fields(
+            AuditAction::Release,
+            "test",
+            &Err::<(), _>(RedsyncError::LockAlreadyExpired),
+        );
+        let fields = fields
+            .into_iter()
+            .map(|(field, value)| (String::from(field), value))
+            .collect();
+
+        let event = AuditEvent::from_entry(String::from("2-0"), fields).unwrap();
+        assert_eq!(event.outcome, "lock already expired");
+        assert!(!event.is_ok());
+    }
+
+    #[test]
+    fn from_entry_invalid() {
+        let fields = vec![(String::from("resource"), String::from("test"))];
+        assert!(AuditEvent::from_entry(String::from("1-0"), fields).is_none());
+    }
+}
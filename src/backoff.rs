@@ -0,0 +1,176 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+const MAX_SCALE: f64 = 32.0;
+
+/// `HALF_LIFE` is how long it takes for the scale of a resource that is no longer contended to
+/// halve, so that a burst of contention does not slow retries down indefinitely.
+const HALF_LIFE: Duration = Duration::from_secs(10);
+
+/// `MAX_RESOURCES` caps the number of resources tracked at once. When full, decayed entries are
+/// dropped first, then the least recently contended.
+const MAX_RESOURCES: usize = 1024;
+
+/// `AdaptiveBackoff` tracks contention per resource and scales retry delays accordingly.
+///
+/// Delays are doubled on every contended attempt and reduced by one step on every successful
+/// acquisition, i.e. AIMD applied to the retry rate. Scales also decay over time, halving every
+/// `HALF_LIFE` without contention.
+#[derive(Debug, Default)]
+pub(crate) struct AdaptiveBackoff {
+    scales: Mutex<HashMap<Vec<u8>, Scale>>,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Scale {
+    value: f64,
+    updated: Instant,
+}
+
+impl Scale {
+    /// `decayed` returns the value of the scale at `now`, halved for every `HALF_LIFE` since it
+    /// was last updated.
+    fn decayed(&self, now: Instant) -> f64 {
+        let elapsed = now.saturating_duration_since(self.updated);
+        let halvings = (elapsed.as_secs_f64() / HALF_LIFE.as_secs_f64()).floor();
+        (self.value * 0.5f64.powf(halvings)).max(1.0)
+    }
+}
+
+impl AdaptiveBackoff {
+    pub(crate) fn new() -> Self {
+        Default::default()
+    }
+
+    pub(crate) fn scale(&self, resource: &[u8]) -> f64 {
+        self.scales
+            .lock()
+            .unwrap()
+            .get(resource)
+            .map_or(1.0, |scale| scale.decayed(Instant::now()))
+    }
+
+    pub(crate) fn record_contention(&self, resource: &[u8]) {
+        let now = Instant::now();
+        let mut scales = self.scales.lock().unwrap();
+        if !scales.contains_key(resource) && scales.len() >= MAX_RESOURCES {
+            evict(&mut scales, now);
+        }
+
+        let scale = scales.entry(resource.to_vec()).or_insert(Scale {
+            value: 1.0,
+            updated: now,
+        });
+        *scale = Scale {
+            value: (scale.decayed(now) * 2.0).min(MAX_SCALE),
+            updated: now,
+        };
+    }
+
+    pub(crate) fn record_success(&self, resource: &[u8]) {
+        let now = Instant::now();
+        let mut scales = self.scales.lock().unwrap();
+        if let Some(scale) = scales.get_mut(resource) {
+            *scale = Scale {
+                value: scale.decayed(now) - 1.0,
+                updated: now,
+            };
+            if scale.value <= 1.0 {
+                scales.remove(resource);
+            }
+        }
+    }
+
+    pub(crate) fn snapshot(&self) -> HashMap<Vec<u8>, f64> {
+        let now = Instant::now();
+        self.scales
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(resource, scale)| (resource.clone(), scale.decayed(now)))
+            .filter(|(_, scale)| *scale > 1.0)
+            .collect()
+    }
+}
+
+/// `evict` makes room in `scales` by dropping every entry that has decayed back to 1, or the least
+/// recently updated entry if none has.
+fn evict(scales: &mut HashMap<Vec<u8>, Scale>, now: Instant) {
+    scales.retain(|_, scale| scale.decayed(now) > 1.0);
+    if scales.len() < MAX_RESOURCES {
+        return;
+    }
+
+    let oldest = scales
+        .iter()
+        .min_by_key(|(_, scale)| scale.updated)
+        .map(|(resource, _)| resource.clone());
+    if let Some(resource) = oldest {
+        scales.remove(&resource);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn record_contention() {
+        let backoff = AdaptiveBackoff::new();
+        assert_eq!(backoff.scale(b"test"), 1.0);
+
+        backoff.record_contention(b"test");
+        backoff.record_contention(b"test");
+        assert_eq!(backoff.scale(b"test"), 4.0);
+
+        for _ in 0..10 {
+            backoff.record_contention(b"test");
+        }
+        assert_eq!(backoff.scale(b"test"), MAX_SCALE);
+    }
+
+    #[test]
+    fn record_success() {
+        let backoff = AdaptiveBackoff::new();
+        backoff.record_contention(b"test");
+        backoff.record_contention(b"test");
+
+        backoff.record_success(b"test");
+        assert_eq!(backoff.scale(b"test"), 3.0);
+
+        backoff.record_success(b"test");
+        backoff.record_success(b"test");
+        assert_eq!(backoff.scale(b"test"), 1.0);
+        assert!(backoff.snapshot().is_empty());
+    }
+
+    #[test]
+    fn decay() {
+        let backoff = AdaptiveBackoff::new();
+        let now = Instant::now();
+        backoff.scales.lock().unwrap().insert(
+            b"test".to_vec(),
+            Scale {
+                value: 8.0,
+                updated: now - HALF_LIFE * 2,
+            },
+        );
+
+        assert_eq!(backoff.scale(b"test"), 2.0);
+        backoff.record_contention(b"test");
+        assert_eq!(backoff.scale(b"test"), 4.0);
+    }
+
+    #[test]
+    fn max_resources() {
+        let backoff = AdaptiveBackoff::new();
+        for i in 0..MAX_RESOURCES + 10 {
+            backoff.record_contention(i.to_string().as_bytes());
+        }
+
+        let snapshot = backoff.snapshot();
+        assert_eq!(snapshot.len(), MAX_RESOURCES);
+        assert!(snapshot.contains_key((MAX_RESOURCES + 9).to_string().as_bytes()));
+    }
+}
@@ -1,24 +1,119 @@
+use crate::audit::AuditLog;
+use crate::backoff::AdaptiveBackoff;
+use crate::contention::{Starvation, StarvationHook, Waiters};
+use crate::drift::DriftEstimator;
+use crate::durability::Durability;
+#[cfg(feature = "redis")]
+use crate::errors::MultiError;
+use crate::errors::{RedsyncError, DEFAULT_ERROR_LIMIT};
 use crate::instance::Instance;
-use crate::redsync::Redsync;
+#[cfg(feature = "redis")]
+use crate::instance::RedisInstance;
+use crate::journal::LockJournal;
+use crate::partial::PartialExtend;
+use crate::policy::LockPolicy;
+use crate::pool::WorkerPool;
+use crate::quota::Quota;
+use crate::record::RecordVersion;
+use crate::redsync::{
+    next_manager_id, ping_cluster, quorum_for, Redsync, SleepFn, RELEASE_THREAD_NAME,
+};
+use crate::resource::ResourceRules;
+use crate::rollback::RollbackPolicy;
+use crate::shortcircuit::ShortCircuit;
+use crate::singleflight::{InFlight, SingleFlight};
+use crate::ttl::DEFAULT_DRIFT_FACTOR;
+use crate::warnings::{static_warnings, ConfigHook, ConfigWarning};
 
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
 use std::time::Duration;
 
 /// `RedsyncBuilder` is a builder for configuring and constructing a Redsync instance.
 pub struct RedsyncBuilder<I: Instance> {
     cluster: Vec<I>,
+    failure_domains: Vec<String>,
+    domain_quorum: usize,
     retry_count: u32,
     retry_delay: Duration,
+    extend_retry_count: u32,
+    extend_retry_delay: Duration,
+    default_ttl: Duration,
+    adaptive_retry: bool,
+    single_flight: SingleFlight,
+    short_circuit: ShortCircuit,
+    pipelined: bool,
+    partial_extend: PartialExtend,
+    rollback: RollbackPolicy,
+    verify_on_build: bool,
+    dynamic_drift: bool,
+    warm_connections: bool,
+    record_version: RecordVersion,
+    interop: bool,
+    policy: Option<Box<dyn LockPolicy>>,
+    stats_sample_rate: Option<f64>,
+    audit: Option<AuditLog>,
+    quotas: Vec<(String, Quota)>,
+    resource_rules: Option<ResourceRules>,
+    paused: Arc<AtomicBool>,
+    config_hook: Option<ConfigHook>,
+    starvation: Option<(Duration, StarvationHook)>,
+    journal: Option<Box<dyn LockJournal>>,
+    sequences: bool,
+    durability: Option<Durability>,
+    error_limit: usize,
+    config_snapshot: bool,
+    sleep: Option<SleepFn>,
 }
 
 impl<I: Instance> RedsyncBuilder<I> {
     pub fn new(cluster: Vec<I>) -> Self {
         Self {
             cluster,
+            failure_domains: vec![],
+            domain_quorum: 0,
             retry_count: 3,
             retry_delay: Duration::from_millis(200),
+            extend_retry_count: 1,
+            extend_retry_delay: Duration::from_millis(20),
+            default_ttl: Duration::from_secs(8),
+            adaptive_retry: false,
+            single_flight: SingleFlight::Disabled,
+            short_circuit: ShortCircuit::Disabled,
+            pipelined: false,
+            partial_extend: PartialExtend::Report,
+            rollback: RollbackPolicy::Release,
+            verify_on_build: false,
+            dynamic_drift: false,
+            warm_connections: false,
+            record_version: RecordVersion::V1,
+            interop: false,
+            policy: None,
+            stats_sample_rate: None,
+            audit: None,
+            quotas: vec![],
+            resource_rules: None,
+            paused: Arc::new(AtomicBool::new(false)),
+            config_hook: None,
+            starvation: None,
+            journal: None,
+            sequences: false,
+            durability: None,
+            error_limit: DEFAULT_ERROR_LIMIT,
+            config_snapshot: false,
+            sleep: None,
         }
     }
 
+    /// `failure_domains` labels each instance, in cluster order, with its failure domain (e.g. its
+    /// availability zone). Lock and extend attempts then also require votes from at least
+    /// `domain_quorum` distinct domains.
+    pub fn failure_domains(mut self, failure_domains: &[&str], domain_quorum: usize) -> Self {
+        self.failure_domains = failure_domains.iter().map(|d| String::from(*d)).collect();
+        self.domain_quorum = domain_quorum;
+        self
+    }
+
     pub fn retry_count(mut self, retry_count: u32) -> Self {
         self.retry_count = retry_count;
         self
@@ -29,26 +124,324 @@ impl<I: Instance> RedsyncBuilder<I> {
         self
     }
 
+    /// `extend_retry_count` sets how many attempts an extend makes before failing. Defaults to a
+    /// single attempt, so that a holder whose lease is about to expire learns of it quickly.
+    pub fn extend_retry_count(mut self, retry_count: u32) -> Self {
+        self.extend_retry_count = retry_count;
+        self
+    }
+
+    /// `error_limit` sets how many errors a failed lock or extend reports before truncating the
+    /// rest. Defaults to `DEFAULT_ERROR_LIMIT`.
+    pub fn error_limit(mut self, error_limit: usize) -> Self {
+        self.error_limit = error_limit;
+        self
+    }
+
+    /// `config_snapshot` attaches a `ConfigSnapshot` of the manager to the errors of failed
+    /// lock, extend and unlock calls, shown when they are displayed and returned by
+    /// `MultiError::config`.
+    pub fn config_snapshot(mut self, config_snapshot: bool) -> Self {
+        self.config_snapshot = config_snapshot;
+        self
+    }
+
+    /// `extend_retry_delay` sets the delay between extend attempts. Defaults to 20ms.
+    pub fn extend_retry_delay(mut self, retry_delay: Duration) -> Self {
+        self.extend_retry_delay = retry_delay;
+        self
+    }
+
+    /// `default_ttl` sets the TTL of locks taken with `Redsync::lock_default`. Defaults to 8s.
+    pub fn default_ttl(mut self, default_ttl: Duration) -> Self {
+        self.default_ttl = default_ttl;
+        self
+    }
+
+    /// `adaptive_retry` scales the retry delay of each resource with its recent contention,
+    /// doubling it on every contended attempt and easing it back on success. See
+    /// `Redsync::retry_backoff`. Disabled by default.
+    pub fn adaptive_retry(mut self, adaptive_retry: bool) -> Self {
+        self.adaptive_retry = adaptive_retry;
+        self
+    }
+
+    /// `single_flight` sets how concurrent lock calls on the same resource within the process
+    /// behave. Defaults to `SingleFlight::Disabled`.
+    pub fn single_flight(mut self, single_flight: SingleFlight) -> Self {
+        self.single_flight = single_flight;
+        self
+    }
+
+    /// `dynamic_drift` makes validity windows use the drift factor measured by
+    /// `Redsync::calibrate_drift`, once available, instead of the static drift factor.
+    pub fn dynamic_drift(mut self, dynamic_drift: bool) -> Self {
+        self.dynamic_drift = dynamic_drift;
+        self
+    }
+
+    /// `record_version` sets the format of the values written at lock keys. Only switch to a newer
+    /// version once every service sharing the locks can decode it.
+    pub fn record_version(mut self, record_version: RecordVersion) -> Self {
+        self.record_version = record_version;
+        self
+    }
+
+    /// `interop` makes the manager wire-compatible with Go redsync and node-redlock, so that
+    /// services written against any of them can contend for the same resources. Keys are the
+    /// bare resource names, values are opaque tokens compared byte for byte, and locks are taken
+    /// with `SET NX PX`, extended by resetting their expiry to the new TTL while the token still
+    /// matches, and released by deleting the key while the token still matches.
+    ///
+    /// Enabling it overrides `record_version` with `RecordVersion::V1`, as the other
+    /// implementations cannot decode tagged records. Resource rules that normalize names must
+    /// produce the names used by every other service.
+    pub fn interop(mut self, interop: bool) -> Self {
+        self.interop = interop;
+        self
+    }
+
+    /// `warm_connections` makes `build` ping every instance, so that connections are established
+    /// before the first lock attempt. Unreachable instances are ignored.
+    pub fn warm_connections(mut self, warm_connections: bool) -> Self {
+        self.warm_connections = warm_connections;
+        self
+    }
+
+    /// `policy` sets a `LockPolicy` consulted before each lock acquisition. Rejected
+    /// acquisitions fail with `RedsyncError::PolicyViolation`.
+    pub fn policy<P: LockPolicy + 'static>(mut self, policy: P) -> Self {
+        self.policy = Some(Box::new(policy));
+        self
+    }
+
+    /// `stats` records acquisition, contention and hold time statistics per resource in Redis,
+    /// sampling a `sample_rate` fraction of events. Read them with `Redsync::stats`.
+    pub fn stats(mut self, sample_rate: f64) -> Self {
+        self.stats_sample_rate = Some(sample_rate.clamp(0.0, 1.0));
+        self
+    }
+
+    /// `audit_log` appends an event to the `redsync:audit` Redis stream for every lock and unlock,
+    /// recording `identity`, the resource, the time and the outcome, for audit and compliance
+    /// purposes. The stream is trimmed to about `retention` events. Read it with
+    /// `Redsync::audit_tail`.
+    pub fn audit_log(mut self, identity: &str, retention: usize) -> Self {
+        self.audit = Some(AuditLog {
+            identity: String::from(identity),
+            retention,
+        });
+        self
+    }
+
+    /// `short_circuit` sets when lock and extend attempts stop contacting the remaining
+    /// instances. Defaults to `ShortCircuit::Disabled`.
+    pub fn short_circuit(mut self, short_circuit: ShortCircuit) -> Self {
+        self.short_circuit = short_circuit;
+        self
+    }
+
+    /// `pipelined` makes lock and extend attempts and unlocks send their call to every instance
+    /// before waiting on any reply, with `Instance::send_acquire`, `send_extend` and
+    /// `send_release`, so that the round trips overlap instead of adding up. Attempts then wait
+    /// for every reply, so `short_circuit` no longer applies to them. Attempts on a single
+    /// instance have no round trips to overlap, and are not pipelined. Disabled by default.
+    pub fn pipelined(mut self, pipelined: bool) -> Self {
+        self.pipelined = pipelined;
+        self
+    }
+
+    /// `partial_extend` sets how extends that some instances rejected are handled. Defaults to
+    /// `PartialExtend::Report`.
+    pub fn partial_extend(mut self, partial_extend: PartialExtend) -> Self {
+        self.partial_extend = partial_extend;
+        self
+    }
+
+    /// `rollback` sets whether locks granted during failed attempts are released. Defaults to
+    /// `RollbackPolicy::Release`.
+    pub fn rollback(mut self, rollback: RollbackPolicy) -> Self {
+        self.rollback = rollback;
+        self
+    }
+
+    /// `quota` limits the lock usage of every resource starting with `prefix`. Locks exceeding
+    /// the quota fail with `RedsyncError::QuotaExceeded`.
+    pub fn quota(mut self, prefix: &str, quota: Quota) -> Self {
+        self.quotas.push((String::from(prefix), quota));
+        self
+    }
+
+    /// `resource_rules` validates and normalizes resource names with `rules` before locking
+    /// them. Invalid names, including those that are not valid UTF-8, fail with
+    /// `RedsyncError::InvalidResourceName`.
+    pub fn resource_rules(mut self, rules: ResourceRules) -> Self {
+        self.resource_rules = Some(rules);
+        self
+    }
+
+    /// `pause_flag` shares `paused` as the manager's maintenance switch, so that pausing any
+    /// manager holding the same flag pauses all of them. See `Redsync::pause`.
+    pub fn pause_flag(mut self, paused: Arc<AtomicBool>) -> Self {
+        self.paused = paused;
+        self
+    }
+
+    /// `journal` records every lock acquired, extended and released by the manager in
+    /// `journal`, so that they can be recovered with `Redsync::recover` after a crash. Journal
+    /// failures do not fail the lock operations themselves.
+    pub fn journal<J: LockJournal + 'static>(mut self, journal: J) -> Self {
+        self.journal = Some(Box::new(journal));
+        self
+    }
+
+    /// `sequences` numbers each acquisition of a resource with a monotonically increasing
+    /// sequence, exposed as `Lock::sequence`. This costs two extra round trips per lock.
+    pub fn sequences(mut self, sequences: bool) -> Self {
+        self.sequences = sequences;
+        self
+    }
+
+    /// `durability` counts an instance's vote for a lock only once `durability.replicas` of its
+    /// replicas have acknowledged it. Instances whose replicas do not acknowledge the lock in
+    /// time fail with `RedsyncError::ReplicationUnconfirmed`.
+    pub fn durability(mut self, durability: Durability) -> Self {
+        self.durability = Some(durability);
+        self
+    }
+
+    /// `on_config_warning` calls `hook` with each configuration that violates Redlock
+    /// assumptions: those detectable from the configuration alone when the manager is built, and
+    /// those depending on instance latency after the first successful lock.
+    pub fn on_config_warning<F: Fn(&ConfigWarning) + Send + Sync + 'static>(
+        mut self,
+        hook: F,
+    ) -> Self {
+        self.config_hook = Some(Box::new(hook));
+        self
+    }
+
+    /// `starvation_monitor` calls `hook` once for each `lock` call that is still waiting for the
+    /// resource after `threshold`, to help debug resources that some callers rarely win.
+    pub fn starvation_monitor<F: Fn(&Starvation) + Send + Sync + 'static>(
+        mut self,
+        threshold: Duration,
+        hook: F,
+    ) -> Self {
+        self.starvation = Some((threshold, Box::new(hook)));
+        self
+    }
+
+    /// `sleep_fn` sets the function used to wait between attempts, in place of
+    /// `std::thread::sleep`, e.g. to advance a test clock or yield to a custom scheduler.
+    pub fn sleep_fn<F: Fn(Duration) + Send + Sync + 'static>(mut self, sleep: F) -> Self {
+        self.sleep = Some(Box::new(sleep));
+        self
+    }
+
+    /// `verify_on_build` makes `try_build` ping every instance and fail if any is unreachable.
+    pub fn verify_on_build(mut self, verify_on_build: bool) -> Self {
+        self.verify_on_build = verify_on_build;
+        self
+    }
+
+    pub fn try_build(self) -> Result<Redsync<I>, RedsyncError> {
+        if self.verify_on_build {
+            ping_cluster(&self.cluster)?;
+        }
+
+        Ok(self.build())
+    }
+
     pub fn build(self) -> Redsync<I> {
-        let quorum = (self.cluster.len() as u32) / 2 + 1;
+        if self.warm_connections {
+            let _ = ping_cluster(&self.cluster);
+        }
+
+        if let Some(hook) = &self.config_hook {
+            static_warnings(self.cluster.len()).iter().for_each(hook);
+        }
+
+        let quorum = quorum_for(self.cluster.len()) as u32;
         let retry_jitter = self.retry_delay.as_millis() as f64 * 0.5;
+        let drift = DriftEstimator::new(self.cluster.len());
+        let release_pool = WorkerPool::new(RELEASE_THREAD_NAME, self.cluster.len());
 
         Redsync {
             cluster: self.cluster,
             quorum,
+            failure_domains: self.failure_domains,
+            domain_quorum: self.domain_quorum,
             retry_count: self.retry_count,
             retry_delay: self.retry_delay,
+            extend_retry_count: self.extend_retry_count,
+            extend_retry_delay: self.extend_retry_delay,
             retry_jitter,
-            drift_factor: 0.01,
+            default_ttl: self.default_ttl,
+            drift_factor: DEFAULT_DRIFT_FACTOR,
+            dynamic_drift: self.dynamic_drift,
+            record_version: if self.interop {
+                RecordVersion::V1
+            } else {
+                self.record_version
+            },
+            interop: self.interop,
+            drift,
+            backoff: self.adaptive_retry.then(AdaptiveBackoff::new),
+            single_flight: self.single_flight,
+            short_circuit: self.short_circuit,
+            pipelined: self.pipelined,
+            partial_extend: self.partial_extend,
+            rollback: self.rollback,
+            inflight: InFlight::new(),
+            waiters: Waiters::new(),
+            policy: self.policy,
+            stats_sample_rate: self.stats_sample_rate,
+            audit: self.audit,
+            quotas: self.quotas,
+            resource_rules: self.resource_rules,
+            paused: self.paused,
+            config_hook: self.config_hook,
+            starvation: self.starvation,
+            latency_checked: AtomicBool::new(false),
+            journal: self.journal,
+            sequences: self.sequences,
+            durability: self.durability,
+            error_limit: self.error_limit,
+            config_snapshot: self.config_snapshot,
+            sleep: self.sleep,
+            release_pool,
+            id: next_manager_id(),
         }
     }
 }
 
-#[cfg(test)]
+#[cfg(feature = "redis")]
+impl RedsyncBuilder<RedisInstance> {
+    /// `from_urls` creates a builder for a cluster of RedisInstances, one per URL. Every invalid
+    /// URL is reported in the returned error.
+    pub fn from_urls(urls: &[&str]) -> Result<Self, RedsyncError> {
+        let mut cluster = Vec::with_capacity(urls.len());
+        let mut errors = MultiError::new();
+
+        for (i, url) in urls.iter().enumerate() {
+            match RedisInstance::new(*url) {
+                Ok(instance) => cluster.push(instance),
+                Err(e) => errors.push(RedsyncError::InstanceError(i, Box::new(e))),
+            }
+        }
+
+        if !errors.is_empty() {
+            return Err(RedsyncError::InvalidUrls(errors));
+        }
+
+        Ok(Self::new(cluster))
+    }
+}
+
+#[cfg(all(test, feature = "redis"))]
 mod tests {
     use super::*;
-    use crate::errors::RedsyncError;
-    use crate::instance::RedisInstance;
 
     #[test]
     fn default() -> Result<(), RedsyncError> {
@@ -57,14 +450,70 @@ mod tests {
 
         assert_eq!(redsync.cluster.len(), 1);
         assert_eq!(redsync.quorum, 1);
+        assert!(redsync.failure_domains.is_empty());
+        assert_eq!(redsync.domain_quorum, 0);
         assert_eq!(redsync.retry_count, 3);
         assert_eq!(redsync.retry_delay, Duration::from_millis(200));
         assert_eq!(redsync.retry_jitter, 100.0);
+        assert_eq!(redsync.extend_retry_count, 1);
+        assert_eq!(redsync.extend_retry_delay, Duration::from_millis(20));
         assert_eq!(redsync.drift_factor, 0.01);
+        assert_eq!(redsync.default_ttl, Duration::from_secs(8));
+        assert!(redsync.backoff.is_none());
+        assert!(!redsync.dynamic_drift);
+        assert_eq!(redsync.record_version, RecordVersion::V1);
+        assert!(!redsync.interop);
+        assert_eq!(redsync.single_flight, SingleFlight::Disabled);
+        assert_eq!(redsync.short_circuit, ShortCircuit::Disabled);
+        assert!(!redsync.pipelined);
+        assert_eq!(redsync.partial_extend, PartialExtend::Report);
+        assert_eq!(redsync.rollback, RollbackPolicy::Release);
+        assert!(redsync.policy.is_none());
+        assert!(redsync.stats_sample_rate.is_none());
+        assert!(redsync.audit.is_none());
+        assert!(redsync.quotas.is_empty());
+        assert!(redsync.resource_rules.is_none());
+        assert!(!redsync.is_paused());
+        assert!(redsync.config_hook.is_none());
+        assert!(redsync.starvation.is_none());
+        assert!(redsync.journal.is_none());
+        assert!(!redsync.sequences);
+        assert!(redsync.durability.is_none());
+        assert_eq!(redsync.error_limit, DEFAULT_ERROR_LIMIT);
+        assert!(!redsync.config_snapshot);
+        assert!(redsync.sleep.is_none());
 
         Ok(())
     }
 
+    #[test]
+    fn from_urls() -> Result<(), RedsyncError> {
+        let redsync = RedsyncBuilder::from_urls(&[
+            "redis://127.0.0.1:6379",
+            "redis://127.0.0.1:6389",
+            "redis://127.0.0.1:6399",
+        ])?
+        .build();
+
+        assert_eq!(redsync.cluster.len(), 3);
+        assert_eq!(redsync.quorum, 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn from_urls_error() {
+        let attempt = RedsyncBuilder::from_urls(&["redis://127.0.0.1:6379", "127.0.0.1", "?"]);
+
+        match attempt {
+            Err(RedsyncError::InvalidUrls(errors)) => {
+                assert_eq!(errors.len(), 2);
+                assert!(matches!(errors[0], RedsyncError::InstanceError(1, _)));
+            }
+            _ => panic!("expected InvalidUrls error"),
+        }
+    }
+
     #[test]
     fn retry_count() -> Result<(), RedsyncError> {
         let cluster = vec![RedisInstance::new("redis://127.0.0.1:6379")?];
@@ -96,4 +545,63 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn default_ttl() -> Result<(), RedsyncError> {
+        let cluster = vec![RedisInstance::new("redis://127.0.0.1:6379")?];
+        let redsync = RedsyncBuilder::new(cluster)
+            .default_ttl(Duration::from_secs(30))
+            .build();
+
+        assert_eq!(redsync.default_ttl, Duration::from_secs(30));
+
+        Ok(())
+    }
+
+    #[test]
+    fn adaptive_retry() -> Result<(), RedsyncError> {
+        let cluster = vec![RedisInstance::new("redis://127.0.0.1:6379")?];
+        let redsync = RedsyncBuilder::new(cluster).adaptive_retry(true).build();
+
+        assert!(redsync.backoff.is_some());
+
+        Ok(())
+    }
+
+    #[test]
+    fn single_flight() -> Result<(), RedsyncError> {
+        let cluster = vec![RedisInstance::new("redis://127.0.0.1:6379")?];
+        let redsync = RedsyncBuilder::new(cluster)
+            .single_flight(SingleFlight::Reject)
+            .build();
+
+        assert_eq!(redsync.single_flight, SingleFlight::Reject);
+
+        Ok(())
+    }
+
+    #[test]
+    fn verify_on_build() -> Result<(), RedsyncError> {
+        let cluster = vec![RedisInstance::new("redis://127.0.0.1:1")?];
+        let attempt = RedsyncBuilder::new(cluster)
+            .verify_on_build(true)
+            .try_build();
+
+        assert!(matches!(
+            attempt,
+            Err(RedsyncError::UnreachableInstances { .. })
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn dynamic_drift() -> Result<(), RedsyncError> {
+        let cluster = vec![RedisInstance::new("redis://127.0.0.1:6379")?];
+        let redsync = RedsyncBuilder::new(cluster).dynamic_drift(true).build();
+
+        assert!(redsync.dynamic_drift);
+
+        Ok(())
+    }
 }
@@ -0,0 +1,356 @@
+use crate::audit::AuditEvent;
+use crate::durability::Durability;
+use crate::errors::RedsyncError;
+use crate::instance::Instance;
+use crate::preflight::NodeDiagnostics;
+use crate::redsync::Lock;
+use crate::reservation::Reservation;
+
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use rand::{thread_rng, Rng};
+
+/// `ChaosInstance` wraps an Instance and injects delays, errors and wrong responses at random.
+pub struct ChaosInstance<I: Instance> {
+    inner: I,
+    delay_probability: f64,
+    delay: Duration,
+    error_probability: f64,
+    wrong_response_probability: f64,
+}
+
+enum Op {
+    Acquire,
+    Extend,
+    Release,
+    Ttl,
+    GuardedSet,
+}
+
+impl<I: Instance> ChaosInstance<I> {
+    pub fn new(inner: I) -> Self {
+        Self {
+            inner,
+            delay_probability: 0.0,
+            delay: Duration::from_millis(0),
+            error_probability: 0.0,
+            wrong_response_probability: 0.0,
+        }
+    }
+
+    pub fn delay(mut self, probability: f64, delay: Duration) -> Self {
+        self.delay_probability = probability.clamp(0.0, 1.0);
+        self.delay = delay;
+        self
+    }
+
+    pub fn error(mut self, probability: f64) -> Self {
+        self.error_probability = probability.clamp(0.0, 1.0);
+        self
+    }
+
+    pub fn wrong_response(mut self, probability: f64) -> Self {
+        self.wrong_response_probability = probability.clamp(0.0, 1.0);
+        self
+    }
+
+    pub fn inner(&self) -> &I {
+        &self.inner
+    }
+
+    fn inject<T: Default>(
+        &self,
+        op: Op,
+        lock: &Lock,
+        call: impl FnOnce(&I, &Lock) -> Result<T, RedsyncError>,
+    ) -> Result<T, RedsyncError> {
+        let mut rng = thread_rng();
+
+        if rng.gen_bool(self.delay_probability) {
+            thread::sleep(self.delay);
+        }
+
+        if rng.gen_bool(self.error_probability) {
+            return Err(RedsyncError::InjectedFault);
+        }
+
+        let result = call(&self.inner, lock);
+
+        if rng.gen_bool(self.wrong_response_probability) {
+            return match (op, result) {
+                (Op::Acquire, Ok(_)) => Err(RedsyncError::ResourceLocked),
+                (_, Ok(_)) => Err(RedsyncError::InvalidLease),
+                (_, Err(_)) => Ok(T::default()),
+            };
+        }
+
+        result
+    }
+}
+
+impl<I: Instance> Instance for ChaosInstance<I> {
+    fn acquire(&self, lock: &Lock) -> Result<(), RedsyncError> {
+        self.inject(Op::Acquire, lock, I::acquire)
+    }
+
+    fn acquire_replicated(&self, lock: &Lock, durability: &Durability) -> Result<(), RedsyncError> {
+        self.inject(Op::Acquire, lock, |inner, lock| {
+            inner.acquire_replicated(lock, durability)
+        })
+    }
+
+    fn acquire_if_version(
+        &self,
+        lock: &Lock,
+        version: u64,
+        durability: Option<&Durability>,
+    ) -> Result<(), RedsyncError> {
+        self.inject(Op::Acquire, lock, |inner, lock| {
+            inner.acquire_if_version(lock, version, durability)
+        })
+    }
+
+    fn extend(&self, lock: &Lock) -> Result<(), RedsyncError> {
+        self.inject(Op::Extend, lock, I::extend)
+    }
+
+    fn reacquire(&self, lock: &Lock) -> Result<(), RedsyncError> {
+        self.inject(Op::Acquire, lock, I::reacquire)
+    }
+
+    fn release(&self, lock: &Lock) -> Result<(), RedsyncError> {
+        self.inject(Op::Release, lock, I::release)
+    }
+
+    fn ttl(&self, lock: &Lock) -> Result<Duration, RedsyncError> {
+        self.inject(Op::Ttl, lock, I::ttl)
+    }
+
+    fn guarded_set(&self, lock: &Lock, key: &str, value: &str) -> Result<(), RedsyncError> {
+        self.inject(Op::GuardedSet, lock, |inner, lock| {
+            inner.guarded_set(lock, key, value)
+        })
+    }
+
+    fn peek(&self, resource: &[u8]) -> Result<Option<Duration>, RedsyncError> {
+        self.inner.peek(resource)
+    }
+
+    fn ping(&self) -> Result<(), RedsyncError> {
+        self.inner.ping()
+    }
+
+    fn diagnostics(&self) -> Result<NodeDiagnostics, RedsyncError> {
+        self.inner.diagnostics()
+    }
+
+    fn time(&self) -> Result<SystemTime, RedsyncError> {
+        self.inner.time()
+    }
+
+    fn force_release_prefix(
+        &self,
+        prefix: &str,
+        dry_run: bool,
+        bare_tokens: bool,
+    ) -> Result<Vec<String>, RedsyncError> {
+        self.inner
+            .force_release_prefix(prefix, dry_run, bare_tokens)
+    }
+
+    fn incr(&self, key: &str, delta: i64, expiry: Duration) -> Result<i64, RedsyncError> {
+        self.inner.incr(key, delta, expiry)
+    }
+
+    fn incr_stats(&self, resource: &[u8], fields: &[(&str, i64)]) -> Result<(), RedsyncError> {
+        self.inner.incr_stats(resource, fields)
+    }
+
+    fn read_stats(&self, resource: &[u8]) -> Result<Vec<(String, i64)>, RedsyncError> {
+        self.inner.read_stats(resource)
+    }
+
+    fn advance_sequence(&self, lock: &Lock, floor: i64, step: i64) -> Result<i64, RedsyncError> {
+        self.inner.advance_sequence(lock, floor, step)
+    }
+
+    fn append_audit(
+        &self,
+        fields: &[(&str, String)],
+        retention: usize,
+    ) -> Result<(), RedsyncError> {
+        self.inner.append_audit(fields, retention)
+    }
+
+    fn read_audit(&self, count: usize) -> Result<Vec<AuditEvent>, RedsyncError> {
+        self.inner.read_audit(count)
+    }
+
+    fn reserve(&self, reservation: &Reservation) -> Result<(), RedsyncError> {
+        self.inner.reserve(reservation)
+    }
+
+    fn cancel_reservation(&self, reservation: &Reservation) -> Result<(), RedsyncError> {
+        self.inner.cancel_reservation(reservation)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::matches;
+    use std::time::Instant;
+
+    struct OkInstance;
+
+    impl Instance for OkInstance {
+        fn acquire(&self, _lock: &Lock) -> Result<(), RedsyncError> {
+            Ok(())
+        }
+
+        fn acquire_replicated(
+            &self,
+            _lock: &Lock,
+            _durability: &Durability,
+        ) -> Result<(), RedsyncError> {
+            Ok(())
+        }
+
+        fn extend(&self, _lock: &Lock) -> Result<(), RedsyncError> {
+            Ok(())
+        }
+
+        fn reacquire(&self, _lock: &Lock) -> Result<(), RedsyncError> {
+            Ok(())
+        }
+
+        fn release(&self, _lock: &Lock) -> Result<(), RedsyncError> {
+            Ok(())
+        }
+
+        fn ttl(&self, lock: &Lock) -> Result<Duration, RedsyncError> {
+            Ok(lock.ttl)
+        }
+
+        fn guarded_set(&self, _lock: &Lock, _key: &str, _value: &str) -> Result<(), RedsyncError> {
+            Ok(())
+        }
+
+        fn peek(&self, _resource: &[u8]) -> Result<Option<Duration>, RedsyncError> {
+            Ok(None)
+        }
+
+        fn ping(&self) -> Result<(), RedsyncError> {
+            Ok(())
+        }
+
+        fn diagnostics(&self) -> Result<NodeDiagnostics, RedsyncError> {
+            Ok(NodeDiagnostics::default())
+        }
+
+        fn time(&self) -> Result<SystemTime, RedsyncError> {
+            Ok(SystemTime::now())
+        }
+
+        fn force_release_prefix(
+            &self,
+            _prefix: &str,
+            _dry_run: bool,
+            _bare_tokens: bool,
+        ) -> Result<Vec<String>, RedsyncError> {
+            Ok(vec![])
+        }
+
+        fn incr(&self, _key: &str, delta: i64, _expiry: Duration) -> Result<i64, RedsyncError> {
+            Ok(delta)
+        }
+
+        fn incr_stats(
+            &self,
+            _resource: &[u8],
+            _fields: &[(&str, i64)],
+        ) -> Result<(), RedsyncError> {
+            Ok(())
+        }
+
+        fn read_stats(&self, _resource: &[u8]) -> Result<Vec<(String, i64)>, RedsyncError> {
+            Ok(vec![])
+        }
+
+        fn advance_sequence(
+            &self,
+            _lock: &Lock,
+            floor: i64,
+            step: i64,
+        ) -> Result<i64, RedsyncError> {
+            Ok(floor.max(step))
+        }
+
+        fn append_audit(
+            &self,
+            _fields: &[(&str, String)],
+            _retention: usize,
+        ) -> Result<(), RedsyncError> {
+            Ok(())
+        }
+
+        fn read_audit(&self, _count: usize) -> Result<Vec<AuditEvent>, RedsyncError> {
+            Ok(vec![])
+        }
+
+        fn reserve(&self, _reservation: &Reservation) -> Result<(), RedsyncError> {
+            Ok(())
+        }
+
+        fn cancel_reservation(&self, _reservation: &Reservation) -> Result<(), RedsyncError> {
+            Ok(())
+        }
+    }
+
+    fn lock() -> Lock {
+        Lock::new("test", "1", Duration::from_millis(500), Instant::now(), 0)
+    }
+
+    #[test]
+    fn passthrough() {
+        let instance = ChaosInstance::new(OkInstance);
+
+        assert!(instance.acquire(&lock()).is_ok());
+        assert!(instance.extend(&lock()).is_ok());
+        assert!(instance.release(&lock()).is_ok());
+    }
+
+    #[test]
+    fn delay() {
+        let instance = ChaosInstance::new(OkInstance).delay(1.0, Duration::from_millis(50));
+
+        let start = Instant::now();
+        assert!(instance.acquire(&lock()).is_ok());
+        assert!(start.elapsed() >= Duration::from_millis(50));
+    }
+
+    #[test]
+    fn error() {
+        let instance = ChaosInstance::new(OkInstance).error(1.0);
+
+        let attempt = instance.acquire(&lock());
+        assert!(matches!(attempt, Err(RedsyncError::InjectedFault)));
+
+        let attempts = instance.acquire_many(&[lock(), lock()]);
+        assert!(attempts
+            .iter()
+            .all(|a| matches!(a, Err(RedsyncError::InjectedFault))));
+    }
+
+    #[test]
+    fn wrong_response() {
+        let instance = ChaosInstance::new(OkInstance).wrong_response(1.0);
+
+        let attempt = instance.acquire(&lock());
+        assert!(matches!(attempt, Err(RedsyncError::ResourceLocked)));
+
+        let attempt = instance.release(&lock());
+        assert!(matches!(attempt, Err(RedsyncError::InvalidLease)));
+    }
+}
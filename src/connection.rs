@@ -0,0 +1,209 @@
+use crate::errors::RedsyncError;
+use crate::instance::RedisInstance;
+
+use std::time::Duration;
+
+/// `RedisInstanceBuilder` configures a RedisInstance field by field, as an alternative to
+/// `RedisInstance::new` for programmatic setups where assembling a URL is error-prone.
+pub struct RedisInstanceBuilder {
+    host: String,
+    port: u16,
+    db: i64,
+    username: Option<String>,
+    password: Option<String>,
+    tls: bool,
+    insecure: bool,
+    connect_timeout: Option<Duration>,
+    response_timeout: Option<Duration>,
+    client_name: Option<String>,
+    max_lease: Option<Duration>,
+}
+
+impl RedisInstanceBuilder {
+    /// `new` creates a RedisInstanceBuilder for the server at `host`, using port 6379 and
+    /// database 0 by default.
+    pub fn new(host: &str) -> Self {
+        Self {
+            host: String::from(host),
+            port: 6379,
+            db: 0,
+            username: None,
+            password: None,
+            tls: false,
+            insecure: false,
+            connect_timeout: None,
+            response_timeout: None,
+            client_name: None,
+            max_lease: None,
+        }
+    }
+
+    /// `port` sets the port of the Redis server.
+    pub fn port(mut self, port: u16) -> Self {
+        self.port = port;
+        self
+    }
+
+    /// `db` sets the database number to select.
+    pub fn db(mut self, db: i64) -> Self {
+        self.db = db;
+        self
+    }
+
+    /// `username` sets the ACL username to authenticate with.
+    pub fn username(mut self, username: &str) -> Self {
+        self.username = Some(String::from(username));
+        self
+    }
+
+    /// `password` sets the password to authenticate with.
+    pub fn password(mut self, password: &str) -> Self {
+        self.password = Some(String::from(password));
+        self
+    }
+
+    /// `tls` enables TLS. This requires one of the TLS features of the redis crate.
+    pub fn tls(mut self, tls: bool) -> Self {
+        self.tls = tls;
+        self
+    }
+
+    /// `insecure` disables hostname verification for TLS connections.
+    pub fn insecure(mut self, insecure: bool) -> Self {
+        self.insecure = insecure;
+        self
+    }
+
+    /// `connect_timeout` bounds the time taken to establish a connection.
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// `response_timeout` bounds the time spent reading or writing a single command. A connection
+    /// whose command timed out is closed rather than reused, as its reply may still arrive.
+    pub fn response_timeout(mut self, timeout: Duration) -> Self {
+        self.response_timeout = Some(timeout);
+        self
+    }
+
+    /// `client_name` sets the name reported by CLIENT LIST for each connection.
+    pub fn client_name(mut self, name: &str) -> Self {
+        self.client_name = Some(String::from(name));
+        self
+    }
+
+    /// `max_lease` sets the maximum lease, as with `RedisInstance::max_lease`.
+    pub fn max_lease(mut self, max_lease: Duration) -> Self {
+        self.max_lease = Some(max_lease);
+        self
+    }
+
+    /// `build` validates the configuration and creates the RedisInstance.
+    pub fn build(self) -> Result<RedisInstance, RedsyncError> {
+        self.validate()?;
+
+        let addr = if self.tls {
+            redis::ConnectionAddr::TcpTls {
+                host: self.host,
+                port: self.port,
+                insecure: self.insecure,
+                tls_params: None,
+            }
+        } else {
+            redis::ConnectionAddr::Tcp(self.host, self.port)
+        };
+        let info = redis::ConnectionInfo {
+            addr,
+            redis: redis::RedisConnectionInfo {
+                db: self.db,
+                username: self.username,
+                password: self.password,
+            },
+        };
+
+        let mut instance = RedisInstance::new(info)?;
+        instance.connect_timeout = self.connect_timeout;
+        instance.response_timeout = self.response_timeout;
+        instance.client_name = self.client_name;
+        if let Some(max_lease) = self.max_lease {
+            instance = instance.max_lease(max_lease);
+        }
+
+        Ok(instance)
+    }
+
+    fn validate(&self) -> Result<(), RedsyncError> {
+        let invalid = |msg: &str| Err(RedsyncError::InvalidConfig(String::from(msg)));
+
+        if self.host.is_empty() {
+            return invalid("host must not be empty");
+        }
+        if self.port == 0 {
+            return invalid("port must not be 0");
+        }
+        if self.db < 0 {
+            return invalid("db must not be negative");
+        }
+        if self.username.is_some() && self.password.is_none() {
+            return invalid("username requires a password");
+        }
+        if self.insecure && !self.tls {
+            return invalid("insecure requires tls");
+        }
+        if self.connect_timeout == Some(Duration::ZERO)
+            || self.response_timeout == Some(Duration::ZERO)
+        {
+            return invalid("timeouts must not be zero");
+        }
+        if let Some(name) = &self.client_name {
+            if name.is_empty() || name.chars().any(|c| c.is_whitespace()) {
+                return invalid("client name must be non-empty and contain no whitespace");
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn build() {
+        let instance = RedisInstanceBuilder::new("127.0.0.1")
+            .port(6380)
+            .db(2)
+            .username("user")
+            .password("secret")
+            .connect_timeout(Duration::from_millis(500))
+            .client_name("redsync")
+            .build();
+
+        assert!(instance.is_ok());
+        let instance = instance.unwrap();
+        assert_eq!(instance.connect_timeout, Some(Duration::from_millis(500)));
+        assert_eq!(instance.client_name, Some(String::from("redsync")));
+    }
+
+    #[test]
+    fn build_invalid() {
+        let cases = vec![
+            RedisInstanceBuilder::new(""),
+            RedisInstanceBuilder::new("127.0.0.1").port(0),
+            RedisInstanceBuilder::new("127.0.0.1").db(-1),
+            RedisInstanceBuilder::new("127.0.0.1").username("user"),
+            RedisInstanceBuilder::new("127.0.0.1").insecure(true),
+            RedisInstanceBuilder::new("127.0.0.1").response_timeout(Duration::ZERO),
+            RedisInstanceBuilder::new("127.0.0.1").client_name("my client"),
+        ];
+
+        for builder in cases {
+            assert!(matches!(
+                builder.build(),
+                Err(RedsyncError::InvalidConfig(_))
+            ));
+        }
+    }
+}
@@ -0,0 +1,151 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// `ResourceContention` describes the local callers currently blocked on a resource.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ResourceContention {
+    pub resource: Vec<u8>,
+    pub waiters: usize,
+    pub longest_wait: Duration,
+}
+
+/// `Starvation` describes a caller that has been waiting to lock a resource for longer than the
+/// starvation threshold set with `RedsyncBuilder::starvation_monitor`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Starvation {
+    pub resource: Vec<u8>,
+    pub waited: Duration,
+    /// `attempts` is the number of lock attempts the caller has made so far.
+    pub attempts: u32,
+    /// `waiters` is the number of local callers waiting on the resource, including this one.
+    pub waiters: usize,
+}
+
+/// `StarvationHook` is called with each `Starvation` detected by a manager.
+pub(crate) type StarvationHook = Box<dyn Fn(&Starvation) + Send + Sync>;
+
+/// `Waiters` tracks the in-process callers currently waiting to lock each resource.
+#[derive(Debug, Default)]
+pub(crate) struct Waiters {
+    waiting: Mutex<HashMap<Vec<u8>, HashMap<u64, Instant>>>,
+    next_id: Mutex<u64>,
+}
+
+pub(crate) struct WaiterGuard<'a> {
+    waiters: &'a Waiters,
+    resource: Vec<u8>,
+    id: u64,
+}
+
+impl Waiters {
+    pub(crate) fn new() -> Self {
+        Default::default()
+    }
+
+    pub(crate) fn enter(&self, resource: &[u8]) -> WaiterGuard<'_> {
+        let id = {
+            let mut next_id = self.next_id.lock().unwrap();
+            *next_id += 1;
+            *next_id
+        };
+
+        self.waiting
+            .lock()
+            .unwrap()
+            .entry(resource.to_vec())
+            .or_default()
+            .insert(id, Instant::now());
+
+        WaiterGuard {
+            waiters: self,
+            resource: resource.to_vec(),
+            id,
+        }
+    }
+
+    /// `count` returns the number of callers waiting to lock `resource`.
+    pub(crate) fn count(&self, resource: &[u8]) -> usize {
+        self.waiting
+            .lock()
+            .unwrap()
+            .get(resource)
+            .map_or(0, HashMap::len)
+    }
+
+    pub(crate) fn report(&self) -> Vec<ResourceContention> {
+        let now = Instant::now();
+        let mut report: Vec<_> = self
+            .waiting
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(resource, waiters)| ResourceContention {
+                resource: resource.clone(),
+                waiters: waiters.len(),
+                longest_wait: waiters
+                    .values()
+                    .map(|since| now.saturating_duration_since(*since))
+                    .max()
+                    .unwrap_or_default(),
+            })
+            .collect();
+
+        report.sort_by(|a, b| b.waiters.cmp(&a.waiters).then(a.resource.cmp(&b.resource)));
+        report
+    }
+}
+
+impl Drop for WaiterGuard<'_> {
+    fn drop(&mut self) {
+        let mut waiting = self.waiters.waiting.lock().unwrap();
+        if let Some(waiters) = waiting.get_mut(&self.resource) {
+            waiters.remove(&self.id);
+            if waiters.is_empty() {
+                waiting.remove(&self.resource);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn report() {
+        let waiters = Waiters::new();
+        let a1 = waiters.enter(b"a");
+        let _a2 = waiters.enter(b"a");
+        let _b1 = waiters.enter(b"b");
+
+        let report = waiters.report();
+        assert_eq!(report.len(), 2);
+        assert_eq!(report[0].resource, b"a");
+        assert_eq!(report[0].waiters, 2);
+        assert_eq!(report[1].resource, b"b");
+        assert_eq!(report[1].waiters, 1);
+
+        drop(a1);
+        let report = waiters.report();
+        assert_eq!(report[0].waiters, 1);
+    }
+
+    #[test]
+    fn count() {
+        let waiters = Waiters::new();
+        let _a1 = waiters.enter(b"a");
+        let _a2 = waiters.enter(b"a");
+
+        assert_eq!(waiters.count(b"a"), 2);
+        assert_eq!(waiters.count(b"b"), 0);
+    }
+
+    #[test]
+    fn report_empty() {
+        let waiters = Waiters::new();
+        drop(waiters.enter(b"a"));
+
+        assert!(waiters.report().is_empty());
+    }
+}
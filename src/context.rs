@@ -0,0 +1,48 @@
+use crate::errors::RedsyncError;
+use crate::instance::Instance;
+use crate::redsync::{Lock, Redsync};
+use crate::ttl::IntoLeaseTtl;
+
+use std::time::{Duration, Instant};
+
+/// `ABORT_FRACTION` is the fraction of a lock's TTL below which `LockContext::should_abort`
+/// advises giving up the critical section.
+const ABORT_FRACTION: f64 = 0.1;
+
+/// `LockContext` is passed to the closure run by `Redsync::with_lock`, letting code inside the
+/// critical section cooperate with the expiry of its lease.
+pub struct LockContext<'a, I: Instance> {
+    redsync: &'a Redsync<I>,
+    lock: Lock,
+}
+
+impl<'a, I: Instance> LockContext<'a, I> {
+    pub(crate) fn new(redsync: &'a Redsync<I>, lock: Lock) -> Self {
+        Self { redsync, lock }
+    }
+
+    /// `remaining` returns how long the lock remains valid.
+    pub fn remaining(&self) -> Duration {
+        self.lock.expiry.saturating_duration_since(Instant::now())
+    }
+
+    /// `should_abort` returns true once less than a tenth of the lock's TTL remains, after which
+    /// work should stop or the lock should be extended.
+    pub fn should_abort(&self) -> bool {
+        self.remaining() < self.lock.ttl.mul_f64(ABORT_FRACTION)
+    }
+
+    /// `extend` resets the lock's TTL to `ttl`.
+    pub fn extend(&mut self, ttl: impl IntoLeaseTtl) -> Result<(), RedsyncError> {
+        self.lock = self.redsync.extend(&self.lock, ttl)?;
+        Ok(())
+    }
+
+    pub fn lock(&self) -> &Lock {
+        &self.lock
+    }
+
+    pub(crate) fn into_lock(self) -> Lock {
+        self.lock
+    }
+}
@@ -0,0 +1,137 @@
+#[cfg(any(feature = "redis", feature = "resp", test))]
+use crate::errors::RedsyncError;
+
+use std::error::Error;
+#[cfg(any(feature = "redis", feature = "resp", test))]
+use std::sync::Mutex;
+#[cfg(any(feature = "redis", feature = "resp", test))]
+use std::time::Duration;
+use std::time::Instant;
+
+/// `Credentials` holds an authentication token and when it expires, if ever.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Credentials {
+    pub username: Option<String>,
+    pub password: String,
+    pub expiry: Option<Instant>,
+}
+
+/// `CredentialsProvider` fetches short-lived credentials, e.g. cloud IAM auth tokens.
+pub trait CredentialsProvider: Send + Sync {
+    fn credentials(&self) -> Result<Credentials, Box<dyn Error + Send + Sync>>;
+}
+
+impl<F> CredentialsProvider for F
+where
+    F: Fn() -> Result<Credentials, Box<dyn Error + Send + Sync>> + Send + Sync,
+{
+    fn credentials(&self) -> Result<Credentials, Box<dyn Error + Send + Sync>> {
+        self()
+    }
+}
+
+/// `CredentialsCache` caches credentials from a provider and refreshes them ahead of expiry.
+#[cfg(any(feature = "redis", feature = "resp", test))]
+pub(crate) struct CredentialsCache {
+    provider: Box<dyn CredentialsProvider>,
+    refresh_margin: Duration,
+    cached: Mutex<Option<Credentials>>,
+}
+
+#[cfg(any(feature = "redis", feature = "resp", test))]
+impl CredentialsCache {
+    pub(crate) fn new(provider: Box<dyn CredentialsProvider>, refresh_margin: Duration) -> Self {
+        Self {
+            provider,
+            refresh_margin,
+            cached: Mutex::new(None),
+        }
+    }
+
+    pub(crate) fn get(&self) -> Result<Credentials, RedsyncError> {
+        let mut cached = self.cached.lock().unwrap();
+
+        let fresh = match &*cached {
+            Some(Credentials {
+                expiry: Some(expiry),
+                ..
+            }) => Instant::now() + self.refresh_margin < *expiry,
+            Some(_) => true,
+            None => false,
+        };
+
+        if !fresh {
+            let credentials = self
+                .provider
+                .credentials()
+                .map_err(|e| RedsyncError::CredentialsError(e.to_string()))?;
+            *cached = Some(credentials);
+        }
+
+        Ok(cached.clone().unwrap())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::Arc;
+
+    fn provider(calls: Arc<AtomicU32>, lifetime: Option<Duration>) -> Box<dyn CredentialsProvider> {
+        Box::new(move || {
+            let n = calls.fetch_add(1, Ordering::SeqCst);
+            Ok(Credentials {
+                username: None,
+                password: format!("token-{}", n),
+                expiry: lifetime.map(|lifetime| Instant::now() + lifetime),
+            })
+        })
+    }
+
+    #[test]
+    fn get_cached() -> Result<(), RedsyncError> {
+        let calls = Arc::new(AtomicU32::new(0));
+        let cache = CredentialsCache::new(
+            provider(calls.clone(), Some(Duration::from_secs(60))),
+            Duration::from_secs(10),
+        );
+
+        assert_eq!(cache.get()?.password, "token-0");
+        assert_eq!(cache.get()?.password, "token-0");
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn get_refresh() -> Result<(), RedsyncError> {
+        let calls = Arc::new(AtomicU32::new(0));
+        let cache = CredentialsCache::new(
+            provider(calls.clone(), Some(Duration::from_secs(5))),
+            Duration::from_secs(10),
+        );
+
+        assert_eq!(cache.get()?.password, "token-0");
+        assert_eq!(cache.get()?.password, "token-1");
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn get_error() {
+        let cache = CredentialsCache::new(
+            Box::new(|| Err("token service unavailable".into())),
+            Duration::from_secs(10),
+        );
+
+        let attempt = cache.get();
+        assert_eq!(
+            attempt,
+            Err(RedsyncError::CredentialsError(String::from(
+                "token service unavailable"
+            )))
+        );
+    }
+}
@@ -0,0 +1,223 @@
+use crate::builder::RedsyncBuilder;
+use crate::errors::RedsyncError;
+use crate::instance::Instance;
+#[cfg(feature = "redis")]
+use crate::instance::RedisInstance;
+use crate::redsync::{next_manager_id, Redsync};
+
+use std::error::Error;
+use std::net::ToSocketAddrs;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+/// `ClusterDiscovery` returns the addresses of the instances currently in the cluster, e.g. from
+/// a service registry or the DNS records of a headless service.
+pub trait ClusterDiscovery: Send + Sync {
+    fn discover(&self) -> Result<Vec<String>, Box<dyn Error + Send + Sync>>;
+}
+
+impl<F> ClusterDiscovery for F
+where
+    F: Fn() -> Result<Vec<String>, Box<dyn Error + Send + Sync>> + Send + Sync,
+{
+    fn discover(&self) -> Result<Vec<String>, Box<dyn Error + Send + Sync>> {
+        self()
+    }
+}
+
+/// `DnsDiscovery` discovers one instance per address that `host` resolves to, such as the pods
+/// behind a Kubernetes headless service. SRV records are not supported.
+pub struct DnsDiscovery {
+    host: String,
+    port: u16,
+}
+
+impl DnsDiscovery {
+    pub fn new(host: &str, port: u16) -> Self {
+        Self {
+            host: String::from(host),
+            port,
+        }
+    }
+}
+
+impl ClusterDiscovery for DnsDiscovery {
+    fn discover(&self) -> Result<Vec<String>, Box<dyn Error + Send + Sync>> {
+        let addrs = (self.host.as_str(), self.port).to_socket_addrs()?;
+        Ok(addrs.map(|addr| format!("redis://{}", addr)).collect())
+    }
+}
+
+type Connect<I> = Box<dyn Fn(&str) -> Result<I, RedsyncError> + Send + Sync>;
+type Configure<I> = Box<dyn Fn(RedsyncBuilder<I>) -> RedsyncBuilder<I> + Send + Sync>;
+
+struct Current<I: Instance> {
+    members: Vec<String>,
+    redsync: Arc<Redsync<I>>,
+    refreshed: Instant,
+}
+
+/// `DiscoveredCluster` maintains a Redsync manager for the cluster returned by a
+/// `ClusterDiscovery`, rebuilding it when the discovered members change.
+///
+/// Locks remain valid across rebuilds, since every manager built for the cluster shares the same
+/// identity, but a lock acquired before a membership change may not be held on a quorum of the new
+/// cluster.
+pub struct DiscoveredCluster<I: Instance> {
+    discovery: Box<dyn ClusterDiscovery>,
+    connect: Connect<I>,
+    configure: Configure<I>,
+    refresh_interval: Duration,
+    current: RwLock<Option<Current<I>>>,
+    id: u64,
+}
+
+impl<I: Instance> DiscoveredCluster<I> {
+    /// `new` creates a DiscoveredCluster that creates an instance for each discovered address
+    /// using `connect`. Call `start` to discover the initial cluster.
+    pub fn new<D, C>(discovery: D, connect: C) -> Self
+    where
+        D: ClusterDiscovery + 'static,
+        C: Fn(&str) -> Result<I, RedsyncError> + Send + Sync + 'static,
+    {
+        Self {
+            discovery: Box::new(discovery),
+            connect: Box::new(connect),
+            configure: Box::new(|builder| builder),
+            refresh_interval: Duration::from_secs(30),
+            current: RwLock::new(None),
+            id: next_manager_id(),
+        }
+    }
+
+    /// `refresh_interval` sets how often the cluster is rediscovered. Defaults to 30 seconds.
+    pub fn refresh_interval(mut self, refresh_interval: Duration) -> Self {
+        self.refresh_interval = refresh_interval;
+        self
+    }
+
+    /// `configure` applies `configure` to the builder of every manager built for the cluster.
+    pub fn configure<F>(mut self, configure: F) -> Self
+    where
+        F: Fn(RedsyncBuilder<I>) -> RedsyncBuilder<I> + Send + Sync + 'static,
+    {
+        self.configure = Box::new(configure);
+        self
+    }
+
+    /// `start` discovers the initial cluster, failing if it cannot be discovered.
+    pub fn start(self) -> Result<Self, RedsyncError> {
+        self.refresh()?;
+        Ok(self)
+    }
+
+    /// `redsync` returns the manager for the current cluster, rediscovering it first if the
+    /// refresh interval has elapsed. Failed rediscoveries keep the current cluster.
+    pub fn redsync(&self) -> Result<Arc<Redsync<I>>, RedsyncError> {
+        let stale = match &*self.current.read().unwrap() {
+            Some(current) => current.refreshed.elapsed() >= self.refresh_interval,
+            None => true,
+        };
+        if stale {
+            let refreshed = self.refresh();
+            if let Some(current) = &*self.current.read().unwrap() {
+                return Ok(current.redsync.clone());
+            }
+            refreshed?;
+        }
+
+        match &*self.current.read().unwrap() {
+            Some(current) => Ok(current.redsync.clone()),
+            None => Err(RedsyncError::DiscoveryFailed(String::from(
+                "cluster not discovered",
+            ))),
+        }
+    }
+
+    /// `refresh` rediscovers the cluster, returning true if its members changed.
+    pub fn refresh(&self) -> Result<bool, RedsyncError> {
+        let mut members = self
+            .discovery
+            .discover()
+            .map_err(|e| RedsyncError::DiscoveryFailed(e.to_string()))?;
+        if members.is_empty() {
+            return Err(RedsyncError::DiscoveryFailed(String::from(
+                "no instances discovered",
+            )));
+        }
+        members.sort();
+        members.dedup();
+
+        let mut current = self.current.write().unwrap();
+        if let Some(current) = current.as_mut() {
+            if current.members == members {
+                current.refreshed = Instant::now();
+                return Ok(false);
+            }
+        }
+
+        let cluster = members
+            .iter()
+            .map(|member| (self.connect)(member))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut redsync = (self.configure)(RedsyncBuilder::new(cluster)).build();
+        redsync.id = self.id;
+
+        *current = Some(Current {
+            members,
+            redsync: Arc::new(redsync),
+            refreshed: Instant::now(),
+        });
+        Ok(true)
+    }
+}
+
+#[cfg(feature = "redis")]
+impl DiscoveredCluster<RedisInstance> {
+    /// `redis` creates a DiscoveredCluster of RedisInstances, treating each discovered address
+    /// as a Redis URL.
+    pub fn redis<D: ClusterDiscovery + 'static>(discovery: D) -> Self {
+        Self::new(discovery, |url| RedisInstance::new(url))
+    }
+}
+
+#[cfg(all(test, feature = "redis"))]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[test]
+    fn refresh() -> Result<(), RedsyncError> {
+        let members = Arc::new(Mutex::new(vec![
+            String::from("redis://127.0.0.1:6379"),
+            String::from("redis://127.0.0.1:6380"),
+            String::from("redis://127.0.0.1:6381"),
+        ]));
+        let discovered = members.clone();
+        let cluster = DiscoveredCluster::redis(move || Ok(discovered.lock().unwrap().clone()))
+            .refresh_interval(Duration::ZERO)
+            .configure(|builder| builder.retry_count(1))
+            .start()?;
+
+        let redsync = cluster.redsync()?;
+        assert_eq!(redsync.cluster.len(), 3);
+        assert_eq!(redsync.retry_count, 1);
+        assert!(!cluster.refresh()?);
+
+        members.lock().unwrap().pop();
+        assert_eq!(cluster.redsync()?.cluster.len(), 2);
+        assert_eq!(cluster.redsync()?.id(), redsync.id());
+
+        members.lock().unwrap().clear();
+        assert!(cluster.refresh().is_err());
+        assert_eq!(cluster.redsync()?.cluster.len(), 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn dns_discovery() {
+        let members = DnsDiscovery::new("127.0.0.1", 6379).discover().unwrap();
+        assert_eq!(members, ["redis://127.0.0.1:6379"]);
+    }
+}
@@ -0,0 +1,96 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime};
+
+/// `DriftEstimator` estimates the clock drift rate of each instance relative to the local clock,
+/// from successive samples of the instance's server time.
+#[derive(Debug)]
+pub(crate) struct DriftEstimator {
+    samples: Mutex<Vec<Option<(Instant, SystemTime)>>>,
+    factor: Mutex<Option<f64>>,
+}
+
+impl DriftEstimator {
+    pub(crate) fn new(instances: usize) -> Self {
+        Self {
+            samples: Mutex::new(vec![None; instances]),
+            factor: Mutex::new(None),
+        }
+    }
+
+    pub(crate) fn factor(&self) -> Option<f64> {
+        *self.factor.lock().unwrap()
+    }
+
+    /// `record` stores new samples, returning the drift rate observed for each instance since its
+    /// previous sample, if any.
+    pub(crate) fn record(&self, samples: Vec<Option<(Instant, SystemTime)>>) -> Vec<f64> {
+        let mut previous = self.samples.lock().unwrap();
+        let mut rates = Vec::new();
+
+        for (prev, next) in previous.iter_mut().zip(samples) {
+            let next = match next {
+                Some(next) => next,
+                None => continue,
+            };
+
+            if let Some(prev) = prev {
+                if let Some(rate) = drift_rate(*prev, next) {
+                    rates.push(rate);
+                }
+            }
+            *prev = Some(next);
+        }
+
+        rates
+    }
+
+    pub(crate) fn set_factor(&self, factor: f64) {
+        *self.factor.lock().unwrap() = Some(factor);
+    }
+}
+
+fn drift_rate(prev: (Instant, SystemTime), next: (Instant, SystemTime)) -> Option<f64> {
+    let local = next.0.checked_duration_since(prev.0)?;
+    if local == Duration::from_millis(0) {
+        return None;
+    }
+
+    let server = match next.1.duration_since(prev.1) {
+        Ok(server) => server.as_secs_f64(),
+        Err(e) => -e.duration().as_secs_f64(),
+    };
+
+    Some((server - local.as_secs_f64()).abs() / local.as_secs_f64())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn record() {
+        let estimator = DriftEstimator::new(2);
+        let (local, server) = (Instant::now(), SystemTime::now());
+
+        let rates = estimator.record(vec![Some((local, server)), None]);
+        assert!(rates.is_empty());
+
+        let rates = estimator.record(vec![
+            Some((
+                local + Duration::from_secs(100),
+                server + Duration::from_secs(101),
+            )),
+            Some((local, server)),
+        ]);
+        assert_eq!(rates, [0.01]);
+    }
+
+    #[test]
+    fn set_factor() {
+        let estimator = DriftEstimator::new(1);
+        assert_eq!(estimator.factor(), None);
+
+        estimator.set_factor(0.001);
+        assert_eq!(estimator.factor(), Some(0.001));
+    }
+}
@@ -0,0 +1,54 @@
+#[cfg(any(feature = "redis", feature = "resp", test))]
+use crate::errors::RedsyncError;
+
+use std::time::Duration;
+
+/// `Durability` requires each instance to confirm that a lock it granted has reached its
+/// replicas, with the `WAIT` command, before the instance's vote is counted. This protects locks
+/// from being lost when an instance crashes and its replica is promoted before replicating them,
+/// at the cost of up to `timeout` of extra latency per instance.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Durability {
+    /// `replicas` is the number of replicas that must acknowledge the lock.
+    pub replicas: u32,
+    /// `timeout` is how long each instance waits for the acknowledgements.
+    pub timeout: Duration,
+}
+
+impl Durability {
+    pub fn new(replicas: u32, timeout: Duration) -> Self {
+        Self { replicas, timeout }
+    }
+
+    /// `check` fails with `ReplicationUnconfirmed` unless `acknowledged` replicas are enough.
+    #[cfg(any(feature = "redis", feature = "resp", test))]
+    pub(crate) fn check(&self, acknowledged: u32) -> Result<(), RedsyncError> {
+        if acknowledged < self.replicas {
+            return Err(RedsyncError::ReplicationUnconfirmed {
+                acknowledged,
+                required: self.replicas,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check() {
+        let durability = Durability::new(2, Duration::from_millis(50));
+        assert!(durability.check(2).is_ok());
+        assert!(durability.check(3).is_ok());
+        assert_eq!(
+            durability.check(1),
+            Err(RedsyncError::ReplicationUnconfirmed {
+                acknowledged: 1,
+                required: 2
+            })
+        );
+    }
+}
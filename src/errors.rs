@@ -1,20 +1,143 @@
+use crate::policy::PolicyViolation;
+use crate::snapshot::ConfigSnapshot;
+
 use std::fmt;
+use std::io;
 use std::ops::{Deref, DerefMut};
+use std::time::Duration;
 
 use thiserror::Error;
 
+/// `ErrorKind` is a coarse category of `RedsyncError`, for callers that only need to decide how
+/// to respond to an error, such as which HTTP status to return, rather than match every variant.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ErrorKind {
+    /// The resource is held by another caller, or has changed since it was last seen.
+    Contention,
+    /// Instances could not be reached or did not respond in time. Retrying later may succeed.
+    Io,
+    /// The lock had expired, or is no longer held by the caller.
+    Expired,
+    /// The request or the manager is misconfigured. Retrying will not succeed.
+    Config,
+    /// The request was refused by a policy, quota or paused manager.
+    Rejected,
+    /// Any other failure, such as an unexpected response from an instance.
+    Internal,
+}
+
+impl ErrorKind {
+    /// `PRECEDENCE` orders kinds by how well they explain an aggregate of errors, so that, say,
+    /// a lock attempt that failed on some instances because the resource was held and on others
+    /// because they were unreachable is reported as contention.
+    const PRECEDENCE: [ErrorKind; 6] = [
+        ErrorKind::Contention,
+        ErrorKind::Expired,
+        ErrorKind::Rejected,
+        ErrorKind::Config,
+        ErrorKind::Io,
+        ErrorKind::Internal,
+    ];
+
+    /// `as_str` returns the name of the kind, as used by its `Display` implementation.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorKind::Contention => "contention",
+            ErrorKind::Io => "io",
+            ErrorKind::Expired => "expired",
+            ErrorKind::Config => "config",
+            ErrorKind::Rejected => "rejected",
+            ErrorKind::Internal => "internal",
+        }
+    }
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
 /// `RedsyncError` is an enum of all error kinds returned by the crate.
 #[derive(Error, Debug, PartialEq)]
 pub enum RedsyncError {
-    #[error("{0}")]
-    RedisError(#[from] redis::RedisError),
+    #[cfg(feature = "redis")]
+    #[error("connection failed: {0}")]
+    ConnectionFailed(redis::RedisError),
+    #[cfg(feature = "redis")]
+    #[error("authentication failed: {0}")]
+    AuthenticationFailed(redis::RedisError),
+    #[cfg(feature = "redis")]
+    #[error("timed out: {0}")]
+    Timeout(redis::RedisError),
+    #[cfg(feature = "redis")]
+    #[error("command failed: {0}")]
+    CommandFailed(redis::RedisError),
+    #[cfg(feature = "redis")]
     #[error("unexpected response from Redis: {0:?}")]
     UnexpectedResponse(redis::Value),
+    #[cfg(feature = "resp")]
+    #[error("{0}")]
+    RespError(#[from] crate::resp::RespError),
+    #[error("failed to fetch credentials: {0}")]
+    CredentialsError(String),
+    #[error("dns resolution failed: {0}")]
+    DnsResolutionFailed(String),
+    #[error("cluster discovery failed: {0}")]
+    DiscoveryFailed(String),
+    #[error("lock journal failed: {0}")]
+    JournalError(String),
+    #[error("instance {0}: {1}")]
+    InstanceError(usize, Box<RedsyncError>),
+    #[error("{script} script failed on {instance}: {source}")]
+    ScriptFailed {
+        script: &'static str,
+        instance: String,
+        source: Box<RedsyncError>,
+    },
+    #[error("resource is served by cluster node {1} (slot {0}): Redsync requires standalone Redis instances")]
+    ClusterRedirection(u16, String),
 
     #[error("requested resource is current locked")]
     ResourceLocked,
+    #[error("requested resource is being locked by another caller in this process")]
+    ResourceLockedLocally,
+    #[error("concurrent lock call on the resource in this process failed: {0}")]
+    InFlightFailed(ErrorKind),
+    #[error("requested resource is reserved by another caller")]
+    ResourceReserved,
     #[error("invalid or expired lease on lock")]
     InvalidLease,
+    #[error("lock had already expired on every instance")]
+    LockAlreadyExpired,
+    #[error("lock was issued by another manager ({0})")]
+    ForeignLock(u64),
+    #[error("resource is at version {actual}, expected version {expected}")]
+    VersionMismatch { expected: u64, actual: u64 },
+    #[error("invalid lease ttl: {0:?}")]
+    InvalidTtl(Duration),
+    #[error("invalid configuration: {0}")]
+    InvalidConfig(String),
+    #[error("invalid resource name: {0:?}")]
+    InvalidResourceName(String),
+    #[error("requested lease ttl {0:?} exceeds the maximum lease")]
+    MaxLeaseExceeded(Duration),
+    #[error("lock rejected by policy: {0}")]
+    PolicyViolation(PolicyViolation),
+    #[error("lock quota exceeded for prefix {0}")]
+    QuotaExceeded(String),
+    #[error("lock manager is paused")]
+    ManagerPaused,
+    #[error("lock acknowledged by {acknowledged} replicas, {required} required")]
+    ReplicationUnconfirmed { acknowledged: u32, required: u32 },
+    #[error("injected fault")]
+    InjectedFault,
+    #[error("instance did not respond before the release deadline")]
+    ReleaseTimedOut,
+    #[error("instance does not support {0}")]
+    Unsupported(&'static str),
+    #[error("failed to spawn thread: {0}")]
+    ThreadSpawnFailed(String),
 
     #[error("lock attempt failed: max retries exceeded: {0}")]
     LockRetriesExceeded(MultiError),
@@ -22,23 +145,364 @@ pub enum RedsyncError {
     ExtendRetriesExceeded(MultiError),
     #[error("unlock attempt failed: {0}")]
     UnlockFailed(MultiError),
+    #[error("ttl attempt failed: {0}")]
+    TtlFailed(MultiError),
+    #[error("guarded write attempt failed: {0}")]
+    GuardedWriteFailed(MultiError),
+    #[error("force unlock attempt failed: {0}")]
+    ForceUnlockFailed(MultiError),
+    #[error("lock preview failed: {0}")]
+    PreviewFailed(MultiError),
+    #[error("stats attempt failed: {0}")]
+    StatsFailed(MultiError),
+    #[error("audit log read failed: {0}")]
+    AuditFailed(MultiError),
+    #[error("reservation attempt failed: {0}")]
+    ReservationFailed(MultiError),
+    #[error("invalid instance urls: {0}")]
+    InvalidUrls(MultiError),
+    #[error("unreachable instances: {0}")]
+    UnreachableInstances(MultiError),
+    #[error("drift calibration attempt failed: {0}")]
+    CalibrationFailed(MultiError),
 }
 
+impl RedsyncError {
+    /// `name` returns a short name for the error, ignoring instance details, as recorded in audit
+    /// events and fixtures.
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            #[cfg(feature = "redis")]
+            RedsyncError::ConnectionFailed(_) => "connection failed",
+            #[cfg(feature = "redis")]
+            RedsyncError::AuthenticationFailed(_) => "authentication failed",
+            #[cfg(feature = "redis")]
+            RedsyncError::Timeout(_) => "timeout",
+            #[cfg(feature = "redis")]
+            RedsyncError::CommandFailed(_) => "command failed",
+            #[cfg(feature = "redis")]
+            RedsyncError::UnexpectedResponse(_) => "unexpected response",
+            #[cfg(feature = "resp")]
+            RedsyncError::RespError(_) => "resp error",
+            RedsyncError::CredentialsError(_) => "credentials error",
+            RedsyncError::DnsResolutionFailed(_) => "dns resolution failed",
+            RedsyncError::DiscoveryFailed(_) => "discovery failed",
+            RedsyncError::JournalError(_) => "journal error",
+            RedsyncError::InstanceError(_, e) => e.name(),
+            RedsyncError::ScriptFailed { .. } => "script failed",
+            RedsyncError::ClusterRedirection(..) => "cluster redirection",
+            RedsyncError::ResourceLocked => "resource locked",
+            RedsyncError::ResourceLockedLocally => "resource locked locally",
+            RedsyncError::InFlightFailed(_) => "in-flight call failed",
+            RedsyncError::ResourceReserved => "resource reserved",
+            RedsyncError::InvalidLease => "invalid lease",
+            RedsyncError::LockAlreadyExpired => "lock already expired",
+            RedsyncError::ForeignLock(_) => "foreign lock",
+            RedsyncError::VersionMismatch { .. } => "version mismatch",
+            RedsyncError::InvalidTtl(_) => "invalid ttl",
+            RedsyncError::InvalidConfig(_) => "invalid config",
+            RedsyncError::InvalidResourceName(_) => "invalid resource name",
+            RedsyncError::MaxLeaseExceeded(_) => "max lease exceeded",
+            RedsyncError::PolicyViolation(_) => "policy violation",
+            RedsyncError::QuotaExceeded(_) => "quota exceeded",
+            RedsyncError::ManagerPaused => "manager paused",
+            RedsyncError::ReplicationUnconfirmed { .. } => "replication unconfirmed",
+            RedsyncError::InjectedFault => "injected fault",
+            RedsyncError::ReleaseTimedOut => "release timed out",
+            RedsyncError::Unsupported(_) => "unsupported",
+            RedsyncError::ThreadSpawnFailed(_) => "thread spawn failed",
+            RedsyncError::LockRetriesExceeded(_) => "lock retries exceeded",
+            RedsyncError::ExtendRetriesExceeded(_) => "extend retries exceeded",
+            RedsyncError::UnlockFailed(_) => "unlock failed",
+            RedsyncError::TtlFailed(_) => "ttl failed",
+            RedsyncError::GuardedWriteFailed(_) => "guarded write failed",
+            RedsyncError::ForceUnlockFailed(_) => "force unlock failed",
+            RedsyncError::PreviewFailed(_) => "preview failed",
+            RedsyncError::StatsFailed(_) => "stats failed",
+            RedsyncError::AuditFailed(_) => "audit failed",
+            RedsyncError::ReservationFailed(_) => "reservation failed",
+            RedsyncError::InvalidUrls(_) => "invalid urls",
+            RedsyncError::UnreachableInstances(_) => "unreachable instances",
+            RedsyncError::CalibrationFailed(_) => "calibration failed",
+        }
+    }
+
+    /// `kind` returns the category of the error. Errors aggregated over instances are categorized
+    /// by the most significant kind among them.
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            #[cfg(feature = "redis")]
+            RedsyncError::ConnectionFailed(_) | RedsyncError::Timeout(_) => ErrorKind::Io,
+            #[cfg(feature = "redis")]
+            RedsyncError::AuthenticationFailed(_) => ErrorKind::Config,
+            #[cfg(feature = "redis")]
+            RedsyncError::CommandFailed(_) | RedsyncError::UnexpectedResponse(_) => {
+                ErrorKind::Internal
+            }
+            #[cfg(feature = "resp")]
+            RedsyncError::RespError(e) => match e {
+                crate::resp::RespError::InvalidUrl(_) => ErrorKind::Config,
+                crate::resp::RespError::Io(_) => ErrorKind::Io,
+                _ => ErrorKind::Internal,
+            },
+            RedsyncError::CredentialsError(_)
+            | RedsyncError::DnsResolutionFailed(_)
+            | RedsyncError::DiscoveryFailed(_)
+            | RedsyncError::ReplicationUnconfirmed { .. }
+            | RedsyncError::InjectedFault
+            | RedsyncError::ReleaseTimedOut => ErrorKind::Io,
+            RedsyncError::JournalError(_)
+            | RedsyncError::ForeignLock(_)
+            | RedsyncError::ThreadSpawnFailed(_) => ErrorKind::Internal,
+            RedsyncError::InstanceError(_, e) => e.kind(),
+            RedsyncError::InFlightFailed(kind) => *kind,
+            RedsyncError::ScriptFailed { source, .. } => source.kind(),
+            RedsyncError::ResourceLocked
+            | RedsyncError::ResourceLockedLocally
+            | RedsyncError::ResourceReserved
+            | RedsyncError::VersionMismatch { .. } => ErrorKind::Contention,
+            RedsyncError::InvalidLease | RedsyncError::LockAlreadyExpired => ErrorKind::Expired,
+            RedsyncError::ClusterRedirection(..)
+            | RedsyncError::InvalidTtl(_)
+            | RedsyncError::InvalidConfig(_)
+            | RedsyncError::InvalidResourceName(_)
+            | RedsyncError::MaxLeaseExceeded(_)
+            | RedsyncError::InvalidUrls(_)
+            | RedsyncError::Unsupported(_) => ErrorKind::Config,
+            RedsyncError::PolicyViolation(_)
+            | RedsyncError::QuotaExceeded(_)
+            | RedsyncError::ManagerPaused => ErrorKind::Rejected,
+            RedsyncError::UnreachableInstances(_) => ErrorKind::Io,
+            RedsyncError::LockRetriesExceeded(errors)
+            | RedsyncError::ExtendRetriesExceeded(errors)
+            | RedsyncError::UnlockFailed(errors)
+            | RedsyncError::TtlFailed(errors)
+            | RedsyncError::GuardedWriteFailed(errors)
+            | RedsyncError::ForceUnlockFailed(errors)
+            | RedsyncError::PreviewFailed(errors)
+            | RedsyncError::StatsFailed(errors)
+            | RedsyncError::AuditFailed(errors)
+            | RedsyncError::ReservationFailed(errors)
+            | RedsyncError::CalibrationFailed(errors) => errors.kind(),
+        }
+    }
+
+    /// `retry_after` suggests how long to wait before retrying a lock that failed with
+    /// `LockRetriesExceeded`, derived from the remaining TTL of the lock holding the resource and
+    /// the latency of the instances. See `MultiError::retry_after`.
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            RedsyncError::LockRetriesExceeded(errors) => errors.retry_after(),
+            _ => None,
+        }
+    }
+
+    /// `inner` returns the error wrapped by `InstanceError`, or this error if it is not one.
+    pub fn inner(&self) -> &RedsyncError {
+        match self {
+            RedsyncError::InstanceError(_, e) => e.inner(),
+            e => e,
+        }
+    }
+
+    fn is_io_error(&self) -> bool {
+        match self {
+            RedsyncError::InstanceError(_, e) => e.is_io_error(),
+            #[cfg(feature = "redis")]
+            RedsyncError::ConnectionFailed(_) | RedsyncError::Timeout(_) => true,
+            #[cfg(feature = "resp")]
+            RedsyncError::RespError(crate::resp::RespError::Io(_)) => true,
+            RedsyncError::ReleaseTimedOut => true,
+            _ => false,
+        }
+    }
+}
+
+#[cfg(feature = "redis")]
+impl From<redis::RedisError> for RedsyncError {
+    fn from(e: redis::RedisError) -> Self {
+        if let Some((address, slot)) = e.redirect_node() {
+            return RedsyncError::ClusterRedirection(slot, String::from(address));
+        }
+
+        if e.is_timeout() {
+            RedsyncError::Timeout(e)
+        } else if e.is_io_error() || e.is_connection_refusal() || e.is_connection_dropped() {
+            RedsyncError::ConnectionFailed(e)
+        } else {
+            RedsyncError::CommandFailed(e)
+        }
+    }
+}
+
+/// `DEFAULT_ERROR_LIMIT` is the number of errors a `MultiError` holds by default.
+pub const DEFAULT_ERROR_LIMIT: usize = 64;
+
 /// `MultiError` wraps `Vec<RedsyncError>`, typically aggregated over instances in a Redsync cluster.
-#[derive(Debug, Default, PartialEq)]
-pub struct MultiError(Vec<RedsyncError>);
+///
+/// At most `limit` errors are held, so that failures of large clusters or long retries cannot
+/// grow it without bound; errors pushed beyond the limit are only counted, as `truncated`.
+#[derive(Debug)]
+pub struct MultiError {
+    errors: Vec<RedsyncError>,
+    limit: usize,
+    truncated: usize,
+    retry_after: Option<Duration>,
+    config: Option<Box<ConfigSnapshot>>,
+}
+
+impl Default for MultiError {
+    fn default() -> Self {
+        Self::with_limit(DEFAULT_ERROR_LIMIT)
+    }
+}
+
+impl PartialEq for MultiError {
+    fn eq(&self, other: &Self) -> bool {
+        self.errors == other.errors && self.truncated == other.truncated
+    }
+}
 
 impl MultiError {
     pub fn new() -> Self {
         Default::default()
     }
 
+    /// `with_limit` creates a MultiError that holds at most `limit` errors.
+    pub fn with_limit(limit: usize) -> Self {
+        Self {
+            errors: Vec::new(),
+            limit,
+            truncated: 0,
+            retry_after: None,
+            config: None,
+        }
+    }
+
+    /// `push` adds `e`, or counts it as truncated if the limit has been reached.
+    pub fn push(&mut self, e: RedsyncError) {
+        if self.errors.len() < self.limit {
+            self.errors.push(e);
+        } else {
+            self.truncated += 1;
+        }
+    }
+
+    /// `append` moves the errors of `other` into this MultiError, subject to its limit.
+    pub fn append(&mut self, other: &mut MultiError) {
+        self.extend(other.errors.drain(..));
+        self.truncated += std::mem::take(&mut other.truncated);
+    }
+
+    /// `truncated` returns the number of errors dropped once the limit was reached.
+    pub fn truncated(&self) -> usize {
+        self.truncated
+    }
+
+    /// `retry_after` returns how long to wait before the failed call may succeed if retried, when
+    /// it failed because of contention and the lock holding the resource could be inspected.
+    pub fn retry_after(&self) -> Option<Duration> {
+        self.retry_after
+    }
+
+    pub(crate) fn set_retry_after(&mut self, retry_after: Option<Duration>) {
+        self.retry_after = retry_after;
+    }
+
+    /// `config` returns the configuration of the manager whose call failed, if it attaches
+    /// configuration snapshots to its errors.
+    pub fn config(&self) -> Option<&ConfigSnapshot> {
+        self.config.as_deref()
+    }
+
+    pub(crate) fn set_config(&mut self, config: Option<ConfigSnapshot>) {
+        self.config = config.map(Box::new);
+    }
+
+    /// `includes` returns true if any aggregated error is `e`, including errors wrapped by
+    /// `InstanceError`.
     pub fn includes(&self, e: RedsyncError) -> bool {
-        self.contains(&e)
+        self.iter().any(|error| *error.inner() == e)
+    }
+
+    /// `all_resource_locked` returns true if every aggregated error is a `ResourceLocked`.
+    pub fn all_resource_locked(&self) -> bool {
+        !self.is_empty()
+            && self
+                .iter()
+                .all(|e| matches!(e.inner(), RedsyncError::ResourceLocked))
+    }
+
+    /// `any_io_error` returns true if any aggregated error was caused by an I/O failure.
+    pub fn any_io_error(&self) -> bool {
+        self.iter().any(RedsyncError::is_io_error)
+    }
+
+    /// `is_quorum_possible` returns true if enough instances failed only transiently for a
+    /// retry to still reach `quorum` out of `cluster_size` instances.
+    pub fn is_quorum_possible(&self, cluster_size: usize, quorum: usize) -> bool {
+        let rejected = self
+            .iter()
+            .filter(|e| {
+                matches!(
+                    e.inner(),
+                    RedsyncError::ResourceLocked
+                        | RedsyncError::InvalidLease
+                        | RedsyncError::MaxLeaseExceeded(_)
+                )
+            })
+            .count();
+
+        cluster_size.saturating_sub(rejected) >= quorum
     }
 
+    /// `kind` returns the most significant kind among the aggregated errors, or
+    /// `ErrorKind::Internal` if there are none.
+    pub fn kind(&self) -> ErrorKind {
+        let kinds: Vec<ErrorKind> = self.iter().map(RedsyncError::kind).collect();
+        ErrorKind::PRECEDENCE
+            .iter()
+            .copied()
+            .find(|kind| kinds.contains(kind))
+            .unwrap_or(ErrorKind::Internal)
+    }
+
+    /// `counts_by_kind` returns the number of aggregated errors of each kind, in order of first
+    /// occurrence.
+    pub fn counts_by_kind(&self) -> Vec<(ErrorKind, usize)> {
+        let mut counts: Vec<(ErrorKind, usize)> = Vec::new();
+        for error in self.iter() {
+            let kind = error.kind();
+            match counts.iter_mut().find(|(k, _)| *k == kind) {
+                Some((_, n)) => *n += 1,
+                None => counts.push((kind, 1)),
+            }
+        }
+
+        counts
+    }
+
+    /// `reset` clears the errors while keeping their allocation for reuse.
     pub(crate) fn reset(&mut self) {
-        self.clear()
+        self.errors.clear();
+        self.truncated = 0;
+    }
+}
+
+impl From<RedsyncError> for io::Error {
+    fn from(e: RedsyncError) -> Self {
+        let kind = match e.kind() {
+            ErrorKind::Contention => io::ErrorKind::ResourceBusy,
+            #[cfg(feature = "redis")]
+            ErrorKind::Io if matches!(e, RedsyncError::Timeout(_)) => io::ErrorKind::TimedOut,
+            ErrorKind::Io => io::ErrorKind::ConnectionAborted,
+            ErrorKind::Expired => io::ErrorKind::NotFound,
+            ErrorKind::Config => io::ErrorKind::InvalidInput,
+            ErrorKind::Rejected => io::ErrorKind::PermissionDenied,
+            ErrorKind::Internal => io::ErrorKind::Other,
+        };
+
+        io::Error::new(kind, e)
     }
 }
 
@@ -46,23 +510,209 @@ impl Deref for MultiError {
     type Target = Vec<RedsyncError>;
 
     fn deref(&self) -> &Self::Target {
-        &self.0
+        &self.errors
     }
 }
 
 impl DerefMut for MultiError {
     fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.0
+        &mut self.errors
+    }
+}
+
+impl Extend<RedsyncError> for MultiError {
+    fn extend<T: IntoIterator<Item = RedsyncError>>(&mut self, iter: T) {
+        for e in iter {
+            self.push(e);
+        }
     }
 }
 
 impl fmt::Display for MultiError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{} errors occurred:", self.len())?;
+        // Identical errors are printed once, along with the instances they occurred on if known
+        let mut groups: Vec<(String, usize, Vec<usize>)> = Vec::new();
         for error in self.iter() {
-            write!(f, "\n\t * {}", error)?;
+            let (message, node) = match error {
+                RedsyncError::InstanceError(i, e) => (e.to_string(), Some(*i)),
+                e => (e.to_string(), None),
+            };
+            let index = match groups.iter().position(|(m, ..)| *m == message) {
+                Some(index) => index,
+                None => {
+                    groups.push((message, 0, Vec::new()));
+                    groups.len() - 1
+                }
+            };
+
+            let group = &mut groups[index];
+            group.1 += 1;
+            group.2.extend(node);
+        }
+
+        write!(f, "{} errors occurred:", self.len() + self.truncated)?;
+        for (message, count, nodes) in groups {
+            write!(f, "\n\t * {}", message)?;
+            if count > 1 {
+                write!(f, " \u{d7}{}", count)?;
+            }
+            if !nodes.is_empty() {
+                let nodes: Vec<_> = nodes.iter().map(usize::to_string).collect();
+                write!(f, " (nodes {})", nodes.join(","))?;
+            }
+        }
+        if self.truncated > 0 {
+            write!(f, "\n\t * ...and {} more errors", self.truncated)?;
+        }
+        if let Some(config) = &self.config {
+            write!(f, "\n\tconfig: {}", config)?;
         }
 
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn all_resource_locked() {
+        let mut errors = MultiError::new();
+        assert!(!errors.all_resource_locked());
+
+        errors.push(RedsyncError::ResourceLocked);
+        errors.push(RedsyncError::ResourceLocked);
+        assert!(errors.all_resource_locked());
+
+        errors.push(RedsyncError::InvalidLease);
+        assert!(!errors.all_resource_locked());
+    }
+
+    #[test]
+    fn kind() {
+        assert_eq!(RedsyncError::ResourceLocked.kind(), ErrorKind::Contention);
+        assert_eq!(
+            RedsyncError::InstanceError(1, Box::new(RedsyncError::InvalidLease)).kind(),
+            ErrorKind::Expired
+        );
+
+        let mut errors = MultiError::new();
+        errors.push(RedsyncError::InjectedFault);
+        errors.push(RedsyncError::ResourceLocked);
+        let e = RedsyncError::LockRetriesExceeded(errors);
+        assert_eq!(e.kind(), ErrorKind::Contention);
+        assert_eq!(
+            RedsyncError::UnlockFailed(MultiError::new()).kind(),
+            ErrorKind::Internal
+        );
+
+        assert_eq!(ErrorKind::Contention.to_string(), "contention");
+
+        let e = io::Error::from(e);
+        assert_eq!(e.kind(), io::ErrorKind::ResourceBusy);
+        assert!(e.get_ref().unwrap().is::<RedsyncError>());
+    }
+
+    #[test]
+    fn any_io_error() {
+        let mut errors = MultiError::new();
+        errors.push(RedsyncError::ResourceLocked);
+        assert!(!errors.any_io_error());
+    }
+
+    #[cfg(feature = "redis")]
+    #[test]
+    fn any_io_error_redis() {
+        let mut errors = MultiError::new();
+        errors.push(RedsyncError::from(redis::RedisError::from(
+            std::io::Error::from(std::io::ErrorKind::ConnectionRefused),
+        )));
+        assert!(errors.any_io_error());
+    }
+
+    #[cfg(feature = "redis")]
+    #[test]
+    fn from_redis_error() {
+        let e = RedsyncError::from(redis::RedisError::from(std::io::Error::from(
+            std::io::ErrorKind::TimedOut,
+        )));
+        assert!(matches!(e, RedsyncError::Timeout { .. }));
+
+        let e = RedsyncError::from(redis::RedisError::from(std::io::Error::from(
+            std::io::ErrorKind::ConnectionRefused,
+        )));
+        assert!(matches!(e, RedsyncError::ConnectionFailed { .. }));
+
+        let e = RedsyncError::from(redis::RedisError::from((
+            redis::ErrorKind::ResponseError,
+            "An error was signalled by the server",
+            String::from("script rejected"),
+        )));
+        assert!(matches!(e, RedsyncError::CommandFailed { .. }));
+    }
+
+    #[test]
+    fn counts_by_kind() {
+        let mut errors = MultiError::new();
+        errors.push(RedsyncError::ResourceLocked);
+        errors.push(RedsyncError::InjectedFault);
+        errors.push(RedsyncError::ResourceLocked);
+
+        assert_eq!(
+            errors.counts_by_kind(),
+            vec![(ErrorKind::Contention, 2), (ErrorKind::Io, 1)]
+        );
+    }
+
+    #[test]
+    fn display_dedup() {
+        let mut errors = MultiError::new();
+        for i in 0..3 {
+            errors.push(RedsyncError::InstanceError(
+                i,
+                Box::new(RedsyncError::InjectedFault),
+            ));
+        }
+        errors.push(RedsyncError::ResourceLocked);
+
+        assert_eq!(
+            errors.to_string(),
+            "4 errors occurred:\n\t * injected fault \u{d7}3 (nodes 0,1,2)\n\t * requested resource is current locked"
+        );
+    }
+
+    #[test]
+    fn limit() {
+        let mut errors = MultiError::with_limit(2);
+        errors.extend((0..3).map(|_| RedsyncError::ResourceLocked));
+        let mut other = MultiError::with_limit(1);
+        other.extend(vec![
+            RedsyncError::InjectedFault,
+            RedsyncError::InvalidLease,
+        ]);
+        errors.append(&mut other);
+
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors.truncated(), 3);
+        assert_eq!(
+            errors.to_string(),
+            "5 errors occurred:\n\t * requested resource is current locked \u{d7}2\n\t * ...and 3 more errors"
+        );
+
+        errors.reset();
+        assert!(errors.is_empty());
+        assert_eq!(errors.truncated(), 0);
+    }
+
+    #[test]
+    fn is_quorum_possible() {
+        let mut errors = MultiError::new();
+        errors.push(RedsyncError::ResourceLocked);
+        errors.push(RedsyncError::InjectedFault);
+        assert!(errors.is_quorum_possible(3, 2));
+
+        errors.push(RedsyncError::InvalidLease);
+        assert!(!errors.is_quorum_possible(3, 2));
+    }
+}
@@ -0,0 +1,97 @@
+use crate::errors::{MultiError, RedsyncError};
+use crate::instance::Instance;
+use crate::redsync::{Lock, Redsync};
+use crate::ttl::IntoLeaseTtl;
+
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Mutex;
+use std::thread;
+
+/// `MAX_PANIC_RELEASES` bounds the releases kept for `take_panic_releases`, dropping the oldest
+/// when nothing collects them.
+const MAX_PANIC_RELEASES: usize = 64;
+
+static PANIC_RELEASES: Mutex<Vec<PanicRelease>> = Mutex::new(Vec::new());
+
+/// `PanicRelease` records the release of a lock whose `LockGuard` was dropped while its thread
+/// was panicking.
+#[derive(Debug)]
+pub struct PanicRelease {
+    /// `thread` is the name of the panicking thread, if it has one.
+    pub thread: Option<String>,
+    pub resource: Vec<u8>,
+    /// `result` is the result of unlocking the lock. A lock that failed to unlock remains held
+    /// until it expires.
+    pub result: Result<(), RedsyncError>,
+}
+
+/// `take_panic_releases` returns the locks released by guards dropped during a panic since the
+/// last call, e.g. to report from the handler of `std::panic::catch_unwind` or of a joined
+/// thread whether the critical section it interrupted left any lock behind.
+pub fn take_panic_releases() -> Vec<PanicRelease> {
+    std::mem::take(&mut *PANIC_RELEASES.lock().unwrap_or_else(|e| e.into_inner()))
+}
+
+/// `LockGuard` holds a lock acquired by `Redsync::lock_scoped` and unlocks it when dropped,
+/// including when the critical section panics. Releases made while panicking are recorded for
+/// `take_panic_releases`.
+pub struct LockGuard<'a, I: Instance> {
+    redsync: &'a Redsync<I>,
+    lock: Option<Lock>,
+}
+
+impl<'a, I: Instance> LockGuard<'a, I> {
+    pub(crate) fn new(redsync: &'a Redsync<I>, lock: Lock) -> Self {
+        Self {
+            redsync,
+            lock: Some(lock),
+        }
+    }
+
+    pub fn lock(&self) -> &Lock {
+        self.lock.as_ref().unwrap()
+    }
+
+    /// `extend` resets the guarded lock's TTL to `ttl`.
+    pub fn extend(&mut self, ttl: impl IntoLeaseTtl) -> Result<(), RedsyncError> {
+        let extended = self.redsync.extend(self.lock(), ttl)?;
+        self.lock = Some(extended);
+        Ok(())
+    }
+
+    /// `unlock` unlocks the guarded lock now, returning the result that dropping the guard would
+    /// discard.
+    pub fn unlock(mut self) -> Result<(), RedsyncError> {
+        let lock = self.lock.take().unwrap();
+        self.redsync.unlock_owned(lock)
+    }
+}
+
+impl<I: Instance> Drop for LockGuard<'_, I> {
+    fn drop(&mut self) {
+        let lock = match self.lock.take() {
+            Some(lock) => lock,
+            None => return,
+        };
+
+        if !thread::panicking() {
+            let _ = self.redsync.unlock_owned(lock);
+            return;
+        }
+
+        // A second panic while unwinding would abort the process
+        let redsync = self.redsync;
+        let result = panic::catch_unwind(AssertUnwindSafe(|| redsync.unlock(&lock)))
+            .unwrap_or_else(|_| Err(RedsyncError::UnlockFailed(MultiError::new())));
+
+        let mut releases = PANIC_RELEASES.lock().unwrap_or_else(|e| e.into_inner());
+        if releases.len() >= MAX_PANIC_RELEASES {
+            releases.remove(0);
+        }
+        releases.push(PanicRelease {
+            thread: thread::current().name().map(String::from),
+            resource: lock.resource,
+            result,
+        });
+    }
+}
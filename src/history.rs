@@ -0,0 +1,56 @@
+use std::time::{Duration, Instant};
+
+/// `RenewalHistory` records when a lock was acquired and how it has been extended since.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RenewalHistory {
+    pub acquired_at: Instant,
+    pub extends: u32,
+    pub last_extended: Option<Instant>,
+}
+
+impl RenewalHistory {
+    pub fn new(acquired_at: Instant) -> Self {
+        Self {
+            acquired_at,
+            extends: 0,
+            last_extended: None,
+        }
+    }
+
+    /// `held_for` returns how long the lock has been held since it was first acquired.
+    pub fn held_for(&self) -> Duration {
+        self.acquired_at.elapsed()
+    }
+
+    /// `last_confirmed` returns when the lease was last confirmed by a quorum, either by its
+    /// acquisition or its latest extend.
+    pub fn last_confirmed(&self) -> Instant {
+        self.last_extended.unwrap_or(self.acquired_at)
+    }
+
+    pub(crate) fn extended(self, at: Instant) -> Self {
+        Self {
+            extends: self.extends + 1,
+            last_extended: Some(at),
+            ..self
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extended() {
+        let acquired_at = Instant::now();
+        let history = RenewalHistory::new(acquired_at);
+        assert_eq!(history.last_confirmed(), acquired_at);
+
+        let at = acquired_at + Duration::from_secs(1);
+        let history = history.extended(at).extended(at);
+        assert_eq!(history.extends, 2);
+        assert_eq!(history.acquired_at, acquired_at);
+        assert_eq!(history.last_confirmed(), at);
+    }
+}
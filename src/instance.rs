@@ -1,138 +1,1689 @@
+use crate::audit::AuditEvent;
+#[cfg(feature = "redis")]
+use crate::audit::AUDIT_STREAM;
+#[cfg(feature = "redis")]
+use crate::credentials::{CredentialsCache, CredentialsProvider};
+use crate::durability::Durability;
 use crate::errors::RedsyncError;
+use crate::pipeline::PendingCall;
+#[cfg(feature = "redis")]
+use crate::preflight::clock_skew;
+use crate::preflight::NodeDiagnostics;
 use crate::redsync::Lock;
+#[cfg(feature = "redis")]
+use crate::reservation::reservation_key;
+use crate::reservation::Reservation;
+#[cfg(feature = "redis")]
+use crate::sequence::sequence_key;
+#[cfg(feature = "redis")]
+use crate::stats::{stats_key, STATS_WINDOW};
+#[cfg(feature = "redis")]
+use crate::subscription::ReleaseSubscription;
 
-use std::time::Duration;
+#[cfg(feature = "redis")]
+use std::net::{SocketAddr, ToSocketAddrs};
+#[cfg(feature = "redis")]
+use std::sync::Mutex;
+#[cfg(any(feature = "redis", feature = "resp"))]
+use std::thread;
+#[cfg(any(feature = "redis", feature = "resp"))]
+use std::time::Instant;
+#[cfg(feature = "redis")]
+use std::time::UNIX_EPOCH;
+use std::time::{Duration, SystemTime};
 
 /// `Instance` represents an entity with locking and unlocking capabilities.
 pub trait Instance {
     fn acquire(&self, lock: &Lock) -> Result<(), RedsyncError>;
     fn extend(&self, lock: &Lock) -> Result<(), RedsyncError>;
     fn release(&self, lock: &Lock) -> Result<(), RedsyncError>;
+    /// `acquire_replicated` acquires `lock` like `acquire`, then waits for it to reach the
+    /// replicas required by `durability`, failing with `ReplicationUnconfirmed` otherwise. The
+    /// lock is left in place when replication is unconfirmed.
+    fn acquire_replicated(
+        &self,
+        _lock: &Lock,
+        _durability: &Durability,
+    ) -> Result<(), RedsyncError> {
+        Err(RedsyncError::Unsupported("acquire_replicated"))
+    }
+    /// `acquire_if_version` acquires `lock` like `acquire`, or like `acquire_replicated` if
+    /// `durability` is given, unless the acquisition sequence of its resource is already past
+    /// `version`, in which case it fails with `VersionMismatch` and leaves both the resource and
+    /// its sequence untouched.
+    fn acquire_if_version(
+        &self,
+        _lock: &Lock,
+        _version: u64,
+        _durability: Option<&Durability>,
+    ) -> Result<(), RedsyncError> {
+        Err(RedsyncError::Unsupported("acquire_if_version"))
+    }
+    /// `send_acquire` sends the acquisition of `lock` without waiting for its reply, which is
+    /// read by `PendingCall::wait`. Managers built with `RedsyncBuilder::pipelined` send to every
+    /// instance before waiting on any. The default acquires `lock` before returning.
+    fn send_acquire(&self, lock: &Lock) -> PendingCall<'_> {
+        PendingCall::done(self.acquire(lock))
+    }
+    /// `send_extend` sends the extension of `lock` like `send_acquire`. The default extends
+    /// `lock` before returning.
+    fn send_extend(&self, lock: &Lock) -> PendingCall<'_> {
+        PendingCall::done(self.extend(lock))
+    }
+    /// `send_release` sends the release of `lock` like `send_acquire`. The default releases
+    /// `lock` before returning.
+    fn send_release(&self, lock: &Lock) -> PendingCall<'_> {
+        PendingCall::done(self.release(lock))
+    }
+    /// `reacquire` acquires `lock` if its resource is unlocked, or renews it to the lock's TTL if
+    /// it is already held with the lock's value.
+    fn reacquire(&self, _lock: &Lock) -> Result<(), RedsyncError> {
+        Err(RedsyncError::Unsupported("reacquire"))
+    }
+    fn ttl(&self, _lock: &Lock) -> Result<Duration, RedsyncError> {
+        Err(RedsyncError::Unsupported("ttl"))
+    }
+    fn guarded_set(&self, _lock: &Lock, _key: &str, _value: &str) -> Result<(), RedsyncError> {
+        Err(RedsyncError::Unsupported("guarded_set"))
+    }
+    /// `peek` returns the remaining TTL of the lock held on `resource`, or `None` if it is not
+    /// locked, without modifying it. Locks without an expiry report `Duration::MAX`.
+    fn peek(&self, _resource: &[u8]) -> Result<Option<Duration>, RedsyncError> {
+        Err(RedsyncError::Unsupported("peek"))
+    }
+    fn ping(&self) -> Result<(), RedsyncError> {
+        Err(RedsyncError::Unsupported("ping"))
+    }
+    fn diagnostics(&self) -> Result<NodeDiagnostics, RedsyncError> {
+        Err(RedsyncError::Unsupported("diagnostics"))
+    }
+    fn time(&self) -> Result<SystemTime, RedsyncError> {
+        Err(RedsyncError::Unsupported("time"))
+    }
+    /// `force_release_prefix` deletes every lock whose resource starts with `prefix`, regardless
+    /// of who holds it, returning the resources found. Only keys holding a tagged lock record
+    /// are matched, along with any string with an expiry if `bare_tokens` is set, and nothing is
+    /// deleted if `dry_run` is set.
+    fn force_release_prefix(
+        &self,
+        _prefix: &str,
+        _dry_run: bool,
+        _bare_tokens: bool,
+    ) -> Result<Vec<String>, RedsyncError> {
+        Err(RedsyncError::Unsupported("force_release_prefix"))
+    }
+    /// `acquire_many` acquires each of `locks`, returning a result per lock. Backends that can
+    /// batch commands should override the default, which acquires them one at a time.
+    fn acquire_many(&self, locks: &[Lock]) -> Vec<Result<(), RedsyncError>> {
+        locks.iter().map(|lock| self.acquire(lock)).collect()
+    }
+    /// `extend_many` extends each of `locks` to its TTL, returning a result per lock.
+    fn extend_many(&self, locks: &[Lock]) -> Vec<Result<(), RedsyncError>> {
+        locks.iter().map(|lock| self.extend(lock)).collect()
+    }
+    /// `release_many` releases each of `locks`, returning a result per lock.
+    fn release_many(&self, locks: &[Lock]) -> Vec<Result<(), RedsyncError>> {
+        locks.iter().map(|lock| self.release(lock)).collect()
+    }
+    /// `incr` increments the counter at `key` by `delta`, without taking it below zero, and
+    /// returns its new value. A counter without an expiry, such as one it creates, is set to
+    /// expire after `expiry`; later increments leave the expiry alone, so that the counter
+    /// covers a fixed window.
+    fn incr(&self, _key: &str, _delta: i64, _expiry: Duration) -> Result<i64, RedsyncError> {
+        Err(RedsyncError::Unsupported("incr"))
+    }
+    /// `incr_stats` increments the statistics fields of `resource` and renews their window.
+    fn incr_stats(&self, _resource: &[u8], _fields: &[(&str, i64)]) -> Result<(), RedsyncError> {
+        Err(RedsyncError::Unsupported("incr_stats"))
+    }
+    fn read_stats(&self, _resource: &[u8]) -> Result<Vec<(String, i64)>, RedsyncError> {
+        Err(RedsyncError::Unsupported("read_stats"))
+    }
+    /// `advance_sequence` sets the acquisition sequence of `lock`'s resource to the greater of
+    /// its current value plus `step` and `floor`, and returns the new value.
+    fn advance_sequence(&self, _lock: &Lock, _floor: i64, _step: i64) -> Result<i64, RedsyncError> {
+        Err(RedsyncError::Unsupported("advance_sequence"))
+    }
+    /// `append_audit` appends an entry with `fields` to the audit stream, trimming the stream to
+    /// about `retention` entries.
+    fn append_audit(
+        &self,
+        _fields: &[(&str, String)],
+        _retention: usize,
+    ) -> Result<(), RedsyncError> {
+        Err(RedsyncError::Unsupported("append_audit"))
+    }
+    /// `read_audit` returns up to `count` of the newest audit events, newest first.
+    fn read_audit(&self, _count: usize) -> Result<Vec<AuditEvent>, RedsyncError> {
+        Err(RedsyncError::Unsupported("read_audit"))
+    }
+    /// `reserve` places the marker of `reservation`, failing with `ResourceLocked` if the
+    /// resource is held past the start of the reservation, or `ResourceReserved` if it is already
+    /// reserved.
+    fn reserve(&self, _reservation: &Reservation) -> Result<(), RedsyncError> {
+        Err(RedsyncError::Unsupported("reserve"))
+    }
+    /// `cancel_reservation` removes the marker of `reservation`, if it is still in place.
+    fn cancel_reservation(&self, _reservation: &Reservation) -> Result<(), RedsyncError> {
+        Err(RedsyncError::Unsupported("cancel_reservation"))
+    }
+}
+
+/// `reserved!` expands to a Lua function returning true if the reservation marker at `key`
+/// belongs to another caller than `value` and starts before a lease of `ttl` ms would end.
+#[cfg(any(feature = "redis", feature = "resp"))]
+macro_rules! reserved {
+    () => {
+        "\
+local function reserved(key, value, ttl)
+    local reservation = redis.call(\"get\", key)
+    if not reservation then
+        return false
+    end
+    local start, owner = string.match(reservation, \"^(%d+):(.*)$\")
+    if owner == value then
+        return false
+    end
+    local time = redis.call(\"time\")
+    local now = tonumber(time[1]) * 1000 + math.floor(tonumber(time[2]) / 1000)
+    return now + tonumber(ttl) > tonumber(start)
+end
+"
+    };
 }
 
-const LOCK_SCRIPT: &str = "\
-return redis.call(\"set\", KEYS[1], ARGV[1], \"nx\", \"px\", ARGV[2])";
+#[cfg(any(feature = "redis", feature = "resp"))]
+pub(crate) const LOCK_SCRIPT: &str = concat!(
+    reserved!(),
+    "\
+if reserved(KEYS[2], ARGV[1], ARGV[2]) then
+    return -2
+end
+return redis.call(\"set\", KEYS[1], ARGV[1], \"nx\", \"px\", ARGV[2])"
+);
+
+#[cfg(any(feature = "redis", feature = "resp"))]
+pub(crate) const VERSIONED_LOCK_SCRIPT: &str = concat!(
+    reserved!(),
+    "\
+local version = tonumber(redis.call(\"get\", KEYS[3])) or 0
+if version > tonumber(ARGV[3]) then
+    return {-3, version}
+end
+if reserved(KEYS[2], ARGV[1], ARGV[2]) then
+    return -2
+end
+return redis.call(\"set\", KEYS[1], ARGV[1], \"nx\", \"px\", ARGV[2])"
+);
+
+#[cfg(any(feature = "redis", feature = "resp"))]
+pub(crate) const REACQUIRE_SCRIPT: &str = concat!(
+    reserved!(),
+    "\
+if reserved(KEYS[2], ARGV[1], ARGV[2]) then
+    return -2
+end
+if redis.call(\"get\", KEYS[1]) == ARGV[1] then
+    return redis.call(\"pexpire\", KEYS[1], ARGV[2])
+end
+if redis.call(\"set\", KEYS[1], ARGV[1], \"nx\", \"px\", ARGV[2]) then
+    return 1
+end
+return 0"
+);
+
+#[cfg(any(feature = "redis", feature = "resp"))]
+pub(crate) const UNLOCK_SCRIPT: &str = "\
+if redis.call(\"get\", KEYS[1]) == ARGV[1] then
+    redis.call(\"publish\", \"redsync:released:\" .. KEYS[1], ARGV[1])
+    return redis.call(\"del\", KEYS[1])
+else
+    return 0
+end";
+
+#[cfg(any(feature = "redis", feature = "resp"))]
+pub(crate) const EXTEND_SCRIPT: &str = concat!(
+    reserved!(),
+    "\
+if redis.call(\"get\", KEYS[1]) ~= ARGV[1] then
+    return 0
+end
+if tonumber(ARGV[3]) > 0 and tonumber(ARGV[2]) > tonumber(ARGV[3]) then
+    return -1
+end
+if reserved(KEYS[2], ARGV[1], ARGV[2]) then
+    return -2
+end
+if redis.call(\"pttl\", KEYS[1]) <= 0 then
+    return 0
+end
+return redis.call(\"pexpire\", KEYS[1], ARGV[2])"
+);
+
+#[cfg(any(feature = "redis", feature = "resp"))]
+pub(crate) const RESERVE_SCRIPT: &str = "\
+local time = redis.call(\"time\")
+local now = tonumber(time[1]) * 1000 + math.floor(tonumber(time[2]) / 1000)
+local ttl = redis.call(\"pttl\", KEYS[1])
+if ttl == -1 or (ttl > 0 and now + ttl > tonumber(ARGV[2])) then
+    return -1
+end
+if redis.call(\"set\", KEYS[2], ARGV[1], \"nx\", \"px\", ARGV[3]) then
+    return 1
+end
+return 0";
+
+#[cfg(any(feature = "redis", feature = "resp"))]
+pub(crate) const CANCEL_RESERVATION_SCRIPT: &str = "\
+if redis.call(\"get\", KEYS[1]) == ARGV[1] then
+    return redis.call(\"del\", KEYS[1])
+else
+    return 0
+end";
+
+#[cfg(any(feature = "redis", feature = "resp"))]
+pub(crate) const TTL_SCRIPT: &str = "\
+if redis.call(\"get\", KEYS[1]) == ARGV[1] then
+    return redis.call(\"pttl\", KEYS[1])
+else
+    return -3
+end";
+
+#[cfg(any(feature = "redis", feature = "resp"))]
+pub(crate) const GUARDED_SET_SCRIPT: &str = "\
+if redis.call(\"get\", KEYS[1]) == ARGV[1] then
+    redis.call(\"set\", KEYS[2], ARGV[2])
+    return 1
+else
+    return 0
+end";
+
+#[cfg(any(feature = "redis", feature = "resp"))]
+pub(crate) const SEQUENCE_SCRIPT: &str = "\
+local current = tonumber(redis.call(\"get\", KEYS[1])) or 0
+local next = math.max(current + tonumber(ARGV[2]), tonumber(ARGV[1]))
+if next ~= current then
+    redis.call(\"set\", KEYS[1], next)
+end
+return next";
+
+#[cfg(any(feature = "redis", feature = "resp"))]
+pub(crate) const INCR_SCRIPT: &str = "\
+local value = redis.call(\"incrby\", KEYS[1], ARGV[1])
+if value < 0 then
+    value = redis.call(\"incrby\", KEYS[1], -value)
+end
+if redis.call(\"pttl\", KEYS[1]) == -1 then
+    redis.call(\"pexpire\", KEYS[1], ARGV[2])
+end
+return value";
+
+/// `FORCE_RELEASE_SCRIPT` deletes KEYS[1] if it holds a lock record, unless ARGV[1] is "1" for a
+/// dry run, returning 1 if it does. Lock records are strings with an expiry tagged with
+/// `RecordVersion::V2`. Untagged values, as written by `RecordVersion::V1` and interop managers,
+/// cannot be told apart from counters and other keys sharing the prefix, so they are only
+/// matched if ARGV[2] is "1".
+#[cfg(any(feature = "redis", feature = "resp"))]
+pub(crate) const FORCE_RELEASE_SCRIPT: &str = "\
+if redis.call(\"type\", KEYS[1]).ok ~= \"string\" or redis.call(\"pttl\", KEYS[1]) < 0 then
+    return 0
+end
+local value = redis.call(\"get\", KEYS[1])
+if string.sub(value, 1, 10) ~= \"redsync/2;\" and ARGV[2] ~= \"1\" then
+    return 0
+end
+if ARGV[1] ~= \"1\" then
+    redis.call(\"del\", KEYS[1])
+end
+return 1";
+
+/// `script_name` returns the name of the operation that `script` performs, for error context.
+#[cfg(any(feature = "redis", feature = "resp"))]
+pub(crate) fn script_name(script: &str) -> &'static str {
+    match script {
+        LOCK_SCRIPT | VERSIONED_LOCK_SCRIPT => "acquire",
+        REACQUIRE_SCRIPT => "reacquire",
+        UNLOCK_SCRIPT => "release",
+        EXTEND_SCRIPT => "extend",
+        TTL_SCRIPT => "ttl",
+        GUARDED_SET_SCRIPT => "guarded set",
+        SEQUENCE_SCRIPT => "sequence",
+        INCR_SCRIPT => "incr",
+        RESERVE_SCRIPT => "reserve",
+        CANCEL_RESERVATION_SCRIPT => "cancel reservation",
+        FORCE_RELEASE_SCRIPT => "force release",
+        _ => "unknown",
+    }
+}
+
+/// `TRANSIENT_RETRY_DELAY` is the pause between retries of a command rejected by a transient
+/// server condition.
+#[cfg(any(feature = "redis", feature = "resp"))]
+const TRANSIENT_RETRY_DELAY: Duration = Duration::from_millis(5);
+
+/// `retry_transient` retries `f` while it fails with an error matching `is_transient`, for as long
+/// as another attempt can start within `deadline`.
+#[cfg(any(feature = "redis", feature = "resp"))]
+pub(crate) fn retry_transient<T, E>(
+    deadline: Duration,
+    is_transient: impl Fn(&E) -> bool,
+    mut f: impl FnMut() -> Result<T, E>,
+) -> Result<T, E> {
+    let start = Instant::now();
+
+    loop {
+        match f() {
+            Err(e) if is_transient(&e) && start.elapsed() + TRANSIENT_RETRY_DELAY < deadline => {
+                thread::sleep(TRANSIENT_RETRY_DELAY)
+            }
+            result => return result,
+        }
+    }
+}
+
+#[cfg(feature = "redis")]
+/// `is_transient` returns true if Redis rejected a command because it is still loading its
+/// dataset or busy running a script.
+fn is_transient(e: &redis::RedisError) -> bool {
+    e.kind() == redis::ErrorKind::BusyLoadingError || e.code() == Some("BUSY")
+}
+
+/// `release_channel` returns the Pub/Sub channel that releases of locks on `resource` are
+/// published to. The message payload is the value of the released lock.
+pub fn release_channel(resource: &[u8]) -> Vec<u8> {
+    [b"redsync:released:", resource].concat()
+}
+
+/// `scan_pattern` returns a SCAN MATCH pattern for keys starting with `prefix`.
+#[cfg(any(feature = "redis", feature = "resp"))]
+pub(crate) fn scan_pattern(prefix: &str) -> String {
+    let mut pattern = String::with_capacity(prefix.len() + 1);
+    for c in prefix.chars() {
+        if matches!(c, '*' | '?' | '[' | ']' | '\\') {
+            pattern.push('\\');
+        }
+        pattern.push(c);
+    }
+    pattern.push('*');
+    pattern
+}
+
+#[cfg(feature = "redis")]
+/// `connection_error` converts a RedisError raised while establishing a connection.
+fn connection_error(e: redis::RedisError) -> RedsyncError {
+    if e.is_timeout() {
+        RedsyncError::Timeout(e)
+    } else if e.kind() == redis::ErrorKind::AuthenticationFailed {
+        RedsyncError::AuthenticationFailed(e)
+    } else {
+        RedsyncError::ConnectionFailed(e)
+    }
+}
+
+#[cfg(feature = "redis")]
+/// `lock_reply` converts the reply of the lock scripts, called with `version` if any.
+fn lock_reply(reply: redis::Value, version: Option<u64>) -> Result<(), RedsyncError> {
+    match reply {
+        redis::Value::Okay => Ok(()),
+        redis::Value::Nil => Err(RedsyncError::ResourceLocked),
+        redis::Value::Int(-2) => Err(RedsyncError::ResourceReserved),
+        redis::Value::Bulk(values) => match values[..] {
+            [redis::Value::Int(-3), redis::Value::Int(actual)] => {
+                Err(RedsyncError::VersionMismatch {
+                    expected: version.unwrap_or_default(),
+                    actual: actual as u64,
+                })
+            }
+            _ => Err(RedsyncError::UnexpectedResponse(redis::Value::Bulk(values))),
+        },
+        v => Err(RedsyncError::UnexpectedResponse(v)),
+    }
+}
+
+#[cfg(feature = "redis")]
+/// `extend_reply` converts the reply of the extend script for `lock`.
+fn extend_reply(reply: redis::Value, lock: &Lock) -> Result<(), RedsyncError> {
+    match reply {
+        redis::Value::Int(1) => Ok(()),
+        redis::Value::Int(0) => Err(RedsyncError::InvalidLease),
+        redis::Value::Int(-1) => Err(RedsyncError::MaxLeaseExceeded(lock.ttl)),
+        redis::Value::Int(-2) => Err(RedsyncError::ResourceReserved),
+        v => Err(RedsyncError::UnexpectedResponse(v)),
+    }
+}
+
+#[cfg(feature = "redis")]
+/// `release_reply` converts the reply of the unlock script.
+fn release_reply(reply: redis::Value, _lock: &Lock) -> Result<(), RedsyncError> {
+    match reply {
+        redis::Value::Int(1) => Ok(()),
+        redis::Value::Int(0) => Err(RedsyncError::InvalidLease),
+        v => Err(RedsyncError::UnexpectedResponse(v)),
+    }
+}
+
+#[cfg(feature = "redis")]
+/// `CONNECT_ATTEMPTS` is the number of attempts made by `RedisInstance::connect`.
+const CONNECT_ATTEMPTS: u32 = 3;
+
+#[cfg(feature = "redis")]
+/// `CONNECT_RETRY_DELAY` is the initial delay between the attempts of `RedisInstance::connect`.
+const CONNECT_RETRY_DELAY: Duration = Duration::from_millis(100);
+
+#[cfg(feature = "redis")]
+/// `ADDRESS_TIMEOUT` bounds each connection attempt to a resolved address when no timeout is
+/// given, so that an unresponsive address family does not block the remaining addresses.
+const ADDRESS_TIMEOUT: Duration = Duration::from_secs(1);
+
+#[cfg(feature = "redis")]
+/// `happy_eyeballs_order` interleaves resolved addresses by family, starting with the family of
+/// the first address, as described in RFC 6555.
+fn happy_eyeballs_order(addrs: Vec<SocketAddr>) -> Vec<SocketAddr> {
+    let preferred = match addrs.first() {
+        Some(addr) => addr.is_ipv6(),
+        None => return addrs,
+    };
+    let (mut first, mut second): (Vec<_>, Vec<_>) =
+        addrs.into_iter().partition(|a| a.is_ipv6() == preferred);
+
+    let mut ordered = Vec::with_capacity(first.len() + second.len());
+    first.reverse();
+    second.reverse();
+    loop {
+        match (first.pop(), second.pop()) {
+            (None, None) => return ordered,
+            (a, b) => ordered.extend(a.into_iter().chain(b)),
+        }
+    }
+}
+
+#[cfg(feature = "redis")]
+/// `RedisInstance` is the implementation of the Instance trait for a Redis server.
+pub struct RedisInstance {
+    client: redis::Client,
+    credentials: Option<CredentialsCache>,
+    conn: Mutex<Option<redis::Connection>>,
+    max_lease: Option<Duration>,
+    pub(crate) connect_timeout: Option<Duration>,
+    pub(crate) response_timeout: Option<Duration>,
+    pub(crate) client_name: Option<String>,
+}
+
+#[cfg(feature = "redis")]
+/// `PooledConnection` returns its connection to the RedisInstance for reuse once dropped,
+/// unless the connection was closed or a command on it failed without its reply being read in
+/// full, such as on a timeout. A reply left unread would otherwise be read as the reply to the
+/// next command sent on the connection.
+struct PooledConnection<'a> {
+    instance: &'a RedisInstance,
+    conn: Option<redis::Connection>,
+    broken: bool,
+}
+
+#[cfg(feature = "redis")]
+impl<'a> PooledConnection<'a> {
+    /// `detach` takes the connection out of the pool, so that it is closed rather than reused
+    /// if dropped, such as while a reply on it is still outstanding.
+    fn detach(mut self) -> redis::Connection {
+        self.conn.take().unwrap()
+    }
+
+    /// `attach` returns `conn` to the pool of `instance` once dropped, undoing `detach`. It must
+    /// only be called once every reply sent on `conn` has been read.
+    fn attach(instance: &'a RedisInstance, conn: redis::Connection) -> Self {
+        Self {
+            instance,
+            conn: Some(conn),
+            broken: false,
+        }
+    }
+
+    fn conn(&mut self) -> &mut redis::Connection {
+        self.conn.as_mut().unwrap()
+    }
+
+    /// `track` marks the connection as broken if `result` failed without reading a full reply.
+    fn track<T>(&mut self, result: redis::RedisResult<T>) -> redis::RedisResult<T> {
+        if let Err(e) = &result {
+            self.broken |= !is_server_error(e);
+        }
+        result
+    }
+}
+
+#[cfg(feature = "redis")]
+impl redis::ConnectionLike for PooledConnection<'_> {
+    fn req_packed_command(&mut self, cmd: &[u8]) -> redis::RedisResult<redis::Value> {
+        let result = self.conn().req_packed_command(cmd);
+        self.track(result)
+    }
+
+    fn req_packed_commands(
+        &mut self,
+        cmd: &[u8],
+        offset: usize,
+        count: usize,
+    ) -> redis::RedisResult<Vec<redis::Value>> {
+        let result = self.conn().req_packed_commands(cmd, offset, count);
+        self.track(result)
+    }
+
+    fn get_db(&self) -> i64 {
+        self.conn.as_ref().unwrap().get_db()
+    }
+
+    fn check_connection(&mut self) -> bool {
+        self.conn().check_connection()
+    }
+
+    fn is_open(&self) -> bool {
+        !self.broken && self.conn.as_ref().unwrap().is_open()
+    }
+}
+
+#[cfg(feature = "redis")]
+impl Drop for PooledConnection<'_> {
+    fn drop(&mut self) {
+        if let Some(conn) = self.conn.take() {
+            if !self.broken && redis::ConnectionLike::is_open(&conn) {
+                *self.instance.conn.lock().unwrap() = Some(conn);
+            }
+        }
+    }
+}
+
+#[cfg(feature = "redis")]
+/// `is_server_error` returns true if `e` is an error reply read in full from the server, after
+/// which the connection can be reused. Any other error, such as a timeout or a reply that could
+/// not be parsed, may leave the rest of a reply unread.
+fn is_server_error(e: &redis::RedisError) -> bool {
+    match e.kind() {
+        // Parse errors are reported as response errors too, with their own description
+        redis::ErrorKind::ResponseError => e
+            .to_string()
+            .starts_with("An error was signalled by the server"),
+        _ => !e.is_io_error() && e.code().is_some(),
+    }
+}
+
+#[cfg(feature = "redis")]
+impl RedisInstance {
+    pub fn new<T: redis::IntoConnectionInfo>(params: T) -> Result<Self, RedsyncError> {
+        let client = redis::Client::open(params).map_err(RedsyncError::ConnectionFailed)?;
+        Ok(Self {
+            client,
+            credentials: None,
+            conn: Mutex::new(None),
+            max_lease: None,
+            connect_timeout: None,
+            response_timeout: None,
+            client_name: None,
+        })
+    }
+
+    /// `with_credentials` creates a RedisInstance that authenticates each connection using
+    /// credentials from `provider`, refreshing them `refresh_margin` ahead of their expiry.
+    pub fn with_credentials<T: redis::IntoConnectionInfo, P: CredentialsProvider + 'static>(
+        params: T,
+        provider: P,
+        refresh_margin: Duration,
+    ) -> Result<Self, RedsyncError> {
+        let client = redis::Client::open(params).map_err(RedsyncError::ConnectionFailed)?;
+        Ok(Self {
+            client,
+            credentials: Some(CredentialsCache::new(Box::new(provider), refresh_margin)),
+            conn: Mutex::new(None),
+            max_lease: None,
+            connect_timeout: None,
+            response_timeout: None,
+            client_name: None,
+        })
+    }
+
+    /// `connect` creates a RedisInstance like `new`, then connects to the server eagerly so that
+    /// misconfiguration surfaces at startup rather than on the first lock. The connection is
+    /// retried as with `connect_with_retry`, making up to 3 attempts.
+    pub fn connect<T: redis::IntoConnectionInfo>(params: T) -> Result<Self, RedsyncError> {
+        Self::connect_with_retry(params, CONNECT_ATTEMPTS, CONNECT_RETRY_DELAY)
+    }
+
+    /// `connect_with_retry` creates a RedisInstance and connects to it like `connect`, making up
+    /// to `attempts` attempts that are `delay` apart, doubling after each. Unresolvable hosts,
+    /// timeouts and refused connections are retried, failing with `DnsResolutionFailed`,
+    /// `Timeout` and `ConnectionFailed` respectively; rejected credentials fail immediately with
+    /// `AuthenticationFailed`.
+    pub fn connect_with_retry<T: redis::IntoConnectionInfo>(
+        params: T,
+        attempts: u32,
+        delay: Duration,
+    ) -> Result<Self, RedsyncError> {
+        let instance = Self::new(params)?;
+
+        let mut delay = delay;
+        for attempt in 1..=attempts.max(1) {
+            let result = instance
+                .resolve()
+                .and_then(|_| instance.connection(Some(ADDRESS_TIMEOUT)).map(drop));
+            match result {
+                Ok(()) => break,
+                Err(e @ RedsyncError::AuthenticationFailed(_)) => return Err(e),
+                Err(e) if attempt >= attempts => return Err(e),
+                Err(_) => {
+                    thread::sleep(delay);
+                    delay *= 2;
+                }
+            }
+        }
+
+        Ok(instance)
+    }
+
+    /// `resolve` checks that the host of the instance resolves to at least one address.
+    fn resolve(&self) -> Result<(), RedsyncError> {
+        let (host, port) = match &self.client.get_connection_info().addr {
+            redis::ConnectionAddr::Tcp(host, port) => (host, *port),
+            redis::ConnectionAddr::TcpTls { host, port, .. } => (host, *port),
+            _ => return Ok(()),
+        };
+
+        let mut addrs = (host.as_str(), port)
+            .to_socket_addrs()
+            .map_err(|e| RedsyncError::DnsResolutionFailed(format!("{}: {}", host, e)))?;
+        if addrs.next().is_none() {
+            return Err(RedsyncError::DnsResolutionFailed(format!(
+                "{}: no addresses",
+                host
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// `max_lease` makes the instance reject extensions that would set a lock's TTL beyond
+    /// `max_lease` with `RedsyncError::MaxLeaseExceeded`.
+    pub fn max_lease(mut self, max_lease: Duration) -> Self {
+        self.max_lease = Some(max_lease);
+        self
+    }
+
+    fn client(&self) -> Result<redis::Client, RedsyncError> {
+        let cache = match &self.credentials {
+            Some(cache) => cache,
+            None => return Ok(self.client.clone()),
+        };
+
+        let credentials = cache.get()?;
+        let mut info = self.client.get_connection_info().clone();
+        info.redis.username = credentials.username;
+        info.redis.password = Some(credentials.password);
+
+        redis::Client::open(info).map_err(RedsyncError::ConnectionFailed)
+    }
+
+    /// `acquire_on` acquires `lock` on `conn`.
+    fn acquire_on(
+        &self,
+        conn: &mut impl redis::ConnectionLike,
+        lock: &Lock,
+        timeout: Duration,
+        version: Option<u64>,
+    ) -> Result<(), RedsyncError> {
+        let source = match version {
+            Some(_) => VERSIONED_LOCK_SCRIPT,
+            None => LOCK_SCRIPT,
+        };
+        let script = redis::Script::new(source);
+        let result = retry_transient(timeout, is_transient, || {
+            let mut invocation = script.key(&lock.resource);
+            invocation
+                .key(reservation_key(&lock.resource))
+                .arg(&lock.value)
+                .arg(lock.ttl.as_millis() as u64);
+            if let Some(version) = version {
+                invocation.key(sequence_key(&lock.resource)).arg(version);
+            }
+            invocation.invoke(conn)
+        });
+
+        match result {
+            Ok(v) => lock_reply(v, version),
+            Err(e) => Err(self.script_error(source, e)),
+        }
+    }
+
+    /// `wait_replicas` waits for the writes made on `conn` to reach the replicas required by
+    /// `durability`.
+    fn wait_replicas(
+        &self,
+        conn: &mut impl redis::ConnectionLike,
+        durability: &Durability,
+    ) -> Result<(), RedsyncError> {
+        // WAIT only covers the writes made on the connection it is sent on
+        let acknowledged: u32 = redis::cmd("WAIT")
+            .arg(durability.replicas)
+            .arg(durability.timeout.as_millis() as u64)
+            .query(conn)?;
+        durability.check(acknowledged)
+    }
+
+    /// `script_error` converts a RedisError raised running `script`, naming the script and the
+    /// instance when the server rejected it, e.g. because an ACL denies EVAL.
+    fn script_error(&self, script: &str, e: redis::RedisError) -> RedsyncError {
+        match RedsyncError::from(e) {
+            e @ RedsyncError::CommandFailed(_) => RedsyncError::ScriptFailed {
+                script: script_name(script),
+                instance: self.client.get_connection_info().addr.to_string(),
+                source: Box::new(e),
+            },
+            e => e,
+        }
+    }
+
+    /// `connection` returns the cached connection if there is one, or establishes a new one.
+    fn connection(&self, timeout: Option<Duration>) -> Result<PooledConnection<'_>, RedsyncError> {
+        let cached = self.conn.lock().unwrap().take();
+        let conn = match cached {
+            Some(conn) => conn,
+            None => self.establish(&self.client()?, timeout)?,
+        };
+
+        Ok(PooledConnection::attach(self, conn))
+    }
+
+    /// `establish` establishes a new connection and applies the configured response timeout and
+    /// client name to it. A command that times out leaves the connection broken, so that it is
+    /// dropped by `PooledConnection` instead of being pooled with the reply still unread.
+    fn establish(
+        &self,
+        client: &redis::Client,
+        timeout: Option<Duration>,
+    ) -> Result<redis::Connection, RedsyncError> {
+        let timeout = match (timeout, self.connect_timeout) {
+            (Some(timeout), Some(connect_timeout)) => Some(timeout.min(connect_timeout)),
+            (timeout, connect_timeout) => timeout.or(connect_timeout),
+        };
+        let mut conn = self.open(client, timeout)?;
+
+        if let Some(timeout) = self.response_timeout {
+            conn.set_read_timeout(Some(timeout))
+                .and_then(|_| conn.set_write_timeout(Some(timeout)))
+                .map_err(RedsyncError::ConnectionFailed)?;
+        }
+        if let Some(name) = &self.client_name {
+            redis::cmd("CLIENT")
+                .arg("SETNAME")
+                .arg(name)
+                .query::<()>(&mut conn)
+                .map_err(RedsyncError::from)?;
+        }
+
+        Ok(conn)
+    }
+
+    /// `open` opens a new connection. When the host resolves to multiple addresses, each is
+    /// attempted in turn with its own timeout, alternating between IPv6 and IPv4.
+    fn open(
+        &self,
+        client: &redis::Client,
+        timeout: Option<Duration>,
+    ) -> Result<redis::Connection, RedsyncError> {
+        let info = client.get_connection_info();
+        let addrs = match &info.addr {
+            redis::ConnectionAddr::Tcp(host, port) => (host.as_str(), *port)
+                .to_socket_addrs()
+                .map(|addrs| addrs.collect::<Vec<_>>())
+                .unwrap_or_default(),
+            _ => Vec::new(),
+        };
+
+        if addrs.len() < 2 {
+            return match timeout {
+                Some(timeout) => client.get_connection_with_timeout(timeout),
+                None => client.get_connection(),
+            }
+            .map_err(connection_error);
+        }
+
+        let mut last_error = None;
+        for addr in happy_eyeballs_order(addrs) {
+            let mut info = info.clone();
+            info.addr = redis::ConnectionAddr::Tcp(addr.ip().to_string(), addr.port());
+
+            let result = redis::Client::open(info).and_then(|client| {
+                client.get_connection_with_timeout(timeout.unwrap_or(ADDRESS_TIMEOUT))
+            });
+            match result {
+                Ok(conn) => return Ok(conn),
+                Err(e) => last_error = Some(e),
+            }
+        }
+
+        Err(connection_error(last_error.unwrap()))
+    }
+
+    /// `subscribe_releases` subscribes to release events for locks on `resource`.
+    pub fn subscribe_releases(
+        &self,
+        resource: impl AsRef<[u8]>,
+    ) -> Result<ReleaseSubscription, RedsyncError> {
+        let client = self.client()?;
+        let conn = self.establish(&client, None)?;
+
+        ReleaseSubscription::new(conn, resource.as_ref())
+    }
+
+    fn timeout(&self, ttl: &Duration) -> Duration {
+        Duration::from_millis((ttl.as_millis() as f64 * 0.01) as u64)
+    }
+
+    /// `send_script` sends `command`, an EVALSHA of `source` for `lock`, without reading its
+    /// reply. Once waited on, the reply is converted with `reply`, or the call is made again with
+    /// `fallback` if the script was not run because it is not loaded or the server is busy.
+    fn send_script(
+        &self,
+        lock: &Lock,
+        source: &'static str,
+        command: &redis::Cmd,
+        reply: fn(redis::Value, &Lock) -> Result<(), RedsyncError>,
+        fallback: fn(&Self, &Lock) -> Result<(), RedsyncError>,
+    ) -> PendingCall<'_> {
+        // The connection is only pooled again once its reply has been read in full, and is closed
+        // if sending the command or reading the reply fails partway
+        let mut conn = match self.connection(Some(self.timeout(&lock.ttl))) {
+            Ok(conn) => conn.detach(),
+            Err(e) => return PendingCall::done(Err(e)),
+        };
+        if let Err(e) = conn.send_packed_command(&command.get_packed_command()) {
+            return PendingCall::done(Err(self.script_error(source, e)));
+        }
+
+        PendingCall::waiting(move |lock| {
+            let result = conn.recv_response();
+            if !matches!(&result, Err(e) if !is_server_error(e)) {
+                drop(PooledConnection::attach(self, conn));
+            }
+            match result {
+                Ok(v) => reply(v, lock),
+                Err(e) if e.kind() == redis::ErrorKind::NoScriptError || is_transient(&e) => {
+                    fallback(self, lock)
+                }
+                Err(e) => Err(self.script_error(source, e)),
+            }
+        })
+    }
+
+    /// `query_many` sends `pipe` with one command per lock in a single round trip. Returns `None`
+    /// if the pipeline failed, so that callers can fall back to per-lock results.
+    fn query_many(&self, locks: &[Lock], pipe: &redis::Pipeline) -> Option<Vec<redis::Value>> {
+        let timeout = locks.iter().map(|lock| self.timeout(&lock.ttl)).max()?;
+        let mut conn = self.connection(Some(timeout)).ok()?;
+
+        pipe.query::<Vec<redis::Value>>(&mut conn)
+            .ok()
+            .filter(|values| values.len() == locks.len())
+    }
+}
+
+#[cfg(feature = "redis")]
+impl Instance for RedisInstance {
+    fn acquire(&self, lock: &Lock) -> Result<(), RedsyncError> {
+        let timeout = self.timeout(&lock.ttl);
+        let mut conn = self.connection(Some(timeout))?;
+
+        self.acquire_on(&mut conn, lock, timeout, None)
+    }
+
+    fn send_acquire(&self, lock: &Lock) -> PendingCall<'_> {
+        let mut command = redis::cmd("EVALSHA");
+        command
+            .arg(redis::Script::new(LOCK_SCRIPT).get_hash())
+            .arg(2)
+            .arg(&lock.resource)
+            .arg(reservation_key(&lock.resource))
+            .arg(&lock.value)
+            .arg(lock.ttl.as_millis() as u64);
+
+        self.send_script(
+            lock,
+            LOCK_SCRIPT,
+            &command,
+            |reply, _| lock_reply(reply, None),
+            Self::acquire,
+        )
+    }
+
+    fn send_extend(&self, lock: &Lock) -> PendingCall<'_> {
+        let mut command = redis::cmd("EVALSHA");
+        command
+            .arg(redis::Script::new(EXTEND_SCRIPT).get_hash())
+            .arg(2)
+            .arg(&lock.resource)
+            .arg(reservation_key(&lock.resource))
+            .arg(&lock.value)
+            .arg(lock.ttl.as_millis() as u64)
+            .arg(self.max_lease.map_or(0, |max| max.as_millis() as u64));
+
+        self.send_script(lock, EXTEND_SCRIPT, &command, extend_reply, Self::extend)
+    }
+
+    fn send_release(&self, lock: &Lock) -> PendingCall<'_> {
+        let mut command = redis::cmd("EVALSHA");
+        command
+            .arg(redis::Script::new(UNLOCK_SCRIPT).get_hash())
+            .arg(1)
+            .arg(&lock.resource)
+            .arg(&lock.value);
+
+        self.send_script(lock, UNLOCK_SCRIPT, &command, release_reply, Self::release)
+    }
+
+    fn acquire_replicated(&self, lock: &Lock, durability: &Durability) -> Result<(), RedsyncError> {
+        let timeout = self.timeout(&lock.ttl);
+        let mut conn = self.connection(Some(timeout))?;
+
+        self.acquire_on(&mut conn, lock, timeout, None)?;
+        self.wait_replicas(&mut conn, durability)
+    }
+
+    fn acquire_if_version(
+        &self,
+        lock: &Lock,
+        version: u64,
+        durability: Option<&Durability>,
+    ) -> Result<(), RedsyncError> {
+        let timeout = self.timeout(&lock.ttl);
+        let mut conn = self.connection(Some(timeout))?;
+
+        self.acquire_on(&mut conn, lock, timeout, Some(version))?;
+        match durability {
+            Some(durability) => self.wait_replicas(&mut conn, durability),
+            None => Ok(()),
+        }
+    }
+
+    fn extend(&self, lock: &Lock) -> Result<(), RedsyncError> {
+        let timeout = self.timeout(&lock.ttl);
+        let mut conn = self.connection(Some(timeout))?;
+
+        let script = redis::Script::new(EXTEND_SCRIPT);
+        let result = retry_transient(timeout, is_transient, || {
+            script
+                .key(&lock.resource)
+                .key(reservation_key(&lock.resource))
+                .arg(&lock.value)
+                .arg(lock.ttl.as_millis() as u64)
+                .arg(self.max_lease.map_or(0, |max| max.as_millis() as u64))
+                .invoke(&mut conn)
+        });
+
+        match result {
+            Ok(v) => extend_reply(v, lock),
+            Err(e) => Err(self.script_error(EXTEND_SCRIPT, e)),
+        }
+    }
+
+    fn reacquire(&self, lock: &Lock) -> Result<(), RedsyncError> {
+        let timeout = self.timeout(&lock.ttl);
+        let mut conn = self.connection(Some(timeout))?;
+
+        let script = redis::Script::new(REACQUIRE_SCRIPT);
+        let result = retry_transient(timeout, is_transient, || {
+            script
+                .key(&lock.resource)
+                .key(reservation_key(&lock.resource))
+                .arg(&lock.value)
+                .arg(lock.ttl.as_millis() as u64)
+                .invoke(&mut conn)
+        });
+
+        match result {
+            Ok(redis::Value::Int(1)) => Ok(()),
+            Ok(redis::Value::Int(0)) => Err(RedsyncError::ResourceLocked),
+            Ok(redis::Value::Int(-2)) => Err(RedsyncError::ResourceReserved),
+            Ok(v) => Err(RedsyncError::UnexpectedResponse(v)),
+            Err(e) => Err(self.script_error(REACQUIRE_SCRIPT, e)),
+        }
+    }
+
+    fn release(&self, lock: &Lock) -> Result<(), RedsyncError> {
+        let timeout = self.timeout(&lock.ttl);
+        let mut conn = self.connection(Some(timeout))?;
+
+        let script = redis::Script::new(UNLOCK_SCRIPT);
+        let result = retry_transient(timeout, is_transient, || {
+            script
+                .key(&lock.resource)
+                .arg(&lock.value)
+                .invoke(&mut conn)
+        });
+
+        match result {
+            Ok(v) => release_reply(v, lock),
+            Err(e) => Err(self.script_error(UNLOCK_SCRIPT, e)),
+        }
+    }
+
+    fn ttl(&self, lock: &Lock) -> Result<Duration, RedsyncError> {
+        let timeout = self.timeout(&lock.ttl);
+        let mut conn = self.connection(Some(timeout))?;
+
+        let script = redis::Script::new(TTL_SCRIPT);
+        let result = retry_transient(timeout, is_transient, || {
+            script
+                .key(&lock.resource)
+                .arg(&lock.value)
+                .invoke(&mut conn)
+        });
+
+        match result {
+            Ok(redis::Value::Int(n)) if n >= 0 => Ok(Duration::from_millis(n as u64)),
+            Ok(redis::Value::Int(-3)) => Err(RedsyncError::InvalidLease),
+            Ok(v) => Err(RedsyncError::UnexpectedResponse(v)),
+            Err(e) => Err(self.script_error(TTL_SCRIPT, e)),
+        }
+    }
+
+    fn guarded_set(&self, lock: &Lock, key: &str, value: &str) -> Result<(), RedsyncError> {
+        let timeout = self.timeout(&lock.ttl);
+        let mut conn = self.connection(Some(timeout))?;
+
+        let script = redis::Script::new(GUARDED_SET_SCRIPT);
+        let result = retry_transient(timeout, is_transient, || {
+            script
+                .key(&lock.resource)
+                .key(key)
+                .arg(&lock.value)
+                .arg(value)
+                .invoke(&mut conn)
+        });
+
+        match result {
+            Ok(redis::Value::Int(1)) => Ok(()),
+            Ok(redis::Value::Int(0)) => Err(RedsyncError::InvalidLease),
+            Ok(v) => Err(RedsyncError::UnexpectedResponse(v)),
+            Err(e) => Err(self.script_error(GUARDED_SET_SCRIPT, e)),
+        }
+    }
+
+    fn peek(&self, resource: &[u8]) -> Result<Option<Duration>, RedsyncError> {
+        let mut conn = self.connection(None)?;
+
+        let result = redis::cmd("PTTL").arg(resource).query(&mut conn);
+
+        match result {
+            Ok(redis::Value::Int(-2)) => Ok(None),
+            Ok(redis::Value::Int(-1)) => Ok(Some(Duration::MAX)),
+            Ok(redis::Value::Int(n)) if n >= 0 => Ok(Some(Duration::from_millis(n as u64))),
+            Ok(v) => Err(RedsyncError::UnexpectedResponse(v)),
+            Err(e) => Err(RedsyncError::from(e)),
+        }
+    }
+
+    fn acquire_many(&self, locks: &[Lock]) -> Vec<Result<(), RedsyncError>> {
+        let mut pipe = redis::pipe();
+        for lock in locks {
+            pipe.cmd("EVAL")
+                .arg(LOCK_SCRIPT)
+                .arg(2)
+                .arg(&lock.resource)
+                .arg(reservation_key(&lock.resource))
+                .arg(&lock.value)
+                .arg(lock.ttl.as_millis() as u64);
+        }
+
+        let results = self.query_many(locks, &pipe);
+        match results {
+            Some(values) => values
+                .into_iter()
+                .map(|value| match value {
+                    redis::Value::Okay => Ok(()),
+                    redis::Value::Nil => Err(RedsyncError::ResourceLocked),
+                    redis::Value::Int(-2) => Err(RedsyncError::ResourceReserved),
+                    v => Err(RedsyncError::UnexpectedResponse(v)),
+                })
+                .collect(),
+            None => locks.iter().map(|lock| self.acquire(lock)).collect(),
+        }
+    }
+
+    fn extend_many(&self, locks: &[Lock]) -> Vec<Result<(), RedsyncError>> {
+        let max_lease = self.max_lease.map_or(0, |max| max.as_millis() as u64);
+        let mut pipe = redis::pipe();
+        for lock in locks {
+            pipe.cmd("EVAL")
+                .arg(EXTEND_SCRIPT)
+                .arg(2)
+                .arg(&lock.resource)
+                .arg(reservation_key(&lock.resource))
+                .arg(&lock.value)
+                .arg(lock.ttl.as_millis() as u64)
+                .arg(max_lease);
+        }
+
+        let results = self.query_many(locks, &pipe);
+        match results {
+            Some(values) => values
+                .into_iter()
+                .zip(locks)
+                .map(|(value, lock)| match value {
+                    redis::Value::Int(1) => Ok(()),
+                    redis::Value::Int(0) => Err(RedsyncError::InvalidLease),
+                    redis::Value::Int(-1) => Err(RedsyncError::MaxLeaseExceeded(lock.ttl)),
+                    redis::Value::Int(-2) => Err(RedsyncError::ResourceReserved),
+                    v => Err(RedsyncError::UnexpectedResponse(v)),
+                })
+                .collect(),
+            None => locks.iter().map(|lock| self.extend(lock)).collect(),
+        }
+    }
+
+    fn release_many(&self, locks: &[Lock]) -> Vec<Result<(), RedsyncError>> {
+        let mut pipe = redis::pipe();
+        for lock in locks {
+            pipe.cmd("EVAL")
+                .arg(UNLOCK_SCRIPT)
+                .arg(1)
+                .arg(&lock.resource)
+                .arg(&lock.value);
+        }
+
+        let results = self.query_many(locks, &pipe);
+        match results {
+            Some(values) => values
+                .into_iter()
+                .map(|value| match value {
+                    redis::Value::Int(1) => Ok(()),
+                    redis::Value::Int(0) => Err(RedsyncError::InvalidLease),
+                    v => Err(RedsyncError::UnexpectedResponse(v)),
+                })
+                .collect(),
+            None => locks.iter().map(|lock| self.release(lock)).collect(),
+        }
+    }
+
+    fn ping(&self) -> Result<(), RedsyncError> {
+        let mut conn = self.connection(None)?;
+
+        redis::cmd("PING")
+            .query::<String>(&mut conn)
+            .map(|_| ())
+            .map_err(RedsyncError::from)
+    }
+
+    fn diagnostics(&self) -> Result<NodeDiagnostics, RedsyncError> {
+        let mut conn = self.connection(None)?;
+
+        let mut config = |name: &str| {
+            redis::cmd("CONFIG")
+                .arg("GET")
+                .arg(name)
+                .query::<Vec<String>>(&mut conn)
+                .ok()
+                .and_then(|reply| reply.into_iter().nth(1))
+        };
+
+        let maxmemory_policy = config("maxmemory-policy");
+        let appendonly = config("appendonly").map(|v| v == "yes");
+        let save = config("save");
+
+        let before = SystemTime::now();
+        let (secs, micros): (u64, u64) = redis::cmd("TIME")
+            .query(&mut conn)
+            .map_err(RedsyncError::from)?;
+        let after = SystemTime::now();
+        let server = UNIX_EPOCH + Duration::from_secs(secs) + Duration::from_micros(micros);
+
+        Ok(NodeDiagnostics {
+            maxmemory_policy,
+            appendonly,
+            save,
+            clock_skew: Some(clock_skew(before, after, server)),
+        })
+    }
+
+    fn time(&self) -> Result<SystemTime, RedsyncError> {
+        let mut conn = self.connection(None)?;
+
+        let (secs, micros): (u64, u64) = redis::cmd("TIME").query(&mut conn)?;
+        Ok(UNIX_EPOCH + Duration::from_secs(secs) + Duration::from_micros(micros))
+    }
+
+    fn incr(&self, key: &str, delta: i64, expiry: Duration) -> Result<i64, RedsyncError> {
+        let mut conn = self.connection(None)?;
+
+        redis::Script::new(INCR_SCRIPT)
+            .key(key)
+            .arg(delta)
+            .arg(expiry.as_millis() as u64)
+            .invoke(&mut conn)
+            .map_err(|e| self.script_error(INCR_SCRIPT, e))
+    }
+
+    fn incr_stats(&self, resource: &[u8], fields: &[(&str, i64)]) -> Result<(), RedsyncError> {
+        let mut conn = self.connection(None)?;
+        let key = stats_key(resource);
+
+        let mut pipe = redis::pipe();
+        for (field, value) in fields {
+            pipe.cmd("HINCRBY")
+                .arg(&key)
+                .arg(*field)
+                .arg(*value)
+                .ignore();
+        }
+        pipe.cmd("PEXPIRE")
+            .arg(&key)
+            .arg(STATS_WINDOW.as_millis() as u64)
+            .ignore();
+
+        pipe.query(&mut conn).map_err(RedsyncError::from)
+    }
+
+    fn advance_sequence(&self, lock: &Lock, floor: i64, step: i64) -> Result<i64, RedsyncError> {
+        let timeout = self.timeout(&lock.ttl);
+        let mut conn = self.connection(Some(timeout))?;
+
+        let script = redis::Script::new(SEQUENCE_SCRIPT);
+        let result = retry_transient(timeout, is_transient, || {
+            script
+                .key(sequence_key(&lock.resource))
+                .arg(floor)
+                .arg(step)
+                .invoke(&mut conn)
+        });
+
+        result.map_err(|e| self.script_error(SEQUENCE_SCRIPT, e))
+    }
+
+    fn read_stats(&self, resource: &[u8]) -> Result<Vec<(String, i64)>, RedsyncError> {
+        let mut conn = self.connection(None)?;
+
+        redis::cmd("HGETALL")
+            .arg(stats_key(resource))
+            .query(&mut conn)
+            .map_err(RedsyncError::from)
+    }
+
+    fn append_audit(
+        &self,
+        fields: &[(&str, String)],
+        retention: usize,
+    ) -> Result<(), RedsyncError> {
+        let mut conn = self.connection(None)?;
+
+        redis::cmd("XADD")
+            .arg(AUDIT_STREAM)
+            .arg("MAXLEN")
+            .arg("~")
+            .arg(retention)
+            .arg("*")
+            .arg(fields)
+            .query(&mut conn)
+            .map_err(RedsyncError::from)
+    }
+
+    fn read_audit(&self, count: usize) -> Result<Vec<AuditEvent>, RedsyncError> {
+        let mut conn = self.connection(None)?;
+
+        let entries: Vec<redis::Value> = redis::cmd("XREVRANGE")
+            .arg(AUDIT_STREAM)
+            .arg("+")
+            .arg("-")
+            .arg("COUNT")
+            .arg(count)
+            .query(&mut conn)?;
+
+        let mut events = Vec::with_capacity(entries.len());
+        for entry in entries {
+            let (id, fields) = redis::from_redis_value(&entry)?;
+            events.extend(AuditEvent::from_entry(id, fields));
+        }
+
+        Ok(events)
+    }
+
+    fn reserve(&self, reservation: &Reservation) -> Result<(), RedsyncError> {
+        let mut conn = self.connection(None)?;
+
+        let result = redis::Script::new(RESERVE_SCRIPT)
+            .key(&reservation.resource)
+            .key(reservation.key())
+            .arg(reservation.marker())
+            .arg(reservation.start_millis())
+            .arg(reservation.remaining().as_millis() as u64)
+            .invoke(&mut conn);
+
+        match result {
+            Ok(redis::Value::Int(1)) => Ok(()),
+            Ok(redis::Value::Int(0)) => Err(RedsyncError::ResourceReserved),
+            Ok(redis::Value::Int(-1)) => Err(RedsyncError::ResourceLocked),
+            Ok(v) => Err(RedsyncError::UnexpectedResponse(v)),
+            Err(e) => Err(self.script_error(RESERVE_SCRIPT, e)),
+        }
+    }
+
+    fn cancel_reservation(&self, reservation: &Reservation) -> Result<(), RedsyncError> {
+        let mut conn = self.connection(None)?;
+
+        redis::Script::new(CANCEL_RESERVATION_SCRIPT)
+            .key(reservation.key())
+            .arg(reservation.marker())
+            .invoke::<i64>(&mut conn)
+            .map(|_| ())
+            .map_err(|e| self.script_error(CANCEL_RESERVATION_SCRIPT, e))
+    }
+
+    fn force_release_prefix(
+        &self,
+        prefix: &str,
+        dry_run: bool,
+        bare_tokens: bool,
+    ) -> Result<Vec<String>, RedsyncError> {
+        let mut conn = self.connection(None)?;
+
+        let keys: Vec<String> = redis::Commands::scan_match(&mut conn, scan_pattern(prefix))
+            .map_err(RedsyncError::from)?
+            .collect();
+
+        // Keys are checked and deleted one at a time, as they may live on different cluster slots
+        let script = redis::Script::new(FORCE_RELEASE_SCRIPT);
+        let mut released = Vec::new();
+        for key in keys {
+            let result = script
+                .key(&key)
+                .arg(if dry_run { "1" } else { "0" })
+                .arg(if bare_tokens { "1" } else { "0" })
+                .invoke::<i64>(&mut conn)
+                .map_err(|e| self.script_error(FORCE_RELEASE_SCRIPT, e))?;
+            if result == 1 {
+                released.push(key);
+            }
+        }
+
+        Ok(released)
+    }
+}
+
+#[cfg(all(test, feature = "redis"))]
+mod tests {
+    use super::*;
+    use crate::record::{LockRecord, RecordVersion};
+    use std::matches;
+    use std::thread;
+    use std::time::Instant;
+
+    struct TestHelper {
+        instance: RedisInstance,
+        lock: Lock,
+    }
+
+    fn setup(resource: &str) -> TestHelper {
+        // These tests require running a redis server on 127.0.0.1:6379
+        let instance = RedisInstance::new("redis://127.0.0.1:6379").unwrap();
+        let lock = Lock::new(resource, "1", Duration::from_millis(500), Instant::now(), 0);
+
+        TestHelper { instance, lock }
+    }
+
+    #[test]
+    fn script_error() -> Result<(), RedsyncError> {
+        let instance = RedisInstance::new("redis://127.0.0.1:6379")?;
+
+        let e = redis::RedisError::from((
+            redis::ErrorKind::ResponseError,
+            "NOPERM",
+            String::from("this user has no permissions to run the 'evalsha' command"),
+        ));
+        let e = instance.script_error(UNLOCK_SCRIPT, e);
+        assert!(matches!(
+            &e,
+            RedsyncError::ScriptFailed { script: "release", instance, .. } if instance == "127.0.0.1:6379"
+        ));
+        assert!(e
+            .to_string()
+            .starts_with("release script failed on 127.0.0.1:6379"));
+
+        let e =
+            redis::RedisError::from(std::io::Error::from(std::io::ErrorKind::ConnectionRefused));
+        let e = instance.script_error(UNLOCK_SCRIPT, e);
+        assert!(matches!(e, RedsyncError::ConnectionFailed(_)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn happy_eyeballs_order() {
+        let addrs: Vec<SocketAddr> = ["[::1]:6379", "[::2]:6379", "[::3]:6379", "127.0.0.1:6379"]
+            .iter()
+            .map(|a| a.parse().unwrap())
+            .collect();
+
+        let ordered: Vec<String> = super::happy_eyeballs_order(addrs)
+            .iter()
+            .map(|a| a.to_string())
+            .collect();
+        assert_eq!(
+            ordered,
+            ["[::1]:6379", "127.0.0.1:6379", "[::2]:6379", "[::3]:6379"]
+        );
+    }
+
+    #[test]
+    fn connect_fallback() {
+        // localhost may resolve to both ::1 and 127.0.0.1, neither of which listens on port 1
+        let instance = RedisInstance::new("redis://localhost:1").unwrap();
+        assert!(matches!(
+            instance.ping(),
+            Err(RedsyncError::ConnectionFailed(_))
+        ));
+    }
+
+    #[test]
+    fn connect_unresolvable_host() {
+        let attempt =
+            RedisInstance::connect_with_retry("redis://redsync.invalid:6379", 2, Duration::ZERO);
+        assert!(matches!(attempt, Err(RedsyncError::DnsResolutionFailed(_))));
+
+        let attempt = RedisInstance::connect_with_retry("redis://127.0.0.1:1", 2, Duration::ZERO);
+        assert!(matches!(attempt, Err(RedsyncError::ConnectionFailed(_))));
+    }
+
+    #[test]
+    fn retry_transient_error() {
+        let mut calls = 0;
+        let result: Result<(), redis::RedisError> =
+            retry_transient(Duration::from_millis(50), is_transient, || {
+                calls += 1;
+                Err(redis::RedisError::from((
+                    redis::ErrorKind::BusyLoadingError,
+                    "Redis is loading the dataset in memory",
+                )))
+            });
+        assert!(result.is_err());
+        assert!(calls > 1);
+
+        let mut calls = 0;
+        let result: Result<(), redis::RedisError> =
+            retry_transient(Duration::from_millis(50), is_transient, || {
+                calls += 1;
+                Err(redis::RedisError::from((
+                    redis::ErrorKind::ResponseError,
+                    "script rejected",
+                )))
+            });
+        assert!(result.is_err());
+        assert_eq!(calls, 1);
+    }
+
+    #[test]
+    fn url_error() {
+        let instance = RedisInstance::new("127.0.0.1:6379");
+        assert!(matches!(
+            instance,
+            Err(RedsyncError::ConnectionFailed { .. })
+        ));
+    }
+
+    #[test]
+    fn cluster_redirection() {
+        let e = redis::RedisError::from((
+            redis::ErrorKind::Moved,
+            "An error was signalled by the server",
+            String::from("3999 127.0.0.1:6381"),
+        ));
+
+        let e = RedsyncError::from(e);
+        assert_eq!(
+            e,
+            RedsyncError::ClusterRedirection(3999, String::from("127.0.0.1:6381"))
+        );
+    }
+
+    #[test]
+    fn ping() {
+        let test = setup("ping");
+
+        let attempt = test.instance.ping();
+        assert!(attempt.is_ok());
+    }
+
+    #[test]
+    fn diagnostics() -> Result<(), RedsyncError> {
+        let test = setup("diagnostics");
 
-const UNLOCK_SCRIPT: &str = "\
-if redis.call(\"get\", KEYS[1]) == ARGV[1] then
-    return redis.call(\"del\", KEYS[1])
-else
-    return 0
-end";
+        let diagnostics = test.instance.diagnostics()?;
+        assert!(diagnostics.maxmemory_policy.is_some());
+        assert!(diagnostics.clock_skew.is_some());
 
-const EXTEND_SCRIPT: &str = "\
-if redis.call(\"get\", KEYS[1]) == ARGV[1] then
-    return redis.call(\"pexpire\", KEYS[1], ARGV[2])
-else
-    return 0
-end";
+        Ok(())
+    }
 
-/// `RedisInstance` is the implementation of the Instance trait for a Redis server.
-pub struct RedisInstance {
-    client: redis::Client,
-}
+    #[test]
+    fn time() -> Result<(), RedsyncError> {
+        let test = setup("time");
 
-impl RedisInstance {
-    pub fn new<T: redis::IntoConnectionInfo>(params: T) -> Result<Self, RedsyncError> {
-        let client = redis::Client::open(params).map_err(RedsyncError::RedisError)?;
-        Ok(Self { client })
-    }
+        let time = test.instance.time()?;
+        assert!(time > UNIX_EPOCH);
 
-    fn timeout(&self, ttl: &Duration) -> Duration {
-        Duration::from_millis((ttl.as_millis() as f64 * 0.01) as u64)
+        Ok(())
     }
-}
 
-impl Instance for RedisInstance {
-    fn acquire(&self, lock: &Lock) -> Result<(), RedsyncError> {
-        let mut conn = self
-            .client
-            .get_connection_with_timeout(self.timeout(&lock.ttl))
-            .map_err(RedsyncError::RedisError)?;
+    #[test]
+    fn connection_reuse() -> Result<(), RedsyncError> {
+        let test = setup("connection_reuse");
+        test.instance.ping()?;
+        assert!(test.instance.conn.lock().unwrap().is_some());
 
-        let result = redis::Script::new(LOCK_SCRIPT)
-            .key(&lock.resource)
-            .arg(&lock.value)
-            .arg(lock.ttl.as_millis() as u64)
-            .invoke(&mut conn);
+        let attempt = test.instance.acquire(&test.lock);
+        assert!(attempt.is_ok());
+        assert!(test.instance.conn.lock().unwrap().is_some());
 
-        match result {
-            Ok(redis::Value::Okay) => Ok(()),
-            Ok(redis::Value::Nil) => Err(RedsyncError::ResourceLocked),
-            Ok(v) => Err(RedsyncError::UnexpectedResponse(v)),
-            Err(e) => Err(RedsyncError::RedisError(e)),
-        }
+        Ok(())
     }
 
-    fn extend(&self, lock: &Lock) -> Result<(), RedsyncError> {
-        let mut conn = self
-            .client
-            .get_connection_with_timeout(self.timeout(&lock.ttl))
-            .map_err(RedsyncError::RedisError)?;
+    /// `fake_server` serves connections that answer each command with `replies(n)`, where `n`
+    /// is the index of the connection, after waiting for `delay(n)`. The CLIENT commands sent
+    /// when connecting are acknowledged straight away.
+    fn fake_server(delay: fn(usize) -> Duration, replies: fn(usize) -> &'static [u8]) -> String {
+        use std::io::{Read, Write};
 
-        let result = redis::Script::new(EXTEND_SCRIPT)
-            .key(&lock.resource)
-            .arg(&lock.value)
-            .arg(lock.ttl.as_millis() as u64)
-            .invoke(&mut conn);
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        thread::spawn(move || {
+            for (n, stream) in listener.incoming().enumerate() {
+                let mut stream = stream.unwrap();
+                thread::spawn(move || {
+                    let mut buf = [0; 512];
+                    while let Ok(read) = stream.read(&mut buf) {
+                        if read == 0 {
+                            return;
+                        }
+                        let commands = String::from_utf8_lossy(&buf[..read]).into_owned();
+                        for command in commands.split('*').filter(|c| !c.is_empty()) {
+                            if command.contains("CLIENT") {
+                                let _ = stream.write_all(b"+OK\r\n");
+                            } else {
+                                thread::sleep(delay(n));
+                                let _ = stream.write_all(replies(n));
+                            }
+                        }
+                    }
+                });
+            }
+        });
 
-        match result {
-            Ok(redis::Value::Int(1)) => Ok(()),
-            Ok(redis::Value::Int(0)) => Err(RedsyncError::InvalidLease),
-            Ok(v) => Err(RedsyncError::UnexpectedResponse(v)),
-            Err(e) => Err(RedsyncError::RedisError(e)),
-        }
+        format!("redis://{}", addr)
     }
 
-    fn release(&self, lock: &Lock) -> Result<(), RedsyncError> {
-        let mut conn = self
-            .client
-            .get_connection_with_timeout(self.timeout(&lock.ttl))
-            .map_err(RedsyncError::RedisError)?;
+    fn query_ping(instance: &RedisInstance) -> redis::RedisResult<String> {
+        let mut conn = instance.connection(None).unwrap();
+        redis::cmd("PING").query(&mut conn)
+    }
 
-        let result = redis::Script::new(UNLOCK_SCRIPT)
-            .key(&lock.resource)
-            .arg(&lock.value)
-            .invoke(&mut conn);
+    #[test]
+    fn connection_dropped_on_timeout() {
+        // The first connection replies too late, leaving its reply unread
+        let url = fake_server(
+            |n| Duration::from_millis(if n == 0 { 200 } else { 0 }),
+            |n| if n == 0 { b"+STALE\r\n" } else { b"+FRESH\r\n" },
+        );
+        let mut instance = RedisInstance::new(url.as_str()).unwrap();
+        instance.response_timeout = Some(Duration::from_millis(50));
 
-        match result {
-            Ok(redis::Value::Int(1)) => Ok(()),
-            Ok(redis::Value::Int(0)) => Err(RedsyncError::InvalidLease),
-            Ok(v) => Err(RedsyncError::UnexpectedResponse(v)),
-            Err(e) => Err(RedsyncError::RedisError(e)),
-        }
+        let err = query_ping(&instance).unwrap_err();
+        assert!(err.is_timeout());
+        assert!(instance.conn.lock().unwrap().is_none());
+
+        thread::sleep(Duration::from_millis(300));
+        assert_eq!(query_ping(&instance).unwrap(), "FRESH");
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::matches;
-    use std::thread;
-    use std::time::Instant;
+    #[test]
+    fn connection_dropped_on_parse_error() {
+        // The first connection sends a malformed reply followed by one left unread
+        let url = fake_server(
+            |_| Duration::ZERO,
+            |n| {
+                if n == 0 {
+                    b":x\r\n+STALE\r\n"
+                } else {
+                    b"+FRESH\r\n"
+                }
+            },
+        );
+        let instance = RedisInstance::new(url.as_str()).unwrap();
 
-    struct TestHelper {
-        instance: RedisInstance,
-        lock: Lock,
+        assert!(query_ping(&instance).is_err());
+        assert!(instance.conn.lock().unwrap().is_none());
+        assert_eq!(query_ping(&instance).unwrap(), "FRESH");
     }
 
-    fn setup(resource: &str) -> TestHelper {
-        // These tests require running a redis server on 127.0.0.1:6379
-        let instance = RedisInstance::new("redis://127.0.0.1:6379").unwrap();
-        let lock = Lock {
-            resource: String::from(resource),
-            value: String::from("1"),
-            ttl: Duration::from_millis(500),
-            expiry: Instant::now(),
-        };
+    #[test]
+    fn send_acquire_drops_connection_on_timeout() {
+        let url = fake_server(
+            |n| Duration::from_millis(if n == 0 { 200 } else { 0 }),
+            |n| if n == 0 { b"+OK\r\n" } else { b"+FRESH\r\n" },
+        );
+        let mut test = setup("send_acquire_drops_connection_on_timeout");
+        test.instance = RedisInstance::new(url.as_str()).unwrap();
+        test.instance.response_timeout = Some(Duration::from_millis(50));
 
-        TestHelper { instance, lock }
+        let pending = test.instance.send_acquire(&test.lock);
+        assert!(pending.wait(&test.lock).is_err());
+        assert!(test.instance.conn.lock().unwrap().is_none());
+
+        thread::sleep(Duration::from_millis(300));
+        assert_eq!(query_ping(&test.instance).unwrap(), "FRESH");
     }
 
     #[test]
-    fn url_error() {
-        let instance = RedisInstance::new("127.0.0.1:6379");
-        assert!(matches!(instance, Err(RedsyncError::RedisError { .. })));
+    fn connection_reused_after_error_reply() {
+        let url = fake_server(|_| Duration::ZERO, |_| b"-ERR unknown command\r\n");
+        let instance = RedisInstance::new(url.as_str()).unwrap();
+
+        let err = query_ping(&instance).unwrap_err();
+        assert_eq!(err.kind(), redis::ErrorKind::ResponseError);
+        assert!(instance.conn.lock().unwrap().is_some());
     }
 
     #[test]
@@ -143,6 +1694,63 @@ mod tests {
         assert!(attempt.is_ok());
     }
 
+    #[test]
+    fn send_acquire() -> Result<(), RedsyncError> {
+        let test = setup("send_acquire");
+
+        let pending = test.instance.send_acquire(&test.lock);
+        pending.wait(&test.lock)?;
+
+        let pending = test.instance.send_acquire(&test.lock);
+        let attempt = pending.wait(&test.lock);
+        assert!(matches!(attempt, Err(RedsyncError::ResourceLocked)));
+
+        // Unread replies must not be left on a pooled connection
+        drop(test.instance.send_acquire(&test.lock));
+        test.instance.release(&test.lock)?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn send_extend_release() -> Result<(), RedsyncError> {
+        let test = setup("send_extend_release");
+
+        let attempt = test.instance.send_extend(&test.lock).wait(&test.lock);
+        assert!(matches!(attempt, Err(RedsyncError::InvalidLease)));
+
+        test.instance.acquire(&test.lock)?;
+        test.instance.send_extend(&test.lock).wait(&test.lock)?;
+        test.instance.send_release(&test.lock).wait(&test.lock)?;
+
+        let attempt = test.instance.send_release(&test.lock).wait(&test.lock);
+        assert!(matches!(attempt, Err(RedsyncError::InvalidLease)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn acquire_if_version() -> Result<(), RedsyncError> {
+        let test = setup("acquire_if_version");
+        let version = test.instance.advance_sequence(&test.lock, 0, 1)? as u64;
+
+        let attempt = test
+            .instance
+            .acquire_if_version(&test.lock, version - 1, None);
+        assert!(matches!(
+            attempt,
+            Err(RedsyncError::VersionMismatch { actual, .. }) if actual == version
+        ));
+        assert_eq!(
+            test.instance.advance_sequence(&test.lock, 0, 0)? as u64,
+            version
+        );
+        test.instance
+            .acquire_if_version(&test.lock, version, None)?;
+
+        Ok(())
+    }
+
     #[test]
     fn acquire_locked_resource() -> Result<(), RedsyncError> {
         let test = setup("acquire_locked_resource");
@@ -171,7 +1779,7 @@ mod tests {
         let mut test = setup("extend_invalid_lock");
         test.instance.acquire(&test.lock)?;
 
-        test.lock.value = String::from("2");
+        test.lock.value = b"2".to_vec();
         let attempt = test.instance.extend(&test.lock);
         assert!(matches!(attempt, Err(RedsyncError::InvalidLease)));
 
@@ -190,6 +1798,97 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn ttl() -> Result<(), RedsyncError> {
+        let test = setup("ttl");
+        test.instance.acquire(&test.lock)?;
+
+        let attempt = test.instance.ttl(&test.lock);
+        assert!(matches!(attempt, Ok(ttl) if ttl <= test.lock.ttl));
+
+        Ok(())
+    }
+
+    #[test]
+    fn ttl_invalid_lock() -> Result<(), RedsyncError> {
+        let mut test = setup("ttl_invalid_lock");
+        test.instance.acquire(&test.lock)?;
+
+        test.lock.value = b"2".to_vec();
+        let attempt = test.instance.ttl(&test.lock);
+        assert!(matches!(attempt, Err(RedsyncError::InvalidLease)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn force_release_prefix() -> Result<(), RedsyncError> {
+        let mut test = setup("force_release_prefix:1");
+        test.lock.value = LockRecord::new(RecordVersion::V2, "1").encode();
+        test.instance.acquire(&test.lock)?;
+        test.lock.resource = b"force_release_prefix:2".to_vec();
+        test.instance.acquire(&test.lock)?;
+        test.lock.resource = b"force_release_prefix:3".to_vec();
+        test.lock.value = b"a+b/c=".to_vec();
+        test.instance.acquire(&test.lock)?;
+
+        let mut keys = test
+            .instance
+            .force_release_prefix("force_release_prefix:", true, false)?;
+        keys.sort();
+        assert_eq!(keys, ["force_release_prefix:1", "force_release_prefix:2"]);
+
+        // Counters and other keys sharing the prefix are left alone unless bare tokens are matched
+        test.instance
+            .incr("force_release_prefix:count", 1, Duration::from_secs(1))?;
+        test.instance
+            .force_release_prefix("force_release_prefix:", false, false)?;
+        let count = test
+            .instance
+            .incr("force_release_prefix:count", 0, Duration::from_secs(1))?;
+        assert_eq!(count, 1);
+
+        let mut keys = test
+            .instance
+            .force_release_prefix("force_release_prefix:", false, true)?;
+        keys.sort();
+        assert_eq!(
+            keys,
+            ["force_release_prefix:3", "force_release_prefix:count"]
+        );
+        let attempt = test.instance.acquire(&test.lock);
+        assert!(attempt.is_ok());
+
+        Ok(())
+    }
+
+    #[test]
+    fn guarded_set() -> Result<(), RedsyncError> {
+        let test = setup("guarded_set");
+        test.instance.acquire(&test.lock)?;
+
+        let attempt = test
+            .instance
+            .guarded_set(&test.lock, "guarded_set:data", "1");
+        assert!(attempt.is_ok());
+
+        Ok(())
+    }
+
+    #[test]
+    fn guarded_set_invalid_lock() -> Result<(), RedsyncError> {
+        let mut test = setup("guarded_set_invalid_lock");
+        test.instance.acquire(&test.lock)?;
+
+        test.lock.value = b"2".to_vec();
+        let attempt = test
+            .instance
+            .guarded_set(&test.lock, "guarded_set_invalid_lock:data", "1");
+        assert!(matches!(attempt, Err(RedsyncError::InvalidLease)));
+
+        Ok(())
+    }
+
     #[test]
     fn release() -> Result<(), RedsyncError> {
         let test = setup("release");
@@ -201,12 +1900,243 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn extend_max_lease() -> Result<(), RedsyncError> {
+        let mut test = setup("extend_max_lease");
+        let instance = test.instance.max_lease(Duration::from_secs(1));
+        instance.acquire(&test.lock)?;
+
+        test.lock.ttl = Duration::from_secs(2);
+        let attempt = instance.extend(&test.lock);
+        assert_eq!(
+            attempt,
+            Err(RedsyncError::MaxLeaseExceeded(Duration::from_secs(2)))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn peek() -> Result<(), RedsyncError> {
+        let test = setup("peek");
+        assert_eq!(test.instance.peek(b"peek")?, None);
+
+        test.instance.acquire(&test.lock)?;
+        assert!(test.instance.peek(b"peek")?.is_some());
+
+        Ok(())
+    }
+
+    #[test]
+    fn incr() -> Result<(), RedsyncError> {
+        let test = setup("incr");
+        let _ = test.instance.incr("incr", 0, Duration::from_millis(1));
+        thread::sleep(Duration::from_millis(5));
+
+        assert_eq!(test.instance.incr("incr", 2, Duration::from_secs(1))?, 2);
+        assert_eq!(test.instance.incr("incr", -1, Duration::from_secs(1))?, 1);
+
+        // Later increments do not push the window forward
+        let _ = test
+            .instance
+            .incr("incr_window", 0, Duration::from_millis(1));
+        thread::sleep(Duration::from_millis(5));
+        assert_eq!(
+            test.instance
+                .incr("incr_window", 1, Duration::from_millis(100))?,
+            1
+        );
+        thread::sleep(Duration::from_millis(60));
+        assert_eq!(
+            test.instance
+                .incr("incr_window", 1, Duration::from_millis(100))?,
+            2
+        );
+        thread::sleep(Duration::from_millis(60));
+        assert_eq!(
+            test.instance
+                .incr("incr_window", 1, Duration::from_millis(100))?,
+            1
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn advance_sequence() -> Result<(), RedsyncError> {
+        let test = setup("advance_sequence");
+        let floor = test.instance.advance_sequence(&test.lock, 0, 0)?;
+
+        assert_eq!(test.instance.advance_sequence(&test.lock, 0, 1)?, floor + 1);
+        assert_eq!(
+            test.instance.advance_sequence(&test.lock, floor + 10, 0)?,
+            floor + 10
+        );
+        assert_eq!(
+            test.instance.advance_sequence(&test.lock, floor, 0)?,
+            floor + 10
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn reacquire() -> Result<(), RedsyncError> {
+        let test = setup("reacquire");
+        let _ = test.instance.release(&test.lock);
+
+        test.instance.reacquire(&test.lock)?;
+        test.instance.reacquire(&test.lock)?;
+
+        let other = Lock {
+            value: b"2".to_vec(),
+            ..setup("reacquire").lock
+        };
+        assert!(matches!(
+            test.instance.reacquire(&other),
+            Err(RedsyncError::ResourceLocked)
+        ));
+
+        test.instance.release(&test.lock)
+    }
+
+    #[test]
+    fn incr_stats() -> Result<(), RedsyncError> {
+        let test = setup("incr_stats");
+        test.instance
+            .incr_stats(b"incr_stats", &[("acquisitions", 2)])?;
+
+        let fields = test.instance.read_stats(b"incr_stats")?;
+        assert!(fields.contains(&(String::from("acquisitions"), 2)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn acquire_replicated() -> Result<(), RedsyncError> {
+        // The test server has no replicas
+        let test = setup("acquire_replicated");
+        let durability = Durability::new(1, Duration::from_millis(10));
+        let attempt = test.instance.acquire_replicated(&test.lock, &durability);
+        assert!(matches!(
+            attempt,
+            Err(RedsyncError::ReplicationUnconfirmed {
+                acknowledged: 0,
+                required: 1
+            })
+        ));
+
+        test.instance.release(&test.lock)?;
+        let durability = Durability::new(0, Duration::from_millis(10));
+        test.instance.acquire_replicated(&test.lock, &durability)?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn reserve() -> Result<(), RedsyncError> {
+        let mut test = setup("reserve");
+        let reservation = Reservation::new(
+            b"reserve".to_vec(),
+            b"2".to_vec(),
+            SystemTime::now() + Duration::from_millis(200),
+            Duration::from_millis(500),
+        );
+        test.instance.reserve(&reservation)?;
+        let attempt = test.instance.reserve(&reservation);
+        assert!(matches!(attempt, Err(RedsyncError::ResourceReserved)));
+
+        // A lease of 500ms would overlap the reservation, but one of 100ms ends before it
+        let attempt = test.instance.acquire(&test.lock);
+        assert!(matches!(attempt, Err(RedsyncError::ResourceReserved)));
+        test.lock.ttl = Duration::from_millis(100);
+        test.instance.acquire(&test.lock)?;
+        test.lock.ttl = Duration::from_millis(500);
+        let attempt = test.instance.extend(&test.lock);
+        assert!(matches!(attempt, Err(RedsyncError::ResourceReserved)));
+        test.instance.release(&test.lock)?;
+
+        // The reservation's own value bypasses it
+        test.lock.value = b"2".to_vec();
+        test.instance.acquire(&test.lock)?;
+        test.instance.release(&test.lock)?;
+        test.instance.cancel_reservation(&reservation)?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn audit() -> Result<(), RedsyncError> {
+        let test = setup("audit");
+        let fields = [
+            ("identity", String::from("worker-1")),
+            ("resource", String::from("audit")),
+            ("action", String::from("acquire")),
+            ("outcome", String::from("ok")),
+            ("time", String::from("1700000000000")),
+        ];
+        test.instance.append_audit(&fields, 100)?;
+
+        let events = test.instance.read_audit(1)?;
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].resource, "audit");
+        assert!(events[0].is_ok());
+
+        Ok(())
+    }
+
+    #[test]
+    fn acquire_many() {
+        let test = setup("acquire_many");
+        let locks: Vec<_> = ["acquire_many_1", "acquire_many_2"]
+            .iter()
+            .map(|resource| Lock {
+                history: test.lock.history,
+                report: test.lock.report,
+                ..Lock::new(
+                    *resource,
+                    test.lock.value.clone(),
+                    test.lock.ttl,
+                    test.lock.expiry,
+                    0,
+                )
+            })
+            .collect();
+
+        let results = test.instance.acquire_many(&locks);
+        assert!(results.iter().all(Result::is_ok));
+
+        let results = test.instance.acquire_many(&locks);
+        assert!(results
+            .iter()
+            .all(|r| matches!(r, Err(RedsyncError::ResourceLocked))));
+
+        let results = test.instance.extend_many(&locks);
+        assert!(results.iter().all(Result::is_ok));
+
+        let results = test.instance.release_many(&locks);
+        assert!(results.iter().all(Result::is_ok));
+    }
+
+    #[test]
+    fn release_publish() -> Result<(), RedsyncError> {
+        let test = setup("release_publish");
+        let mut subscription = test.instance.subscribe_releases("release_publish")?;
+        test.instance.acquire(&test.lock)?;
+        test.instance.release(&test.lock)?;
+
+        let released = subscription.wait(Duration::from_secs(1))?;
+        assert_eq!(released, Some(test.lock.value));
+
+        Ok(())
+    }
+
     #[test]
     fn release_invalid_lock() -> Result<(), RedsyncError> {
         let mut test = setup("unlock_invalid_lock");
         test.instance.acquire(&test.lock)?;
 
-        test.lock.value = String::from("2");
+        test.lock.value = b"2".to_vec();
         let attempt = test.instance.release(&test.lock);
         assert!(matches!(attempt, Err(RedsyncError::InvalidLease)));
 
@@ -224,4 +2154,148 @@ mod tests {
 
         Ok(())
     }
+
+    // The scripts used by Go redsync v4 and node-redlock v5, verbatim, to check that locks taken
+    // by either side are honoured by the other.
+    const GO_TOUCH_SCRIPT: &str = "\
+if redis.call(\"GET\", KEYS[1]) == ARGV[1] then
+    return redis.call(\"PEXPIRE\", KEYS[1], ARGV[2])
+else
+    return 0
+end";
+
+    const GO_DELETE_SCRIPT: &str = "\
+local val = redis.call(\"GET\", KEYS[1])
+if val == ARGV[1] then
+    return redis.call(\"DEL\", KEYS[1])
+elseif val == false then
+    return -1
+else
+    return 0
+end";
+
+    const NODE_ACQUIRE_SCRIPT: &str = "\
+for i, key in ipairs(KEYS) do
+  if redis.call(\"exists\", key) == 1 then
+    return 0
+  end
+end
+for i, key in ipairs(KEYS) do
+  redis.call(\"set\", key, ARGV[1], \"PX\", ARGV[2])
+end
+return #KEYS";
+
+    const NODE_EXTEND_SCRIPT: &str = "\
+for i, key in ipairs(KEYS) do
+  if redis.call(\"get\", key) ~= ARGV[1] then
+    return 0
+  end
+end
+for i, key in ipairs(KEYS) do
+  redis.call(\"set\", key, ARGV[1], \"PX\", ARGV[2])
+end
+return #KEYS";
+
+    const NODE_RELEASE_SCRIPT: &str = "\
+local count = 0
+for i, key in ipairs(KEYS) do
+  if redis.call(\"get\", key) == ARGV[1] then
+    if redis.call(\"del\", key) == 1 then
+      count = count + 1
+    end
+  end
+end
+return count";
+
+    #[test]
+    fn interop_go_redsync() -> Result<(), RedsyncError> {
+        let mut test = setup("interop_go_redsync");
+        let mut conn = test.instance.connection(None)?;
+        let touch = redis::Script::new(GO_TOUCH_SCRIPT);
+        let delete = redis::Script::new(GO_DELETE_SCRIPT);
+
+        // Locked by this crate, contended by Go redsync
+        test.instance.acquire(&test.lock)?;
+        let acquired: Option<String> = redis::cmd("SET")
+            .arg(&test.lock.resource)
+            .arg("Ax8n0pV5LhQyJ2c4mZr9aw==")
+            .arg("NX")
+            .arg("PX")
+            .arg(500)
+            .query(&mut conn)?;
+        assert_eq!(acquired, None);
+        let touched: i64 = touch
+            .key(&test.lock.resource)
+            .arg(&test.lock.value)
+            .arg(500)
+            .invoke(&mut conn)?;
+        assert_eq!(touched, 1);
+        let deleted: i64 = delete
+            .key(&test.lock.resource)
+            .arg(&test.lock.value)
+            .invoke(&mut conn)?;
+        assert_eq!(deleted, 1);
+
+        // Locked by Go redsync, contended by this crate
+        test.lock.value = b"Ax8n0pV5LhQyJ2c4mZr9aw==".to_vec();
+        let _: () = redis::cmd("SET")
+            .arg(&test.lock.resource)
+            .arg(&test.lock.value)
+            .arg("NX")
+            .arg("PX")
+            .arg(500)
+            .query(&mut conn)?;
+        let contender = setup("interop_go_redsync").lock;
+        let attempt = test.instance.acquire(&contender);
+        assert!(matches!(attempt, Err(RedsyncError::ResourceLocked)));
+        test.instance.extend(&test.lock)?;
+        test.instance.release(&test.lock)?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn interop_node_redlock() -> Result<(), RedsyncError> {
+        let mut test = setup("interop_node_redlock");
+        let mut conn = test.instance.connection(None)?;
+        let acquire = redis::Script::new(NODE_ACQUIRE_SCRIPT);
+        let extend = redis::Script::new(NODE_EXTEND_SCRIPT);
+        let release = redis::Script::new(NODE_RELEASE_SCRIPT);
+
+        // Locked by this crate, contended by node-redlock
+        test.instance.acquire(&test.lock)?;
+        let acquired: i64 = acquire
+            .key(&test.lock.resource)
+            .arg("8c3b2f4e9a1d07e6b5c4a3f2e1d0c9b8")
+            .arg(500)
+            .invoke(&mut conn)?;
+        assert_eq!(acquired, 0);
+        let extended: i64 = extend
+            .key(&test.lock.resource)
+            .arg(&test.lock.value)
+            .arg(500)
+            .invoke(&mut conn)?;
+        assert_eq!(extended, 1);
+        let released: i64 = release
+            .key(&test.lock.resource)
+            .arg(&test.lock.value)
+            .invoke(&mut conn)?;
+        assert_eq!(released, 1);
+
+        // Locked by node-redlock, contended by this crate
+        test.lock.value = b"8c3b2f4e9a1d07e6b5c4a3f2e1d0c9b8".to_vec();
+        let acquired: i64 = acquire
+            .key(&test.lock.resource)
+            .arg(&test.lock.value)
+            .arg(500)
+            .invoke(&mut conn)?;
+        assert_eq!(acquired, 1);
+        let contender = setup("interop_node_redlock").lock;
+        let attempt = test.instance.acquire(&contender);
+        assert!(matches!(attempt, Err(RedsyncError::ResourceLocked)));
+        test.instance.extend(&test.lock)?;
+        test.instance.release(&test.lock)?;
+
+        Ok(())
+    }
 }
@@ -0,0 +1,111 @@
+use crate::attempts::AttemptOutcome;
+use crate::errors::{ErrorKind, RedsyncError};
+use crate::instance::Instance;
+use crate::redsync::Redsync;
+use crate::ttl::LeaseTtl;
+
+use std::ops::Range;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// `JobOutcome` is the result of `SingletonJob::run`.
+#[derive(Debug, PartialEq)]
+pub enum JobOutcome<T> {
+    /// The job ran in this process, returning `T`.
+    Ran(T),
+    /// The job was already claimed for the current window by another runner.
+    Skipped,
+}
+
+type MissedHook = Box<dyn Fn(&str, Range<u64>) + Send + Sync>;
+
+/// `SingletonJob` runs a job at most once per period across every process sharing the cluster,
+/// such as a cron job deployed to a fleet. Time is divided into windows of the period, aligned to
+/// the Unix epoch, and the first runner to lock `job:<name>:<window>` runs the job for that
+/// window. The lock is left to expire at the end of the window rather than unlocked, so runners
+/// that arrive after the job finishes still skip it.
+pub struct SingletonJob<'a, I: Instance> {
+    dlm: &'a Redsync<I>,
+    name: String,
+    period: Duration,
+    last_window: Option<u64>,
+    on_missed: Option<MissedHook>,
+}
+
+impl<'a, I: Instance> SingletonJob<'a, I> {
+    /// `new` creates a SingletonJob named `name` that runs at most once every `period`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `period` is outside the bounds of a `LeaseTtl`, as each window is claimed with a
+    /// lock lasting up to the period.
+    pub fn new(dlm: &'a Redsync<I>, name: &str, period: Duration) -> Self {
+        assert!(
+            (LeaseTtl::MIN..=LeaseTtl::MAX).contains(&period),
+            "a SingletonJob period must be within the bounds of a LeaseTtl"
+        );
+
+        Self {
+            dlm,
+            name: String::from(name),
+            period,
+            last_window: None,
+            on_missed: None,
+        }
+    }
+
+    /// `on_missed` calls `hook` with the job name and the range of windows skipped between two
+    /// calls to `run` in this process, e.g. because the scheduler driving it stalled. Windows run
+    /// by other processes in the meantime are included, as they cannot be told apart locally.
+    pub fn on_missed<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&str, Range<u64>) + Send + Sync + 'static,
+    {
+        self.on_missed = Some(Box::new(hook));
+        self
+    }
+
+    /// `window` returns the index of the window containing `time`.
+    pub fn window(&self, time: SystemTime) -> u64 {
+        let elapsed = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+        (elapsed.as_millis() / self.period.as_millis()) as u64
+    }
+
+    /// `resource` returns the resource locked to claim `window`.
+    pub fn resource(&self, window: u64) -> String {
+        format!("job:{}:{}", self.name, window)
+    }
+
+    /// `run` runs `f` if no other runner has claimed the current window, making a single lock
+    /// attempt. Fails if the window could not be claimed for any reason other than contention.
+    pub fn run<T>(&mut self, f: impl FnOnce() -> T) -> Result<JobOutcome<T>, RedsyncError> {
+        let now = SystemTime::now();
+        let window = self.window(now);
+        self.check_missed(window);
+
+        // The lock spans the rest of the window, so that the window is claimed exactly once
+        let end = UNIX_EPOCH + Duration::from_millis((window + 1) * self.period.as_millis() as u64);
+        let ttl = end
+            .duration_since(now)
+            .unwrap_or_default()
+            .max(LeaseTtl::MIN);
+
+        let mut attempts = self.dlm.lock_attempts(self.resource(window), ttl)?;
+        match attempts.next_attempt() {
+            AttemptOutcome::Acquired(_) => Ok(JobOutcome::Ran(f())),
+            AttemptOutcome::Failed(errors) if errors.kind() == ErrorKind::Contention => {
+                Ok(JobOutcome::Skipped)
+            }
+            AttemptOutcome::Failed(errors) => Err(RedsyncError::LockRetriesExceeded(errors)),
+        }
+    }
+
+    fn check_missed(&mut self, window: u64) {
+        if let (Some(last), Some(hook)) = (self.last_window, &self.on_missed) {
+            if window > last + 1 {
+                hook(&self.name, last + 1..window);
+            }
+        }
+
+        self.last_window = Some(window);
+    }
+}
@@ -0,0 +1,266 @@
+use crate::errors::RedsyncError;
+
+use std::fmt::Write as _;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// `JournalEntry` is a lock recorded in a `LockJournal`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct JournalEntry {
+    pub resource: Vec<u8>,
+    pub value: Vec<u8>,
+    pub ttl: Duration,
+    pub expires_at: SystemTime,
+}
+
+/// `LockJournal` durably records the locks held by a process, so that a restarted process can
+/// recover them with `Redsync::recover` instead of leaving peers blocked until they expire.
+pub trait LockJournal: Send + Sync {
+    /// `record` adds `entry`, replacing any entry for the same resource and value.
+    fn record(&self, entry: &JournalEntry) -> Result<(), RedsyncError>;
+    /// `remove` drops the entry for `resource` and `value`, once its lock has been released.
+    fn remove(&self, resource: &[u8], value: &[u8]) -> Result<(), RedsyncError>;
+    /// `entries` returns every entry that has been recorded and not removed.
+    fn entries(&self) -> Result<Vec<JournalEntry>, RedsyncError>;
+}
+
+/// `FileJournal` is a `LockJournal` that appends to a local file. The file grows with every lock
+/// and extend until `compact` rewrites it with only the current entries.
+pub struct FileJournal {
+    path: PathBuf,
+    file: Mutex<Option<File>>,
+}
+
+impl FileJournal {
+    /// `new` creates a journal at `path`. The file is created on the first append.
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        Self {
+            path: path.into(),
+            file: Mutex::new(None),
+        }
+    }
+
+    /// `compact` rewrites the journal with only the entries that have not been removed.
+    pub fn compact(&self) -> Result<(), RedsyncError> {
+        let mut file = self.file.lock().unwrap();
+        let entries = self.read()?;
+
+        let tmp = self.path.with_extension("tmp");
+        let mut lines = String::new();
+        for entry in &entries {
+            lines.push_str(&encode_record(entry));
+        }
+        fs::write(&tmp, lines)
+            .and_then(|_| fs::rename(&tmp, &self.path))
+            .map_err(journal_error)?;
+
+        // Reopen on the next append, as the previous handle refers to the replaced file
+        *file = None;
+        Ok(())
+    }
+
+    fn append(&self, line: &str) -> Result<(), RedsyncError> {
+        let mut file = self.file.lock().unwrap();
+        if file.is_none() {
+            let mut opened = OpenOptions::new()
+                .create(true)
+                .read(true)
+                .append(true)
+                .open(&self.path)
+                .map_err(journal_error)?;
+            // Terminate a line torn by a crash mid-append, so that it does not swallow this one
+            if !ends_with_newline(&mut opened).map_err(journal_error)? {
+                opened.write_all(b"\n").map_err(journal_error)?;
+            }
+            *file = Some(opened);
+        }
+
+        let file = file.as_mut().unwrap();
+        file.write_all(line.as_bytes())
+            .and_then(|_| file.sync_data())
+            .map_err(journal_error)
+    }
+
+    fn read(&self) -> Result<Vec<JournalEntry>, RedsyncError> {
+        let file = match File::open(&self.path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(journal_error(e)),
+        };
+
+        let mut entries: Vec<JournalEntry> = Vec::new();
+        for line in BufReader::new(file).lines() {
+            let line = line.map_err(journal_error)?;
+            let fields: Vec<&str> = line.split(' ').collect();
+            match fields.as_slice() {
+                ["+", resource, value, ttl, expires_at] => {
+                    let entry = match decode_record(resource, value, ttl, expires_at) {
+                        Some(entry) => entry,
+                        // A partially written last line is left by a crash mid-append
+                        None => continue,
+                    };
+                    entries.retain(|e| !(e.resource == entry.resource && e.value == entry.value));
+                    entries.push(entry);
+                }
+                ["-", resource, value] => {
+                    if let (Some(resource), Some(value)) = (decode_hex(resource), decode_hex(value))
+                    {
+                        entries.retain(|e| !(e.resource == resource && e.value == value));
+                    }
+                }
+                _ => continue,
+            }
+        }
+
+        Ok(entries)
+    }
+}
+
+impl LockJournal for FileJournal {
+    fn record(&self, entry: &JournalEntry) -> Result<(), RedsyncError> {
+        self.append(&encode_record(entry))
+    }
+
+    fn remove(&self, resource: &[u8], value: &[u8]) -> Result<(), RedsyncError> {
+        self.append(&format!(
+            "- {} {}\n",
+            encode_hex(resource),
+            encode_hex(value)
+        ))
+    }
+
+    fn entries(&self) -> Result<Vec<JournalEntry>, RedsyncError> {
+        let _file = self.file.lock().unwrap();
+        self.read()
+    }
+}
+
+/// `ends_with_newline` returns true if `file` is empty or its last byte is a newline.
+fn ends_with_newline(file: &mut File) -> io::Result<bool> {
+    if file.metadata()?.len() == 0 {
+        return Ok(true);
+    }
+
+    let mut last = [0; 1];
+    file.seek(SeekFrom::End(-1))?;
+    file.read_exact(&mut last)?;
+    Ok(last[0] == b'\n')
+}
+
+fn journal_error(e: io::Error) -> RedsyncError {
+    RedsyncError::JournalError(e.to_string())
+}
+
+/// `encode_record` formats an entry as a journal line. The resource and value are hex-encoded,
+/// since they may contain the separators used by the journal.
+fn encode_record(entry: &JournalEntry) -> String {
+    let expires_at = entry
+        .expires_at
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+
+    format!(
+        "+ {} {} {} {}\n",
+        encode_hex(&entry.resource),
+        encode_hex(&entry.value),
+        entry.ttl.as_millis(),
+        expires_at.as_millis()
+    )
+}
+
+fn decode_record(resource: &str, value: &str, ttl: &str, expires_at: &str) -> Option<JournalEntry> {
+    Some(JournalEntry {
+        resource: decode_hex(resource)?,
+        value: decode_hex(value)?,
+        ttl: Duration::from_millis(ttl.parse().ok()?),
+        expires_at: UNIX_EPOCH + Duration::from_millis(expires_at.parse().ok()?),
+    })
+}
+
+fn encode_hex(bytes: &[u8]) -> String {
+    let mut hex = String::with_capacity(bytes.len() * 2);
+    for byte in bytes {
+        let _ = write!(hex, "{:02x}", byte);
+    }
+    hex
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(resource: &str, value: &str) -> JournalEntry {
+        JournalEntry {
+            resource: resource.as_bytes().to_vec(),
+            value: value.as_bytes().to_vec(),
+            ttl: Duration::from_secs(10),
+            expires_at: UNIX_EPOCH + Duration::from_millis(1_700_000_000_000),
+        }
+    }
+
+    #[test]
+    fn file_journal() -> Result<(), RedsyncError> {
+        let path = std::env::temp_dir().join(format!("redsync-journal-{}", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let journal = FileJournal::new(&path);
+
+        journal.record(&entry("orders 42", "a"))?;
+        journal.record(&entry("orders:7", "b"))?;
+        journal.record(&entry("orders:7", "b"))?;
+        journal.remove(b"orders 42", b"a")?;
+        assert_eq!(journal.entries()?, [entry("orders:7", "b")]);
+
+        journal.compact()?;
+        journal.record(&entry("orders:8", "c"))?;
+        assert_eq!(
+            journal.entries()?,
+            [entry("orders:7", "b"), entry("orders:8", "c")]
+        );
+
+        fs::remove_file(&path).map_err(journal_error)
+    }
+
+    #[test]
+    fn file_journal_truncated() -> Result<(), RedsyncError> {
+        let path = std::env::temp_dir().join(format!("redsync-truncated-{}", std::process::id()));
+        fs::write(&path, "+ 6f72646572 61 10000 1700000000000\n+ 6f72").map_err(journal_error)?;
+
+        let journal = FileJournal::new(&path);
+        assert_eq!(journal.entries()?.len(), 1);
+
+        journal.record(&entry("orders:7", "b"))?;
+        assert_eq!(journal.entries()?.len(), 2);
+
+        fs::remove_file(&path).map_err(journal_error)
+    }
+
+    #[test]
+    fn file_journal_binary() -> Result<(), RedsyncError> {
+        let path = std::env::temp_dir().join(format!("redsync-binary-{}", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let journal = FileJournal::new(&path);
+
+        let entry = JournalEntry {
+            resource: vec![0x00, b' ', 0xff, b'\n'],
+            value: vec![0xfe, b'-'],
+            ..entry("", "")
+        };
+        journal.record(&entry)?;
+        assert_eq!(journal.entries()?, vec![entry.clone()]);
+
+        journal.remove(&entry.resource, &entry.value)?;
+        assert!(journal.entries()?.is_empty());
+
+        fs::remove_file(&path).map_err(journal_error)
+    }
+}
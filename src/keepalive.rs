@@ -0,0 +1,233 @@
+use crate::errors::RedsyncError;
+use crate::instance::Instance;
+use crate::redsync::{Lock, Redsync};
+
+use std::panic;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use rand::{thread_rng, Rng};
+
+/// `KeepaliveSchedule` computes when a held lock should be renewed, as a jittered fraction of its TTL.
+/// It drives both `Watchdog`, which renews a lock from a background thread, and `MaintainedLock`,
+/// which renews it from the caller's own loop.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct KeepaliveSchedule {
+    fraction: f64,
+    jitter: f64,
+}
+
+impl Default for KeepaliveSchedule {
+    fn default() -> Self {
+        Self {
+            fraction: 2.0 / 3.0,
+            jitter: 0.05,
+        }
+    }
+}
+
+impl KeepaliveSchedule {
+    /// `new` creates a schedule that renews locks once `fraction` of their TTL has elapsed,
+    /// clamped to between 0 and 1.
+    pub fn new(fraction: f64) -> Self {
+        Self {
+            fraction: fraction.clamp(0.0, 1.0),
+            ..Default::default()
+        }
+    }
+
+    /// `jitter` sets how far, as a fraction of the TTL, renewals are randomly moved either way,
+    /// so that locks taken together are not all renewed at once. Defaults to 0.05.
+    pub fn jitter(mut self, jitter: f64) -> Self {
+        self.jitter = jitter.clamp(0.0, 1.0);
+        self
+    }
+
+    /// `next_deadline` returns when `lock` should next be renewed.
+    pub fn next_deadline(&self, lock: &Lock) -> Instant {
+        let jitter = if self.jitter > 0.0 {
+            thread_rng().gen_range(-1.0..1.0) * self.jitter
+        } else {
+            0.0
+        };
+
+        lock.next_extend_deadline(self.fraction + jitter)
+    }
+
+    /// `next_delay` returns how long to wait from now before renewing `lock`, or zero if it is
+    /// already due.
+    pub fn next_delay(&self, lock: &Lock) -> Duration {
+        self.next_deadline(lock)
+            .saturating_duration_since(Instant::now())
+    }
+}
+
+/// `MaintainedLock` renews a held lock on a `KeepaliveSchedule` from the caller's own loop,
+/// for environments where spawning a background thread per lock is unsuitable.
+pub struct MaintainedLock<'a, I: Instance> {
+    redsync: &'a Redsync<I>,
+    lock: Lock,
+    schedule: KeepaliveSchedule,
+    deadline: Instant,
+}
+
+impl<'a, I: Instance> MaintainedLock<'a, I> {
+    pub(crate) fn new(redsync: &'a Redsync<I>, lock: Lock, schedule: KeepaliveSchedule) -> Self {
+        let deadline = schedule.next_deadline(&lock);
+        Self {
+            redsync,
+            lock,
+            schedule,
+            deadline,
+        }
+    }
+
+    /// `maintain` extends the lock if its renewal deadline has passed by `now`, returning true if
+    /// it was extended. Fails with `RedsyncError::InvalidLease` once the lock has expired.
+    ///
+    /// After each extension, the expiry of the lock is re-synced with `Redsync::remaining_ttl`,
+    /// so that the next renewal is scheduled from what a quorum of instances report rather than
+    /// the local estimate alone. If the instances cannot report it, the local estimate is kept.
+    pub fn maintain(&mut self, now: Instant) -> Result<bool, RedsyncError> {
+        if now >= self.lock.expiry {
+            return Err(RedsyncError::InvalidLease);
+        }
+
+        if now < self.deadline {
+            return Ok(false);
+        }
+
+        self.lock = self.redsync.extend(&self.lock, self.lock.ttl)?;
+        if let Ok(remaining) = self.redsync.remaining_ttl(&self.lock) {
+            if remaining.is_zero() {
+                return Err(RedsyncError::InvalidLease);
+            }
+            self.lock.expiry = self.lock.expiry.min(Instant::now() + remaining);
+        }
+        self.deadline = self.schedule.next_deadline(&self.lock);
+        Ok(true)
+    }
+
+    /// `next_deadline` returns when `maintain` will next extend the lock.
+    pub fn next_deadline(&self) -> Instant {
+        self.deadline
+    }
+
+    pub fn lock(&self) -> &Lock {
+        &self.lock
+    }
+
+    pub fn into_lock(self) -> Lock {
+        self.lock
+    }
+}
+
+/// `WATCHDOG_THREAD_NAME` is the name of the threads spawned by `Redsync::watchdog`.
+pub(crate) const WATCHDOG_THREAD_NAME: &str = "redsync-watchdog";
+
+/// `Watchdog` renews a held lock on a `KeepaliveSchedule` from a background thread, until it is
+/// stopped or a renewal fails. Dropping it stops the renewals without waiting for the thread, and
+/// leaves the lock to expire at the end of its TTL.
+pub struct Watchdog {
+    stop: mpsc::Sender<()>,
+    handle: JoinHandle<Result<Lock, RedsyncError>>,
+}
+
+impl Watchdog {
+    pub(crate) fn spawn<I: Instance + Send + Sync + 'static>(
+        redsync: Arc<Redsync<I>>,
+        lock: Lock,
+        schedule: KeepaliveSchedule,
+    ) -> Result<Self, RedsyncError> {
+        let (stop, stopped) = mpsc::channel();
+        let handle = thread::Builder::new()
+            .name(String::from(WATCHDOG_THREAD_NAME))
+            .spawn(move || {
+                let mut lock = MaintainedLock::new(&redsync, lock, schedule);
+                loop {
+                    let delay = lock
+                        .next_deadline()
+                        .saturating_duration_since(Instant::now());
+                    match stopped.recv_timeout(delay) {
+                        Err(RecvTimeoutError::Timeout) => lock.maintain(Instant::now())?,
+                        _ => return Ok(lock.into_lock()),
+                    };
+                }
+            })
+            .map_err(|e| RedsyncError::ThreadSpawnFailed(e.to_string()))?;
+
+        Ok(Self { stop, handle })
+    }
+
+    /// `is_running` returns false once a renewal has failed, after which the lock should be
+    /// considered lost.
+    pub fn is_running(&self) -> bool {
+        !self.handle.is_finished()
+    }
+
+    /// `stop` stops the renewals and returns the lock as last renewed, so that it can be
+    /// unlocked, or the error of the renewal that failed.
+    pub fn stop(self) -> Result<Lock, RedsyncError> {
+        drop(self.stop);
+        match self.handle.join() {
+            Ok(result) => result,
+            Err(e) => panic::resume_unwind(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lock(ttl: Duration) -> Lock {
+        Lock::new("test", "1", ttl, Instant::now() + ttl, 0)
+    }
+
+    #[test]
+    fn default() {
+        let schedule = KeepaliveSchedule::default();
+
+        assert_eq!(schedule.fraction, 2.0 / 3.0);
+        assert_eq!(schedule.jitter, 0.05);
+    }
+
+    #[test]
+    fn next_deadline() {
+        let lock = lock(Duration::from_secs(3));
+        let schedule = KeepaliveSchedule::new(0.5).jitter(0.0);
+
+        let deadline = schedule.next_deadline(&lock);
+        assert_eq!(deadline, lock.expiry - Duration::from_millis(1500));
+    }
+
+    #[test]
+    fn next_deadline_jitter() {
+        let lock = lock(Duration::from_secs(1));
+        let schedule = KeepaliveSchedule::new(0.5).jitter(0.1);
+
+        let deadline = schedule.next_deadline(&lock);
+        let (min, max) = (
+            lock.expiry - Duration::from_millis(600),
+            lock.expiry - Duration::from_millis(400),
+        );
+        assert!(
+            min <= deadline && deadline <= max,
+            "expected deadline to be between {:?} and {:?}, but got {:?}",
+            min,
+            max,
+            deadline,
+        );
+    }
+
+    #[test]
+    fn next_delay() {
+        let lock = lock(Duration::from_secs(1));
+        let schedule = KeepaliveSchedule::new(0.5).jitter(0.0);
+
+        let delay = schedule.next_delay(&lock);
+        assert!(delay <= Duration::from_millis(500));
+    }
+}
@@ -0,0 +1,114 @@
+use crate::redsync::Lock;
+
+use std::backtrace::Backtrace;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// `LeakedLock` describes a lock whose validity expired without being unlocked or extended.
+#[derive(Debug)]
+pub struct LeakedLock {
+    pub resource: Vec<u8>,
+    pub value: Vec<u8>,
+    pub expired_for: Duration,
+    pub backtrace: Backtrace,
+}
+
+impl fmt::Display for LeakedLock {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "lock on {} ({}) expired {:?} ago without being unlocked, acquired at:\n{}",
+            String::from_utf8_lossy(&self.resource),
+            String::from_utf8_lossy(&self.value),
+            self.expired_for,
+            self.backtrace
+        )
+    }
+}
+
+struct Tracked {
+    expiry: Instant,
+    backtrace: Backtrace,
+}
+
+/// `LockKey` is the resource and value of a tracked lock.
+type LockKey = (Vec<u8>, Vec<u8>);
+
+static TRACKED: Mutex<BTreeMap<LockKey, Tracked>> = Mutex::new(BTreeMap::new());
+
+pub(crate) fn track(lock: &Lock) {
+    TRACKED.lock().unwrap().insert(
+        (lock.resource.clone(), lock.value.clone()),
+        Tracked {
+            expiry: lock.expiry,
+            backtrace: Backtrace::capture(),
+        },
+    );
+}
+
+pub(crate) fn untrack(lock: &Lock) {
+    TRACKED
+        .lock()
+        .unwrap()
+        .remove(&(lock.resource.clone(), lock.value.clone()));
+}
+
+/// `leaked_locks` returns every lock acquired in this process whose validity expired without
+/// an unlock or extend, and stops tracking them. Backtraces are only captured when enabled
+/// through `RUST_BACKTRACE` or `RUST_LIB_BACKTRACE`.
+pub fn leaked_locks() -> Vec<LeakedLock> {
+    let now = Instant::now();
+    let mut tracked = TRACKED.lock().unwrap();
+
+    let expired: Vec<_> = tracked
+        .iter()
+        .filter(|(_, t)| t.expiry <= now)
+        .map(|(key, _)| key.clone())
+        .collect();
+
+    expired
+        .into_iter()
+        .filter_map(|key| {
+            let t = tracked.remove(&key)?;
+            Some(LeakedLock {
+                resource: key.0,
+                value: key.1,
+                expired_for: now - t.expiry,
+                backtrace: t.backtrace,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lock(resource: &str, expiry: Instant) -> Lock {
+        Lock::new(resource, "1", Duration::from_secs(1), expiry, 0)
+    }
+
+    #[test]
+    fn leaked_locks() {
+        // Kept in a single test since the registry is shared across the process
+        let expired = lock("leak_expired", Instant::now() - Duration::from_secs(1));
+        let valid = lock("leak_valid", Instant::now() + Duration::from_secs(60));
+        let unlocked = lock("leak_unlocked", Instant::now() - Duration::from_secs(1));
+        track(&expired);
+        track(&valid);
+        track(&unlocked);
+        untrack(&unlocked);
+
+        let leaked: Vec<_> = super::leaked_locks()
+            .into_iter()
+            .map(|l| l.resource)
+            .filter(|r| r.starts_with(b"leak_"))
+            .collect();
+        assert_eq!(leaked, vec![b"leak_expired"]);
+        assert!(!super::leaked_locks()
+            .iter()
+            .any(|l| l.resource == b"leak_expired"));
+    }
+}
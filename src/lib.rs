@@ -22,19 +22,123 @@
 //!   ]);
 //!
 //!   let lock = dlm.lock("resource", Duration::from_secs(1))?;
-//!   dlm.unlock(&lock)?;
+//!   dlm.unlock_owned(lock)?;
 //!
 //!   Ok(())
 //! }
 //! ```
 //!
 //! For more examples, see [examples](https://github.com/jace-ys/redsync/tree/master/examples).
+pub use crate::attempts::{AttemptOutcome, LockAttempts};
+pub use crate::audit::{AuditAction, AuditEvent, AUDIT_STREAM};
 pub use crate::builder::RedsyncBuilder;
-pub use crate::errors::{MultiError, RedsyncError};
-pub use crate::instance::{Instance, RedisInstance};
-pub use crate::redsync::{Lock, Redsync};
+pub use crate::chaos::ChaosInstance;
+#[cfg(feature = "redis")]
+pub use crate::connection::RedisInstanceBuilder;
+pub use crate::contention::{ResourceContention, Starvation};
+pub use crate::context::LockContext;
+pub use crate::credentials::{Credentials, CredentialsProvider};
+pub use crate::discovery::{ClusterDiscovery, DiscoveredCluster, DnsDiscovery};
+pub use crate::durability::Durability;
+pub use crate::errors::{ErrorKind, MultiError, RedsyncError, DEFAULT_ERROR_LIMIT};
+pub use crate::guard::{take_panic_releases, LockGuard, PanicRelease};
+pub use crate::history::RenewalHistory;
+#[cfg(feature = "redis")]
+pub use crate::instance::RedisInstance;
+pub use crate::instance::{release_channel, Instance};
+pub use crate::job::{JobOutcome, SingletonJob};
+pub use crate::journal::{FileJournal, JournalEntry, LockJournal};
+pub use crate::keepalive::{KeepaliveSchedule, MaintainedLock, Watchdog};
+#[cfg(feature = "leak-detector")]
+pub use crate::leak::{leaked_locks, LeakedLock};
+#[cfg(feature = "resp")]
+pub use crate::metrics::InstanceMetrics;
+pub use crate::options::LockOptions;
+pub use crate::partial::PartialExtend;
+pub use crate::pipeline::PendingCall;
+pub use crate::policy::{LockPolicy, PolicyViolation};
+pub use crate::preflight::{NodeDiagnostics, PreflightReport, PreflightWarning, MAX_CLOCK_SKEW};
+pub use crate::preview::LockPreview;
+pub use crate::quota::{Quota, DEFAULT_HELD_WINDOW};
+pub use crate::record::{LockRecord, RecordVersion};
+pub use crate::redsync::{quorum_for, Lock, Redsync};
+pub use crate::replay::{
+    Fixture, Interaction, Operation, RecordedInstance, Recording, ReplayInstance,
+};
+pub use crate::report::{AcquireReport, UnlockReport};
+pub use crate::reservation::Reservation;
+pub use crate::resource::ResourceRules;
+#[cfg(feature = "resp")]
+pub use crate::resp::{RespError, RespInstance, Value as RespValue};
+pub use crate::rollback::RollbackPolicy;
+pub use crate::scope::{LockScope, ScopedLock};
+pub use crate::sharded::ShardedRedsync;
+pub use crate::shortcircuit::ShortCircuit;
+pub use crate::singleflight::SingleFlight;
+pub use crate::snapshot::ConfigSnapshot;
+pub use crate::stats::{ResourceStats, STATS_WINDOW};
+pub use crate::stripe::LockStripe;
+#[cfg(feature = "redis")]
+pub use crate::subscription::ReleaseSubscription;
+pub use crate::ttl::{min_safe_ttl, IntoLeaseTtl, LeaseTtl, DEFAULT_DRIFT_FACTOR, DRIFT_MARGIN};
+pub use crate::warnings::ConfigWarning;
 
+mod attempts;
+mod audit;
+mod backoff;
 mod builder;
+mod chaos;
+#[cfg(feature = "redis")]
+mod connection;
+mod contention;
+mod context;
+mod credentials;
+mod discovery;
+mod drift;
+mod durability;
 mod errors;
+mod guard;
+mod history;
 mod instance;
+mod job;
+mod journal;
+mod keepalive;
+#[cfg(feature = "leak-detector")]
+mod leak;
+#[cfg(feature = "lock-order")]
+mod lockorder;
+#[cfg(feature = "resp")]
+mod metrics;
+mod options;
+#[cfg(feature = "opentelemetry")]
+mod otel;
+mod partial;
+mod pipeline;
+mod policy;
+mod pool;
+mod preflight;
+mod preview;
+mod quota;
+mod record;
 mod redsync;
+mod replay;
+mod report;
+mod reservation;
+mod resource;
+#[cfg(feature = "resp")]
+mod resp;
+mod rollback;
+mod scope;
+mod sequence;
+mod sharded;
+mod shortcircuit;
+mod singleflight;
+mod snapshot;
+mod stats;
+mod stripe;
+#[cfg(feature = "redis")]
+mod subscription;
+#[cfg(feature = "test-harness")]
+pub mod test_harness;
+mod ttl;
+mod warnings;
@@ -0,0 +1,78 @@
+use std::cell::RefCell;
+use std::collections::BTreeSet;
+use std::sync::Mutex;
+
+/// `ORDERS` holds every pair of resources observed locked in order, (held, acquired), by any
+/// thread in the process.
+static ORDERS: Mutex<BTreeSet<(Vec<u8>, Vec<u8>)>> = Mutex::new(BTreeSet::new());
+
+thread_local! {
+    static HELD: RefCell<Vec<Vec<u8>>> = const { RefCell::new(Vec::new()) };
+}
+
+/// `acquired` records that the current thread locked `resource` while holding its other locks,
+/// panicking if another thread was previously observed locking them in the opposite order.
+pub(crate) fn acquired(resource: &[u8]) {
+    HELD.with(|held| {
+        let mut held = held.borrow_mut();
+        let mut orders = ORDERS.lock().unwrap();
+
+        for prior in held.iter().filter(|prior| *prior != resource) {
+            if orders.contains(&(resource.to_vec(), prior.clone())) {
+                drop(orders);
+                panic!(
+                    "lock order inversion: {} acquired while holding {}, but was previously held while acquiring it",
+                    String::from_utf8_lossy(resource),
+                    String::from_utf8_lossy(prior)
+                );
+            }
+        }
+
+        for prior in held.iter().filter(|prior| *prior != resource) {
+            orders.insert((prior.clone(), resource.to_vec()));
+        }
+        held.push(resource.to_vec());
+    });
+}
+
+/// `released` records that the current thread no longer holds `resource`.
+pub(crate) fn released(resource: &[u8]) {
+    HELD.with(|held| {
+        let mut held = held.borrow_mut();
+        if let Some(i) = held.iter().rposition(|r| r == resource) {
+            held.remove(i);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::panic;
+
+    #[test]
+    fn inversion() {
+        acquired(b"order_a");
+        acquired(b"order_b");
+        released(b"order_b");
+        released(b"order_a");
+
+        acquired(b"order_b");
+        let result = panic::catch_unwind(|| acquired(b"order_a"));
+        assert!(result.is_err());
+        released(b"order_b");
+    }
+
+    #[test]
+    fn consistent_order() {
+        acquired(b"consistent_a");
+        acquired(b"consistent_b");
+        released(b"consistent_b");
+        released(b"consistent_a");
+
+        acquired(b"consistent_a");
+        acquired(b"consistent_b");
+        released(b"consistent_b");
+        released(b"consistent_a");
+    }
+}
@@ -0,0 +1,137 @@
+use std::io::{self, Read};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// `InstanceMetrics` is a snapshot of the traffic a `RespInstance` has exchanged with its
+/// server, returned by `RespInstance::metrics`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct InstanceMetrics {
+    /// `commands` is the number of commands sent, including AUTH, SELECT and script loads.
+    pub commands: u64,
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+    /// `script_hits` is the number of scripts run by their cached SHA1 digest.
+    pub script_hits: u64,
+    /// `script_misses` is the number of scripts that had to be loaded or sent in full.
+    pub script_misses: u64,
+}
+
+impl InstanceMetrics {
+    /// `script_hit_rate` returns the fraction of scripts run from the script cache, or None if
+    /// no scripts have been run.
+    pub fn script_hit_rate(&self) -> Option<f64> {
+        let scripts = self.script_hits + self.script_misses;
+        if scripts == 0 {
+            return None;
+        }
+
+        Some(self.script_hits as f64 / scripts as f64)
+    }
+}
+
+/// `Counters` accumulates the `InstanceMetrics` of an instance, and records them as
+/// `redsync.instance.*` metrics when OpenTelemetry is enabled.
+pub(crate) struct Counters {
+    #[cfg_attr(not(feature = "opentelemetry"), allow(dead_code))]
+    instance: String,
+    commands: AtomicU64,
+    bytes_sent: AtomicU64,
+    bytes_received: AtomicU64,
+    script_hits: AtomicU64,
+    script_misses: AtomicU64,
+}
+
+impl Counters {
+    pub(crate) fn new(instance: String) -> Self {
+        Self {
+            instance,
+            commands: AtomicU64::new(0),
+            bytes_sent: AtomicU64::new(0),
+            bytes_received: AtomicU64::new(0),
+            script_hits: AtomicU64::new(0),
+            script_misses: AtomicU64::new(0),
+        }
+    }
+
+    /// `sent` records a command of `bytes` bytes.
+    pub(crate) fn sent(&self, bytes: usize) {
+        self.commands.fetch_add(1, Ordering::Relaxed);
+        self.bytes_sent.fetch_add(bytes as u64, Ordering::Relaxed);
+        #[cfg(feature = "opentelemetry")]
+        crate::otel::command_sent(&self.instance, bytes as u64);
+    }
+
+    pub(crate) fn received(&self, bytes: usize) {
+        self.bytes_received
+            .fetch_add(bytes as u64, Ordering::Relaxed);
+        #[cfg(feature = "opentelemetry")]
+        crate::otel::bytes_received(&self.instance, bytes as u64);
+    }
+
+    /// `script` records a script run from the script cache if `hit`, or loaded otherwise.
+    pub(crate) fn script(&self, hit: bool) {
+        let counter = if hit {
+            &self.script_hits
+        } else {
+            &self.script_misses
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        #[cfg(feature = "opentelemetry")]
+        crate::otel::script_run(&self.instance, hit);
+    }
+
+    pub(crate) fn snapshot(&self) -> InstanceMetrics {
+        InstanceMetrics {
+            commands: self.commands.load(Ordering::Relaxed),
+            bytes_sent: self.bytes_sent.load(Ordering::Relaxed),
+            bytes_received: self.bytes_received.load(Ordering::Relaxed),
+            script_hits: self.script_hits.load(Ordering::Relaxed),
+            script_misses: self.script_misses.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// `Counted` wraps a reader, recording the bytes read through it as received.
+pub(crate) struct Counted<R> {
+    pub(crate) inner: R,
+    pub(crate) counters: Arc<Counters>,
+}
+
+impl<R: Read> Read for Counted<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.counters.received(n);
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counters() -> io::Result<()> {
+        let counters = Arc::new(Counters::new(String::from("127.0.0.1:6379")));
+        counters.sent(14);
+        counters.script(false);
+        counters.script(true);
+        counters.script(true);
+        counters.script(true);
+
+        let mut reader = Counted {
+            inner: &b"+PONG\r\n"[..],
+            counters: counters.clone(),
+        };
+        let mut reply = Vec::new();
+        reader.read_to_end(&mut reply)?;
+
+        let metrics = counters.snapshot();
+        assert_eq!(metrics.commands, 1);
+        assert_eq!(metrics.bytes_sent, 14);
+        assert_eq!(metrics.bytes_received, 7);
+        assert_eq!(metrics.script_hit_rate(), Some(0.75));
+        assert_eq!(InstanceMetrics::default().script_hit_rate(), None);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,25 @@
+use crate::durability::Durability;
+
+use std::time::Duration;
+
+/// `LockOptions` overrides the configuration of a manager for a single acquisition. See
+/// `Redsync::lock_with`.
+#[derive(Clone, Debug, Default)]
+pub struct LockOptions {
+    pub(crate) durability: Option<Durability>,
+    /// `version` is the version `Redsync::lock_if_version` expects the resource to be at.
+    pub(crate) version: Option<u64>,
+}
+
+impl LockOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// `durability` requires `replicas` replicas of each instance to acknowledge the lock within
+    /// `timeout`, with the `WAIT` command, before the instance's vote is counted.
+    pub fn durability(mut self, replicas: u32, timeout: Duration) -> Self {
+        self.durability = Some(Durability::new(replicas, timeout));
+        self
+    }
+}
@@ -0,0 +1,157 @@
+use crate::errors::RedsyncError;
+use crate::redsync::{Call, Lock};
+
+use std::time::Instant;
+
+use opentelemetry::global;
+use opentelemetry::trace::{get_active_span, mark_span_as_active, Status, Tracer};
+use opentelemetry::{ContextGuard, KeyValue};
+
+/// `INSTRUMENTATION` is the name of the tracer and meter used by Redsync.
+const INSTRUMENTATION: &str = "redsync";
+
+/// `CallTelemetry` records a lock, extend or reacquire call as a `redsync.<operation>` span,
+/// active for the duration of the call so that each attempt is recorded on it as an event, and
+/// as `redsync.calls` and `redsync.call.duration` metrics once it completes.
+pub(crate) struct CallTelemetry {
+    operation: &'static str,
+    started: Instant,
+    _guard: ContextGuard,
+}
+
+impl CallTelemetry {
+    pub(crate) fn start(call: &Call, resource: &[u8], quorum: u32, cluster_size: usize) -> Self {
+        let tracer = global::tracer(INSTRUMENTATION);
+        let span = tracer
+            .span_builder(span_name(call))
+            .with_attributes(vec![
+                KeyValue::new(
+                    "redsync.resource",
+                    String::from_utf8_lossy(resource).into_owned(),
+                ),
+                KeyValue::new("redsync.quorum", quorum as i64),
+                KeyValue::new("redsync.cluster_size", cluster_size as i64),
+            ])
+            .start(&tracer);
+
+        Self {
+            operation: operation(call),
+            started: Instant::now(),
+            _guard: mark_span_as_active(span),
+        }
+    }
+
+    pub(crate) fn finish(self, result: &Result<Lock, RedsyncError>) {
+        let outcome = match result {
+            Ok(_) => "success",
+            Err(_) => "failure",
+        };
+
+        get_active_span(|span| {
+            span.set_attribute(KeyValue::new("redsync.outcome", outcome));
+            match result {
+                Ok(lock) => span.set_attribute(KeyValue::new(
+                    "redsync.validity_ms",
+                    lock.validity_at_acquisition().as_millis() as i64,
+                )),
+                Err(e) => span.set_status(Status::error(e.to_string())),
+            }
+            span.end();
+        });
+
+        let attributes = [
+            KeyValue::new("redsync.operation", self.operation),
+            KeyValue::new("redsync.outcome", outcome),
+        ];
+        let meter = global::meter(INSTRUMENTATION);
+        meter
+            .u64_counter("redsync.calls")
+            .with_description("Lock, extend and reacquire calls made by Redsync managers")
+            .init()
+            .add(1, &attributes);
+        meter
+            .f64_histogram("redsync.call.duration")
+            .with_description("Duration of calls, including retries")
+            .init()
+            .record(self.started.elapsed().as_secs_f64(), &attributes);
+    }
+}
+
+/// `attempted` records an attempt of the active call that was granted by `votes` instances.
+pub(crate) fn attempted(call: &Call, votes: u32, acquired: bool) {
+    get_active_span(|span| {
+        span.add_event(
+            "redsync.attempt",
+            vec![
+                KeyValue::new("redsync.votes", votes as i64),
+                KeyValue::new("redsync.acquired", acquired),
+            ],
+        );
+        span.set_attribute(KeyValue::new("redsync.votes", votes as i64));
+    });
+
+    global::meter(INSTRUMENTATION)
+        .u64_counter("redsync.attempts")
+        .with_description("Attempts made by Redsync calls")
+        .init()
+        .add(1, &[KeyValue::new("redsync.operation", operation(call))]);
+}
+
+/// `command_sent` records a command of `bytes` bytes sent to `instance`.
+pub(crate) fn command_sent(instance: &str, bytes: u64) {
+    let attributes = [KeyValue::new("redsync.instance", instance.to_string())];
+    let meter = global::meter(INSTRUMENTATION);
+    meter
+        .u64_counter("redsync.instance.commands")
+        .with_description("Commands sent to Redis instances")
+        .init()
+        .add(1, &attributes);
+    meter
+        .u64_counter("redsync.instance.bytes_sent")
+        .with_description("Bytes sent to Redis instances")
+        .init()
+        .add(bytes, &attributes);
+}
+
+/// `bytes_received` records `bytes` bytes received from `instance`.
+pub(crate) fn bytes_received(instance: &str, bytes: u64) {
+    global::meter(INSTRUMENTATION)
+        .u64_counter("redsync.instance.bytes_received")
+        .with_description("Bytes received from Redis instances")
+        .init()
+        .add(
+            bytes,
+            &[KeyValue::new("redsync.instance", instance.to_string())],
+        );
+}
+
+/// `script_run` records a script run on `instance`, from its script cache if `hit`.
+pub(crate) fn script_run(instance: &str, hit: bool) {
+    global::meter(INSTRUMENTATION)
+        .u64_counter("redsync.instance.scripts")
+        .with_description("Scripts run on Redis instances")
+        .init()
+        .add(
+            1,
+            &[
+                KeyValue::new("redsync.instance", instance.to_string()),
+                KeyValue::new("redsync.script_cache_hit", hit),
+            ],
+        );
+}
+
+fn operation(call: &Call) -> &'static str {
+    match call {
+        Call::Lock { .. } => "lock",
+        Call::Extend => "extend",
+        Call::Reacquire => "reacquire",
+    }
+}
+
+fn span_name(call: &Call) -> &'static str {
+    match call {
+        Call::Lock { .. } => "redsync.lock",
+        Call::Extend => "redsync.extend",
+        Call::Reacquire => "redsync.reacquire",
+    }
+}
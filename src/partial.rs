@@ -0,0 +1,10 @@
+/// `PartialExtend` configures how an extend that reaches quorum, but that some instances rejected
+/// with `InvalidLease`, is handled.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PartialExtend {
+    /// The rejecting instances are reported in `Lock::lost_instances`.
+    Report,
+    /// The lock is re-acquired on the rejecting instances, and those that could not be repaired
+    /// are reported in `Lock::lost_instances`.
+    Repair,
+}
@@ -0,0 +1,74 @@
+use crate::errors::RedsyncError;
+use crate::redsync::Lock;
+
+type Reply<'a> = Box<dyn FnOnce(&Lock) -> Result<(), RedsyncError> + 'a>;
+
+/// `PendingCall` is a call that has been sent to an instance but whose reply has not been read
+/// yet, returned by `Instance::send_acquire`, `send_extend` and `send_release`. Sending a call to
+/// every instance before waiting on any lets the round trips to the instances overlap on a single
+/// thread.
+pub struct PendingCall<'a> {
+    state: State<'a>,
+}
+
+enum State<'a> {
+    Done(Result<(), RedsyncError>),
+    Waiting(Reply<'a>),
+}
+
+impl<'a> PendingCall<'a> {
+    /// `done` wraps the result of a call that completed when it was sent, such as one made by an
+    /// instance that cannot pipeline calls, or one that failed to send.
+    pub fn done(result: Result<(), RedsyncError>) -> Self {
+        Self {
+            state: State::Done(result),
+        }
+    }
+
+    /// `waiting` creates a PendingCall whose result is read by `reply` once it is waited on.
+    /// Dropping the PendingCall without waiting on it drops `reply` unread, so implementations
+    /// should not reuse a connection with a reply still outstanding.
+    pub fn waiting(reply: impl FnOnce(&Lock) -> Result<(), RedsyncError> + 'a) -> Self {
+        Self {
+            state: State::Waiting(Box::new(reply)),
+        }
+    }
+
+    /// `wait` blocks until the reply to the call for `lock` is read, and returns its result.
+    pub fn wait(self, lock: &Lock) -> Result<(), RedsyncError> {
+        match self.state {
+            State::Done(result) => result,
+            State::Waiting(reply) => reply(lock),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use std::time::Duration;
+
+    #[test]
+    fn wait() {
+        let lock = Lock::pending(
+            String::from("test"),
+            String::from("1"),
+            Duration::from_secs(1),
+            0,
+        );
+
+        let pending = PendingCall::done(Err(RedsyncError::ResourceLocked));
+        assert_eq!(pending.wait(&lock), Err(RedsyncError::ResourceLocked));
+
+        let read = Cell::new(false);
+        let pending = PendingCall::waiting(|lock| {
+            read.set(true);
+            assert_eq!(lock.resource, b"test");
+            Ok(())
+        });
+        assert!(!read.get());
+        assert_eq!(pending.wait(&lock), Ok(()));
+        assert!(read.get());
+    }
+}
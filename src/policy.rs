@@ -0,0 +1,37 @@
+use std::fmt;
+use std::time::Duration;
+
+/// `PolicyViolation` describes why a `LockPolicy` rejected an acquisition.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PolicyViolation {
+    pub reason: String,
+}
+
+impl PolicyViolation {
+    pub fn new(reason: impl Into<String>) -> Self {
+        Self {
+            reason: reason.into(),
+        }
+    }
+}
+
+impl fmt::Display for PolicyViolation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.reason)
+    }
+}
+
+/// `LockPolicy` is consulted before each lock acquisition, and may veto it, e.g. to enforce a
+/// maximum TTL or reject resources matching banned patterns.
+pub trait LockPolicy: Send + Sync {
+    fn check(&self, resource: &str, ttl: Duration) -> Result<(), PolicyViolation>;
+}
+
+impl<F> LockPolicy for F
+where
+    F: Fn(&str, Duration) -> Result<(), PolicyViolation> + Send + Sync,
+{
+    fn check(&self, resource: &str, ttl: Duration) -> Result<(), PolicyViolation> {
+        self(resource, ttl)
+    }
+}
@@ -0,0 +1,98 @@
+use crate::errors::RedsyncError;
+
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+
+type Job = Box<dyn FnOnce() + Send>;
+
+/// `WorkerPool` runs jobs on a fixed number of worker threads, started on first use, so that the
+/// number of threads does not grow with the number of concurrent callers. Jobs that cannot be
+/// picked up straight away wait in a queue.
+pub(crate) struct WorkerPool {
+    name: &'static str,
+    size: usize,
+    jobs: Mutex<Option<mpsc::Sender<Job>>>,
+}
+
+impl WorkerPool {
+    /// `new` creates a pool of `size` workers, whose threads are named `name`.
+    pub(crate) fn new(name: &'static str, size: usize) -> Self {
+        Self {
+            name,
+            size: size.max(1),
+            jobs: Mutex::new(None),
+        }
+    }
+
+    /// `execute` queues `job` to run on a worker, failing with `ThreadSpawnFailed` if the workers
+    /// could not be started.
+    pub(crate) fn execute(&self, job: impl FnOnce() + Send + 'static) -> Result<(), RedsyncError> {
+        let mut jobs = self.jobs.lock().unwrap();
+        if jobs.is_none() {
+            *jobs = Some(self.start()?);
+        }
+
+        match jobs.as_ref().map(|jobs| jobs.send(Box::new(job))) {
+            Some(Ok(())) => Ok(()),
+            _ => Err(RedsyncError::ThreadSpawnFailed(String::from(
+                "worker pool has stopped",
+            ))),
+        }
+    }
+
+    /// `start` spawns the workers, which run until the pool is dropped. Workers survive panicking
+    /// jobs, so that the pool keeps its size.
+    fn start(&self) -> Result<mpsc::Sender<Job>, RedsyncError> {
+        let (sender, receiver) = mpsc::channel::<Job>();
+        let receiver = Arc::new(Mutex::new(receiver));
+
+        for _ in 0..self.size {
+            let receiver = Arc::clone(&receiver);
+            thread::Builder::new()
+                .name(String::from(self.name))
+                .spawn(move || loop {
+                    let job = match receiver.lock().unwrap().recv() {
+                        Ok(job) => job,
+                        Err(_) => break,
+                    };
+                    let _ = panic::catch_unwind(AssertUnwindSafe(job));
+                })
+                .map_err(|e| RedsyncError::ThreadSpawnFailed(e.to_string()))?;
+        }
+
+        Ok(sender)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn execute() -> Result<(), RedsyncError> {
+        let pool = WorkerPool::new("redsync-test", 2);
+        let (sender, receiver) = mpsc::channel();
+
+        pool.execute(|| panic!("job panicked"))?;
+        for i in 0..4 {
+            let sender = sender.clone();
+            pool.execute(move || {
+                let name = thread::current().name().map(String::from);
+                sender.send((i, name)).unwrap();
+            })?;
+        }
+
+        let mut done: Vec<_> = (0..4)
+            .map(|_| receiver.recv_timeout(Duration::from_secs(1)).unwrap())
+            .collect();
+        done.sort();
+        for (i, (j, name)) in done.into_iter().enumerate() {
+            assert_eq!(i, j);
+            assert_eq!(name.as_deref(), Some("redsync-test"));
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,147 @@
+use crate::errors::RedsyncError;
+
+use std::time::Duration;
+#[cfg(any(feature = "redis", feature = "resp", test))]
+use std::time::SystemTime;
+
+/// Clock skew above which a node is reported in preflight warnings.
+pub const MAX_CLOCK_SKEW: Duration = Duration::from_millis(100);
+
+/// `NodeDiagnostics` holds the configuration of a single instance relevant to Redlock safety.
+///
+/// Fields are `None` when the instance does not expose them, e.g. when CONFIG is disabled.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct NodeDiagnostics {
+    pub maxmemory_policy: Option<String>,
+    pub appendonly: Option<bool>,
+    pub save: Option<String>,
+    pub clock_skew: Option<Duration>,
+}
+
+/// `PreflightWarning` describes a misconfiguration that undermines Redlock guarantees.
+#[derive(Debug, PartialEq)]
+pub enum PreflightWarning {
+    /// The instance could not be inspected.
+    Unreachable(usize, String),
+    /// The instance may evict keys, including lock keys, under memory pressure.
+    Eviction(usize, String),
+    /// The instance has neither AOF nor RDB persistence enabled, so locks are lost on restart.
+    NoPersistence(usize),
+    /// The instance's clock differs from the local clock by more than `MAX_CLOCK_SKEW`.
+    ClockSkew(usize, Duration),
+}
+
+/// `PreflightReport` holds the diagnostics gathered from every instance in a Redsync cluster.
+#[derive(Debug, PartialEq)]
+pub struct PreflightReport {
+    pub nodes: Vec<Result<NodeDiagnostics, RedsyncError>>,
+}
+
+impl PreflightReport {
+    pub fn warnings(&self) -> Vec<PreflightWarning> {
+        let mut warnings = Vec::new();
+
+        for (i, node) in self.nodes.iter().enumerate() {
+            let node = match node {
+                Ok(node) => node,
+                Err(e) => {
+                    warnings.push(PreflightWarning::Unreachable(i, e.to_string()));
+                    continue;
+                }
+            };
+
+            if let Some(policy) = &node.maxmemory_policy {
+                if policy != "noeviction" {
+                    warnings.push(PreflightWarning::Eviction(i, policy.clone()));
+                }
+            }
+
+            if node.appendonly == Some(false) && node.save.as_deref() == Some("") {
+                warnings.push(PreflightWarning::NoPersistence(i));
+            }
+
+            if let Some(skew) = node.clock_skew {
+                if skew > MAX_CLOCK_SKEW {
+                    warnings.push(PreflightWarning::ClockSkew(i, skew));
+                }
+            }
+        }
+
+        warnings
+    }
+
+    pub fn is_ok(&self) -> bool {
+        self.warnings().is_empty()
+    }
+}
+
+/// `clock_skew` returns the difference between a server timestamp and the local clock at the
+/// midpoint of the request that fetched it.
+#[cfg(any(feature = "redis", feature = "resp", test))]
+pub(crate) fn clock_skew(before: SystemTime, after: SystemTime, server: SystemTime) -> Duration {
+    let rtt = after.duration_since(before).unwrap_or_default();
+    let local = before + rtt / 2;
+
+    match server.duration_since(local) {
+        Ok(skew) => skew,
+        Err(e) => e.duration(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn warnings() {
+        let report = PreflightReport {
+            nodes: vec![
+                Ok(NodeDiagnostics {
+                    maxmemory_policy: Some(String::from("noeviction")),
+                    appendonly: Some(true),
+                    save: Some(String::from("")),
+                    clock_skew: Some(Duration::from_millis(5)),
+                }),
+                Ok(NodeDiagnostics {
+                    maxmemory_policy: Some(String::from("allkeys-lru")),
+                    appendonly: Some(false),
+                    save: Some(String::from("")),
+                    clock_skew: Some(Duration::from_secs(2)),
+                }),
+                Err(RedsyncError::InjectedFault),
+            ],
+        };
+
+        assert_eq!(
+            report.warnings(),
+            [
+                PreflightWarning::Eviction(1, String::from("allkeys-lru")),
+                PreflightWarning::NoPersistence(1),
+                PreflightWarning::ClockSkew(1, Duration::from_secs(2)),
+                PreflightWarning::Unreachable(2, String::from("injected fault")),
+            ]
+        );
+        assert!(!report.is_ok());
+    }
+
+    #[test]
+    fn warnings_unknown() {
+        let report = PreflightReport {
+            nodes: vec![Ok(NodeDiagnostics::default())],
+        };
+
+        assert!(report.is_ok());
+    }
+
+    #[test]
+    fn clock_skew_midpoint() {
+        let before = SystemTime::UNIX_EPOCH + Duration::from_secs(100);
+        let after = before + Duration::from_millis(20);
+
+        let skew = clock_skew(before, after, before + Duration::from_millis(60));
+        assert_eq!(skew, Duration::from_millis(50));
+
+        let skew = clock_skew(before, after, before);
+        assert_eq!(skew, Duration::from_millis(10));
+    }
+}
@@ -0,0 +1,68 @@
+use std::time::Duration;
+
+/// `LockPreview` predicts the outcome of locking a resource, as observed by `Redsync::can_lock`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LockPreview {
+    /// `available` is true if a quorum of instances do not currently hold a lock on the resource.
+    pub available: bool,
+    /// `free_in` is how long until a quorum of instances are expected to release the resource,
+    /// or zero if it is available now.
+    pub free_in: Duration,
+}
+
+impl LockPreview {
+    /// `from_ttls` builds a preview from the remaining TTL of the lock held on each responding
+    /// instance, or `None` if the instance holds no lock. Returns `None` if too few instances
+    /// responded to predict a quorum.
+    pub(crate) fn from_ttls(ttls: Vec<Option<Duration>>, quorum: usize) -> Option<Self> {
+        let free = ttls.iter().filter(|ttl| ttl.is_none()).count();
+        if free >= quorum {
+            return Some(Self {
+                available: true,
+                free_in: Duration::ZERO,
+            });
+        }
+
+        let mut held: Vec<Duration> = ttls.into_iter().flatten().collect();
+        held.sort_unstable();
+
+        held.get(quorum - free - 1).map(|&free_in| Self {
+            available: false,
+            free_in,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_ttls() {
+        let ms = |n| Some(Duration::from_millis(n));
+
+        let preview = LockPreview::from_ttls(vec![None, None, ms(100)], 2);
+        assert_eq!(
+            preview,
+            Some(LockPreview {
+                available: true,
+                free_in: Duration::ZERO
+            })
+        );
+
+        let preview = LockPreview::from_ttls(vec![None, ms(300), ms(100)], 2);
+        assert_eq!(
+            preview,
+            Some(LockPreview {
+                available: false,
+                free_in: Duration::from_millis(100)
+            })
+        );
+
+        let preview = LockPreview::from_ttls(vec![ms(300), ms(200), ms(100)], 2);
+        assert_eq!(preview.map(|p| p.free_in), Some(Duration::from_millis(200)));
+
+        let preview = LockPreview::from_ttls(vec![None], 2);
+        assert_eq!(preview, None);
+    }
+}
@@ -0,0 +1,57 @@
+use std::time::Duration;
+
+/// `DEFAULT_HELD_WINDOW` is how long the counter of a `max_held` quota lives before it is reset.
+pub const DEFAULT_HELD_WINDOW: Duration = Duration::from_secs(3600);
+
+/// `Quota` limits the lock usage of every resource sharing a prefix, e.g. a tenant's resources.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Quota {
+    pub(crate) max_held: Option<i64>,
+    pub(crate) max_rate: Option<(i64, Duration)>,
+    pub(crate) held_window: Duration,
+}
+
+impl Default for Quota {
+    fn default() -> Self {
+        Self {
+            max_held: None,
+            max_rate: None,
+            held_window: DEFAULT_HELD_WINDOW,
+        }
+    }
+}
+
+impl Quota {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// `max_held` limits how many locks may be held concurrently.
+    pub fn max_held(mut self, max_held: u32) -> Self {
+        self.max_held = Some(max_held as i64);
+        self
+    }
+
+    /// `max_rate` limits how many acquisitions may be attempted per `window`.
+    pub fn max_rate(mut self, max_acquisitions: u32, window: Duration) -> Self {
+        self.max_rate = Some((max_acquisitions as i64, window));
+        self
+    }
+
+    /// `held_window` sets how long the counter of held locks lives before it is reset, forgetting
+    /// the slots of locks that were never unlocked, e.g. by a crashed process. Locks held across
+    /// a reset are no longer counted, so the quota may briefly admit more than `max_held` locks.
+    /// Defaults to `DEFAULT_HELD_WINDOW`.
+    pub fn held_window(mut self, window: Duration) -> Self {
+        self.held_window = window;
+        self
+    }
+}
+
+pub(crate) fn held_key(prefix: &str) -> String {
+    format!("redsync:quota:{}:held", prefix)
+}
+
+pub(crate) fn rate_key(prefix: &str) -> String {
+    format!("redsync:quota:{}:rate", prefix)
+}
@@ -0,0 +1,74 @@
+/// `RecordVersion` is the format of the value stored at a lock key.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RecordVersion {
+    /// The raw lock token, as written by all crate versions up to 1.0.
+    V1,
+    /// A tagged record, `redsync/2;<token>`, that later fields can be appended to as
+    /// `;`-separated sections.
+    V2,
+}
+
+const V2_TAG: &[u8] = b"redsync/2";
+
+/// `LockRecord` is the decoded value stored at a lock key.
+///
+/// Decoding accepts every known format, so services on different crate versions can read each
+/// other's locks while a new format is being rolled out.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LockRecord {
+    pub version: RecordVersion,
+    pub token: Vec<u8>,
+}
+
+impl LockRecord {
+    pub fn new(version: RecordVersion, token: impl AsRef<[u8]>) -> Self {
+        Self {
+            version,
+            token: token.as_ref().to_vec(),
+        }
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        match self.version {
+            RecordVersion::V1 => self.token.clone(),
+            RecordVersion::V2 => [V2_TAG, b";", &self.token].concat(),
+        }
+    }
+
+    pub fn decode(value: &[u8]) -> Self {
+        let mut sections = value.split(|&b| b == b';');
+        match (sections.next(), sections.next()) {
+            (Some(V2_TAG), Some(token)) => Self::new(RecordVersion::V2, token),
+            _ => Self::new(RecordVersion::V1, value),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode() {
+        let record = LockRecord::new(RecordVersion::V1, "abc");
+        assert_eq!(record.encode(), b"abc");
+
+        let record = LockRecord::new(RecordVersion::V2, "abc");
+        assert_eq!(record.encode(), b"redsync/2;abc");
+    }
+
+    #[test]
+    fn decode() {
+        let record = LockRecord::decode(b"abc");
+        assert_eq!(record, LockRecord::new(RecordVersion::V1, "abc"));
+
+        let record = LockRecord::decode(b"redsync/2;abc");
+        assert_eq!(record, LockRecord::new(RecordVersion::V2, "abc"));
+    }
+
+    #[test]
+    fn decode_future_sections() {
+        let record = LockRecord::decode(b"redsync/2;abc;fence=42");
+        assert_eq!(record, LockRecord::new(RecordVersion::V2, "abc"));
+    }
+}
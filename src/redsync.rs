@@ -1,35 +1,218 @@
+use crate::attempts::LockAttempts;
+use crate::audit::{AuditAction, AuditEvent, AuditLog};
+use crate::backoff::AdaptiveBackoff;
 use crate::builder::RedsyncBuilder;
-use crate::errors::{MultiError, RedsyncError};
+use crate::contention::{ResourceContention, Starvation, StarvationHook, Waiters};
+use crate::context::LockContext;
+use crate::drift::DriftEstimator;
+use crate::durability::Durability;
+use crate::errors::{ErrorKind, MultiError, RedsyncError};
+use crate::guard::LockGuard;
+use crate::history::RenewalHistory;
 use crate::instance::Instance;
+#[cfg(feature = "redis")]
+use crate::instance::RedisInstance;
+use crate::journal::{JournalEntry, LockJournal};
+use crate::keepalive::{KeepaliveSchedule, MaintainedLock, Watchdog};
+use crate::options::LockOptions;
+use crate::partial::PartialExtend;
+use crate::pipeline::PendingCall;
+use crate::policy::LockPolicy;
+use crate::pool::WorkerPool;
+use crate::preflight::PreflightReport;
+use crate::preview::LockPreview;
+use crate::quota::{held_key, rate_key, Quota};
+use crate::record::{LockRecord, RecordVersion};
+use crate::report::{AcquireReport, UnlockReport};
+use crate::reservation::Reservation;
+use crate::resource::ResourceRules;
+use crate::rollback::{Rollback, RollbackPolicy};
+use crate::scope::LockScope;
+use crate::shortcircuit::ShortCircuit;
+use crate::singleflight::{InFlight, SingleFlight};
+use crate::snapshot::ConfigSnapshot;
+use crate::stats::{ResourceStats, ACQUISITIONS, CONTENTIONS, HOLD_MS, RELEASES};
+use crate::stripe::fnv1a;
+use crate::ttl::{IntoLeaseTtl, DRIFT_MARGIN};
+use crate::warnings::{latency_warnings, static_warnings, ConfigHook, ConfigWarning};
 
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
 use std::ops::{Add, Sub};
-use std::thread;
-use std::time::{Duration, Instant};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant, SystemTime};
 
 use rand::distributions::Alphanumeric;
 use rand::{thread_rng, Rng};
 
 /// `Lock` holds the metadata of an acquired lock.
 pub struct Lock {
-    pub resource: String,
-    pub value: String,
+    /// `resource` is the name of the locked resource. It is binary-safe, so identifiers such as
+    /// hashes or serialized structs can be locked as they are.
+    pub resource: Vec<u8>,
+    /// `value` is the token stored at the lock key, identifying this acquisition.
+    pub value: Vec<u8>,
     pub ttl: Duration,
     pub expiry: Instant,
+    pub history: RenewalHistory,
+    /// `lost_instances` lists the instances that no longer held the lock when it was last
+    /// extended, leaving it with less redundancy than the cluster size.
+    pub lost_instances: Vec<usize>,
+    pub report: AcquireReport,
+    /// `sequence` is the acquisition sequence number of the lock when sequences are enabled
+    /// through `RedsyncBuilder::sequences`. Each acquisition of a resource is numbered higher than
+    /// the previous ones, so it can order the side effects of successive holders.
+    pub sequence: Option<u64>,
+    /// `manager` identifies the manager that issued the lock. Managers reject locks issued by
+    /// others unless adopted through `Redsync::adopt`.
+    pub manager: u64,
+    /// `quota_slot` is set while the lock counts against the `max_held` quotas of its resource's
+    /// prefixes, and cleared once unlocking it has returned the slot.
+    pub(crate) quota_slot: AtomicBool,
+}
+
+impl Lock {
+    /// `new` constructs a lock by hand on `resource`, holding `value` until `expiry`, as issued by
+    /// the manager identified by `manager`. Such locks must be adopted through `Redsync::adopt`
+    /// unless `manager` is the `id` of the manager they are used with.
+    pub fn new(
+        resource: impl Into<Vec<u8>>,
+        value: impl Into<Vec<u8>>,
+        ttl: Duration,
+        expiry: Instant,
+        manager: u64,
+    ) -> Self {
+        let now = Instant::now();
+        Self {
+            resource: resource.into(),
+            value: value.into(),
+            ttl,
+            expiry,
+            history: RenewalHistory::new(now),
+            lost_instances: Vec::new(),
+            sequence: None,
+            manager,
+            quota_slot: AtomicBool::new(false),
+            report: AcquireReport {
+                started: now,
+                ttl,
+                io: Duration::ZERO,
+                drift: Duration::ZERO,
+            },
+        }
+    }
+
+    /// `validity_at_acquisition` returns how much of the TTL remained once the lock was acquired,
+    /// or last extended, after accounting for I/O and clock drift.
+    pub fn validity_at_acquisition(&self) -> Duration {
+        self.report.validity()
+    }
+
+    /// `record` decodes the value stored at the lock key.
+    pub fn record(&self) -> LockRecord {
+        LockRecord::decode(&self.value)
+    }
+
+    /// `next_extend_deadline` returns the instant at which `fraction` of the lock's TTL has elapsed.
+    pub fn next_extend_deadline(&self, fraction: f64) -> Instant {
+        let remaining = self.ttl.mul_f64(1.0 - fraction.clamp(0.0, 1.0));
+        self.expiry.checked_sub(remaining).unwrap_or(self.expiry)
+    }
+
+    /// `pending` creates the lock for a call that has yet to make its first attempt. The attempts
+    /// of the call reuse it, resetting only the fields that depend on when they started.
+    pub(crate) fn pending(
+        resource: impl Into<Vec<u8>>,
+        value: impl Into<Vec<u8>>,
+        ttl: Duration,
+        manager: u64,
+    ) -> Self {
+        Self::new(resource, value, ttl, Instant::now(), manager)
+    }
+}
+
+#[cfg(feature = "redis")]
+impl Redsync<RedisInstance> {
+    /// `from_urls` creates a Redsync manager for a cluster of RedisInstances, one per URL.
+    pub fn from_urls(urls: &[&str]) -> Result<Self, RedsyncError> {
+        Ok(RedsyncBuilder::from_urls(urls)?.build())
+    }
+}
+
+/// `quorum_for` returns the number of instances that must agree for a cluster of
+/// `cluster_size` instances. Being a const fn, it can size arrays or assert a deployment's
+/// quorum at compile time.
+pub const fn quorum_for(cluster_size: usize) -> usize {
+    cluster_size / 2 + 1
 }
 
 /// `Redsync` is a distributed lock manager that implements the Redlock algorithm.
 pub struct Redsync<I: Instance> {
     pub(crate) cluster: Vec<I>,
     pub(crate) quorum: u32,
+    pub(crate) failure_domains: Vec<String>,
+    pub(crate) domain_quorum: usize,
     pub(crate) retry_count: u32,
     pub(crate) retry_delay: Duration,
+    pub(crate) extend_retry_count: u32,
+    pub(crate) extend_retry_delay: Duration,
     pub(crate) retry_jitter: f64,
+    pub(crate) default_ttl: Duration,
     pub(crate) drift_factor: f64,
+    pub(crate) dynamic_drift: bool,
+    pub(crate) record_version: RecordVersion,
+    pub(crate) interop: bool,
+    pub(crate) drift: DriftEstimator,
+    pub(crate) backoff: Option<AdaptiveBackoff>,
+    pub(crate) single_flight: SingleFlight,
+    pub(crate) short_circuit: ShortCircuit,
+    pub(crate) pipelined: bool,
+    pub(crate) partial_extend: PartialExtend,
+    pub(crate) rollback: RollbackPolicy,
+    pub(crate) inflight: InFlight,
+    pub(crate) waiters: Waiters,
+    pub(crate) starvation: Option<(Duration, StarvationHook)>,
+    pub(crate) policy: Option<Box<dyn LockPolicy>>,
+    pub(crate) stats_sample_rate: Option<f64>,
+    pub(crate) audit: Option<AuditLog>,
+    pub(crate) quotas: Vec<(String, Quota)>,
+    pub(crate) resource_rules: Option<ResourceRules>,
+    pub(crate) paused: Arc<AtomicBool>,
+    pub(crate) config_hook: Option<ConfigHook>,
+    pub(crate) latency_checked: AtomicBool,
+    pub(crate) journal: Option<Box<dyn LockJournal>>,
+    pub(crate) sequences: bool,
+    pub(crate) durability: Option<Durability>,
+    pub(crate) error_limit: usize,
+    pub(crate) config_snapshot: bool,
+    pub(crate) sleep: Option<SleepFn>,
+    pub(crate) release_pool: WorkerPool,
+    pub(crate) id: u64,
+}
+
+/// `SleepFn` waits out the delay between the attempts of a call.
+pub(crate) type SleepFn = Box<dyn Fn(Duration) + Send + Sync>;
+
+/// `NEXT_MANAGER_ID` is the identity of the next manager built in the process. Identities start
+/// at 1, so that locks constructed by hand with a manager of 0 are never mistaken for issued ones.
+static NEXT_MANAGER_ID: AtomicU64 = AtomicU64::new(1);
+
+/// `next_manager_id` allocates the identity of a new manager.
+pub(crate) fn next_manager_id() -> u64 {
+    NEXT_MANAGER_ID.fetch_add(1, Ordering::Relaxed)
 }
 
-enum Call {
-    Lock,
+pub(crate) enum Call {
+    /// `Lock` acquires a lock, confirming its replication with `durability` and checking that
+    /// the resource is not past `version`, if given.
+    Lock {
+        durability: Option<Durability>,
+        version: Option<u64>,
+    },
     Extend,
+    Reacquire,
 }
 
 impl<I: Instance> Redsync<I> {
@@ -37,242 +220,3824 @@ impl<I: Instance> Redsync<I> {
         RedsyncBuilder::new(cluster).build()
     }
 
-    pub fn lock(&self, resource: &str, ttl: Duration) -> Result<Lock, RedsyncError> {
-        let value = self.get_unique_lock_id();
-        self.call(Call::Lock, resource, &value, ttl)
+    /// `from_array` creates a Redsync manager for a cluster whose size is fixed at compile time.
+    pub fn from_array<const N: usize>(cluster: [I; N]) -> Self {
+        Self::new(Vec::from(cluster))
+    }
+
+    pub fn lock(
+        &self,
+        resource: impl AsRef<[u8]>,
+        ttl: impl IntoLeaseTtl,
+    ) -> Result<Lock, RedsyncError> {
+        self.lock_with(resource, ttl, &LockOptions::new())
+    }
+
+    /// `lock_with` locks `resource` like `lock`, with `options` overriding the configuration of
+    /// the manager for this acquisition.
+    pub fn lock_with(
+        &self,
+        resource: impl AsRef<[u8]>,
+        ttl: impl IntoLeaseTtl,
+        options: &LockOptions,
+    ) -> Result<Lock, RedsyncError> {
+        self.lock_as(resource.as_ref(), ttl, options, self.get_unique_lock_id())
+    }
+
+    /// `lock_as` locks `resource` with `value` as the value of the lock.
+    fn lock_as(
+        &self,
+        resource: &[u8],
+        ttl: impl IntoLeaseTtl,
+        options: &LockOptions,
+        value: Vec<u8>,
+    ) -> Result<Lock, RedsyncError> {
+        self.check_paused()?;
+        let resource = &*self.check_resource(resource)?;
+        let ttl = ttl.into_lease_ttl()?.as_duration();
+        self.check_policy(resource, ttl)?;
+        let _waiter = self.waiters.enter(resource);
+        let guard = self.inflight.enter(resource, self.single_flight)?;
+        let quota_slot = self.reserve_quotas(resource)?;
+        let result = self
+            .call(
+                Call::Lock {
+                    durability: options.durability.or(self.durability),
+                    version: options.version,
+                },
+                Lock::pending(resource, value, ttl, self.id),
+                None,
+            )
+            .inspect(|lock| {
+                lock.quota_slot.store(quota_slot, Ordering::SeqCst);
+                self.check_latency(lock);
+            })
+            .inspect_err(|_| {
+                if quota_slot {
+                    self.release_held(resource);
+                }
+            });
+        self.audit(AuditAction::Acquire, resource, &result);
+        if let Some(guard) = guard {
+            guard.finish(&result);
+        }
+        result
+    }
+
+    /// `lock_once` makes a single attempt to acquire `lock`, subject to the same checks and
+    /// bookkeeping as `lock`, collecting the reasons it failed into `errors`.
+    pub(crate) fn lock_once(&self, lock: &mut Lock, errors: &mut MultiError) -> bool {
+        match self.try_lock_once(lock, errors) {
+            Ok(acquired) => acquired,
+            Err(e) => {
+                errors.push(e);
+                false
+            }
+        }
+    }
+
+    fn try_lock_once(
+        &self,
+        lock: &mut Lock,
+        errors: &mut MultiError,
+    ) -> Result<bool, RedsyncError> {
+        self.check_paused()?;
+        let resource = lock.resource.clone();
+        let _waiter = self.waiters.enter(&resource);
+        let guard = self.inflight.enter(&resource, self.single_flight)?;
+        let quota_slot = self.reserve_quotas(&resource)?;
+
+        let call = Call::Lock {
+            durability: self.durability,
+            version: None,
+        };
+        let result = match self.attempt(&call, lock, errors) {
+            true => {
+                lock.quota_slot.store(quota_slot, Ordering::SeqCst);
+                self.check_latency(lock);
+                Ok(())
+            }
+            false => {
+                if quota_slot {
+                    self.release_held(&resource);
+                }
+                Err(RedsyncError::LockRetriesExceeded(MultiError::new()))
+            }
+        };
+        self.audit(AuditAction::Acquire, &resource, &result);
+        // A failed attempt is only one of several, so calls waiting on it make their own
+        match guard {
+            Some(guard) if result.is_ok() => guard.finish(&result),
+            _ => {}
+        }
+        Ok(result.is_ok())
+    }
+
+    /// `reserve` reserves `resource` for `ttl` from `at`. Until the reservation ends, locks on
+    /// `resource` whose lease would overlap it, or extensions into it, fail with
+    /// `ResourceReserved`, unless they are taken with `claim`. The reservation fails with
+    /// `ReservationFailed` unless it is placed on a quorum of instances, including when the
+    /// resource is already reserved or locked past `at`.
+    ///
+    /// Reservations are compared against the clocks of the instances, so `at` should leave a
+    /// margin for clock skew. Clients that do not use this crate's scripts, such as those of
+    /// other Redlock implementations, ignore reservations.
+    pub fn reserve(
+        &self,
+        resource: impl AsRef<[u8]>,
+        at: SystemTime,
+        ttl: Duration,
+    ) -> Result<Reservation, RedsyncError> {
+        let resource = self.check_resource(resource.as_ref())?.into_owned();
+        let reservation = Reservation::new(resource, self.get_unique_lock_id(), at, ttl);
+        if reservation.remaining().is_zero() {
+            return Err(RedsyncError::InvalidTtl(ttl));
+        }
+
+        let mut votes = 0;
+        let mut errors = MultiError::with_limit(self.error_limit);
+        for instance in &self.cluster {
+            match instance.reserve(&reservation) {
+                Ok(()) => votes += 1,
+                Err(e) => errors.push(e),
+            }
+        }
+
+        if votes < self.quorum {
+            self.cancel_reservation(&reservation);
+            return Err(RedsyncError::ReservationFailed(errors));
+        }
+
+        Ok(reservation)
+    }
+
+    /// `claim` locks the resource of `reservation` for `ttl`, bypassing the reservation.
+    pub fn claim(
+        &self,
+        reservation: &Reservation,
+        ttl: impl IntoLeaseTtl,
+    ) -> Result<Lock, RedsyncError> {
+        self.lock_as(
+            &reservation.resource,
+            ttl,
+            &LockOptions::new(),
+            reservation.value.clone(),
+        )
+    }
+
+    /// `cancel_reservation` removes `reservation` from every instance, ignoring failures. Locks
+    /// already claimed with it are left held.
+    pub fn cancel_reservation(&self, reservation: &Reservation) {
+        for instance in &self.cluster {
+            let _ = instance.cancel_reservation(reservation);
+        }
+    }
+
+    /// `config_warnings` returns the configurations of the manager that violate Redlock
+    /// assumptions and can be detected without contacting any instance.
+    pub fn config_warnings(&self) -> Vec<ConfigWarning> {
+        static_warnings(self.cluster.len())
+    }
+
+    /// `check_latency` reports latency-dependent configuration warnings for the first lock
+    /// acquired by the manager.
+    fn check_latency(&self, lock: &Lock) {
+        let hook = match &self.config_hook {
+            Some(hook) => hook,
+            None => return,
+        };
+        if self.latency_checked.swap(true, Ordering::SeqCst) {
+            return;
+        }
+
+        latency_warnings(
+            self.cluster.len(),
+            lock.report.io,
+            self.retry_delay,
+            lock.ttl,
+            self.drift_factor,
+        )
+        .iter()
+        .for_each(hook);
+    }
+
+    /// `reacquire_if_owner` locks `resource` with `token` as the lock value, succeeding both if
+    /// the resource is unlocked and if it is still held with `token`, in which case the lease is
+    /// renewed to `ttl`. This lets a restarted job resume a lease it may already hold. Failed
+    /// attempts are not rolled back, so that they never release that lease.
+    pub fn reacquire_if_owner(
+        &self,
+        resource: impl AsRef<[u8]>,
+        token: impl AsRef<[u8]>,
+        ttl: impl IntoLeaseTtl,
+    ) -> Result<Lock, RedsyncError> {
+        self.check_paused()?;
+        let resource = &*self.check_resource(resource.as_ref())?;
+        let ttl = ttl.into_lease_ttl()?.as_duration();
+        self.check_policy(resource, ttl)?;
+        let lock = Lock::pending(resource, token.as_ref(), ttl, self.id);
+        self.call(Call::Reacquire, lock, None)
+    }
+
+    /// `lock_if_version` locks `resource` only if it is still at `version`, the sequence of the
+    /// last lock acquired on it, or 0 if it was never locked. The version is checked by each
+    /// instance as part of the acquisition, so if another lock was acquired since, the resource
+    /// is left unlocked, its version unchanged, and `VersionMismatch` is returned with the
+    /// version it was at. This requires sequences to be enabled through
+    /// `RedsyncBuilder::sequences`.
+    pub fn lock_if_version(
+        &self,
+        resource: impl AsRef<[u8]>,
+        version: u64,
+        ttl: impl IntoLeaseTtl,
+    ) -> Result<Lock, RedsyncError> {
+        if !self.sequences {
+            return Err(RedsyncError::InvalidConfig(String::from(
+                "lock_if_version requires sequences",
+            )));
+        }
+
+        let options = LockOptions {
+            version: Some(version),
+            ..LockOptions::new()
+        };
+        match self.lock_as(resource.as_ref(), ttl, &options, self.get_unique_lock_id()) {
+            Err(RedsyncError::LockRetriesExceeded(errors)) => {
+                let actual = errors
+                    .iter()
+                    .filter_map(|e| match e.inner() {
+                        RedsyncError::VersionMismatch { actual, .. } => Some(*actual),
+                        _ => None,
+                    })
+                    .max();
+                match actual {
+                    Some(actual) => Err(RedsyncError::VersionMismatch {
+                        expected: version,
+                        actual,
+                    }),
+                    None => Err(RedsyncError::LockRetriesExceeded(errors)),
+                }
+            }
+            result => result,
+        }
+    }
+
+    /// `with_lock` locks `resource`, runs `f` in the critical section and unlocks it afterwards.
+    /// Failures to unlock are ignored, since the lock then expires on its own.
+    pub fn with_lock<T>(
+        &self,
+        resource: impl AsRef<[u8]>,
+        ttl: impl IntoLeaseTtl,
+        f: impl FnOnce(&mut LockContext<'_, I>) -> T,
+    ) -> Result<T, RedsyncError> {
+        let lock = self.lock(resource, ttl)?;
+        let mut context = LockContext::new(self, lock);
+
+        let result = f(&mut context);
+        let _ = self.unlock(&context.into_lock());
+
+        Ok(result)
+    }
+
+    /// `lock_scoped` locks `resource` and returns a guard that unlocks it when dropped, even if
+    /// the critical section panics.
+    pub fn lock_scoped(
+        &self,
+        resource: impl AsRef<[u8]>,
+        ttl: impl IntoLeaseTtl,
+    ) -> Result<LockGuard<'_, I>, RedsyncError> {
+        let lock = self.lock(resource, ttl)?;
+        Ok(LockGuard::new(self, lock))
+    }
+
+    /// `scope` runs `f` with a `LockScope`, through which any number of locks can be acquired.
+    /// They are all released, in reverse order, once `f` returns or panics.
+    pub fn scope<T>(&self, f: impl FnOnce(&mut LockScope<'_, I>) -> T) -> T {
+        let mut scope = LockScope::new(self);
+        f(&mut scope)
+    }
+
+    /// `lock_default` locks `resource` with the manager's default TTL.
+    pub fn lock_default(&self, resource: impl AsRef<[u8]>) -> Result<Lock, RedsyncError> {
+        self.lock(resource, self.default_ttl)
+    }
+
+    pub fn extend(&self, lock: &Lock, ttl: impl IntoLeaseTtl) -> Result<Lock, RedsyncError> {
+        self.extend_within(lock, ttl, None)
+    }
+
+    /// `extend_until` extends `lock` like `extend`, but stops retrying once the next attempt
+    /// would start after `deadline`, failing with `ExtendRetriesExceeded`. An attempt already
+    /// underway when `deadline` passes is completed, so a lock is never left partially extended.
+    pub fn extend_until(
+        &self,
+        lock: &Lock,
+        ttl: impl IntoLeaseTtl,
+        deadline: Instant,
+    ) -> Result<Lock, RedsyncError> {
+        self.extend_within(lock, ttl, Some(deadline))
+    }
+
+    fn extend_within(
+        &self,
+        lock: &Lock,
+        ttl: impl IntoLeaseTtl,
+        deadline: Option<Instant>,
+    ) -> Result<Lock, RedsyncError> {
+        self.check_manager(lock)?;
+        let ttl = ttl.into_lease_ttl()?.as_duration();
+        let pending = Lock::pending(lock.resource.clone(), lock.value.clone(), ttl, self.id);
+        let mut extended = self.call(Call::Extend, pending, deadline)?;
+        extended.history = lock.history.extended(extended.history.acquired_at);
+        extended.sequence = lock.sequence;
+        extended.quota_slot = AtomicBool::new(lock.quota_slot.swap(false, Ordering::SeqCst));
+        Ok(extended)
+    }
+
+    /// `extend_many` extends each of `locks` to `ttl` in a single pass, batching the extends sent
+    /// to each instance, and returns a result per lock. Locks that fail to extend are released,
+    /// as with `extend`.
+    pub fn extend_many(
+        &self,
+        locks: &[&Lock],
+        ttl: impl IntoLeaseTtl,
+    ) -> Result<Vec<Result<Lock, RedsyncError>>, RedsyncError> {
+        for lock in locks {
+            self.check_manager(lock)?;
+        }
+        let ttl = ttl.into_lease_ttl()?.as_duration();
+        let drift = self.get_drift(ttl);
+        let start = Instant::now();
+
+        let mut extended: Vec<Lock> = locks
+            .iter()
+            .map(|lock| Lock {
+                history: lock.history.extended(start),
+                sequence: lock.sequence,
+                report: AcquireReport {
+                    started: start,
+                    ttl,
+                    io: Duration::ZERO,
+                    drift,
+                },
+                ..Lock::new(
+                    lock.resource.clone(),
+                    lock.value.clone(),
+                    ttl,
+                    start + ttl - drift,
+                    self.id,
+                )
+            })
+            .collect();
+        let mut votes = vec![0; locks.len()];
+        let mut domains = vec![HashSet::new(); locks.len()];
+        let mut errors: Vec<_> = locks
+            .iter()
+            .map(|_| MultiError::with_limit(self.error_limit))
+            .collect();
+
+        for (i, instance) in self.cluster.iter().enumerate() {
+            for (j, result) in instance.extend_many(&extended).into_iter().enumerate() {
+                match result {
+                    Ok(()) => {
+                        votes[j] += 1;
+                        if let Some(domain) = self.failure_domains.get(i) {
+                            domains[j].insert(domain);
+                        }
+                    }
+                    Err(RedsyncError::InvalidLease) => {
+                        extended[j].lost_instances.push(i);
+                        errors[j].push(RedsyncError::InstanceError(
+                            i,
+                            Box::new(RedsyncError::InvalidLease),
+                        ));
+                    }
+                    Err(e) => errors[j].push(RedsyncError::InstanceError(i, Box::new(e))),
+                }
+            }
+        }
+
+        let now = Instant::now();
+        let mut failed = Vec::new();
+        let mut results = Vec::with_capacity(locks.len());
+        for (j, mut lock) in extended.into_iter().enumerate() {
+            lock.report.io = start.elapsed();
+            if votes[j] >= self.quorum
+                && domains[j].len() >= self.domain_quorum
+                && lock.expiry > now
+            {
+                #[cfg(feature = "leak-detector")]
+                crate::leak::track(&lock);
+                if let Some(journal) = &self.journal {
+                    let _ = journal.record(&journal_entry(&lock));
+                }
+                lock.quota_slot =
+                    AtomicBool::new(locks[j].quota_slot.swap(false, Ordering::SeqCst));
+                results.push(Ok(lock));
+            } else {
+                let errors = std::mem::take(&mut errors[j]);
+                results.push(Err(RedsyncError::ExtendRetriesExceeded(errors)));
+                failed.push(lock);
+            }
+        }
+
+        if !failed.is_empty() {
+            for instance in &self.cluster {
+                let _ = instance.release_many(&failed);
+            }
+        }
+
+        Ok(results)
+    }
+
+    /// `guarded_set` sets `key` to `value` on every instance where `lock` is still held, failing
+    /// unless the write succeeds on a quorum of instances.
+    pub fn guarded_set(&self, lock: &Lock, key: &str, value: &str) -> Result<(), RedsyncError> {
+        let mut n = 0;
+        let mut errors = MultiError::with_limit(self.error_limit);
+
+        for instance in &self.cluster {
+            match instance.guarded_set(lock, key, value) {
+                Ok(()) => n += 1,
+                Err(e) => errors.push(e),
+            };
+        }
+
+        if n < self.quorum {
+            return Err(RedsyncError::GuardedWriteFailed(errors));
+        }
+
+        Ok(())
+    }
+
+    /// `lock_attempts` returns a `LockAttempts` that makes one lock attempt per call to
+    /// `next_attempt`, leaving the retry policy to the caller.
+    pub fn lock_attempts(
+        &self,
+        resource: impl AsRef<[u8]>,
+        ttl: impl IntoLeaseTtl,
+    ) -> Result<LockAttempts<'_, I>, RedsyncError> {
+        self.check_paused()?;
+        let resource = &*self.check_resource(resource.as_ref())?;
+        let ttl = ttl.into_lease_ttl()?.as_duration();
+        self.check_policy(resource, ttl)?;
+        let value = self.get_unique_lock_id();
+        Ok(LockAttempts::new(self, resource, value, ttl))
+    }
+
+    /// `pause` makes new acquisitions fail with `RedsyncError::ManagerPaused` until `resume` is
+    /// called, e.g. during a migration. Held locks can still be extended and unlocked.
+    pub fn pause(&self) {
+        self.paused.store(true, Ordering::SeqCst);
+    }
+
+    /// `resume` lets new acquisitions proceed after `pause`.
+    pub fn resume(&self) {
+        self.paused.store(false, Ordering::SeqCst);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::SeqCst)
+    }
+
+    /// `is_interop` returns whether the manager was built wire-compatible with other Redlock
+    /// implementations. See `RedsyncBuilder::interop`.
+    pub fn is_interop(&self) -> bool {
+        self.interop
+    }
+
+    fn check_paused(&self) -> Result<(), RedsyncError> {
+        match self.is_paused() {
+            true => Err(RedsyncError::ManagerPaused),
+            false => Ok(()),
+        }
+    }
+
+    /// `check_resource` applies the configured resource rules to `resource`, if any. Resources
+    /// must be valid UTF-8 for the rules to apply to them.
+    fn check_resource<'a>(&self, resource: &'a [u8]) -> Result<Cow<'a, [u8]>, RedsyncError> {
+        let rules = match &self.resource_rules {
+            Some(rules) => rules,
+            None => return Ok(Cow::Borrowed(resource)),
+        };
+
+        let text = std::str::from_utf8(resource).map_err(|_| {
+            RedsyncError::InvalidResourceName(String::from_utf8_lossy(resource).into_owned())
+        })?;
+        Ok(match rules.apply(text)? {
+            Cow::Borrowed(text) => Cow::Borrowed(text.as_bytes()),
+            Cow::Owned(text) => Cow::Owned(text.into_bytes()),
+        })
+    }
+
+    fn check_policy(&self, resource: &[u8], ttl: Duration) -> Result<(), RedsyncError> {
+        match &self.policy {
+            Some(policy) => policy
+                .check(&String::from_utf8_lossy(resource), ttl)
+                .map_err(RedsyncError::PolicyViolation),
+            None => Ok(()),
+        }
+    }
+
+    /// `reserve_quotas` counts an acquisition of `resource` against every quota registered for
+    /// its prefixes, returning true if it took a slot of a `max_held` quota. Quotas are not
+    /// enforced while their counters cannot be reached.
+    fn reserve_quotas(&self, resource: &[u8]) -> Result<bool, RedsyncError> {
+        let mut held = Vec::new();
+        let quotas = self.quotas.iter();
+        for (prefix, quota) in quotas.filter(|(p, _)| resource.starts_with(p.as_bytes())) {
+            if let Some((max_rate, window)) = quota.max_rate {
+                if self.incr_counter(&rate_key(prefix), 1, window) > Some(max_rate) {
+                    self.return_held(&held);
+                    return Err(RedsyncError::QuotaExceeded(prefix.clone()));
+                }
+            }
+
+            if let Some(max_held) = quota.max_held {
+                held.push((prefix, quota));
+                if self.incr_counter(&held_key(prefix), 1, quota.held_window) > Some(max_held) {
+                    self.return_held(&held);
+                    return Err(RedsyncError::QuotaExceeded(prefix.clone()));
+                }
+            }
+        }
+
+        Ok(!held.is_empty())
+    }
+
+    /// `release_quotas` returns the `max_held` quota slot taken by `lock`, at most once.
+    fn release_quotas(&self, lock: &Lock) {
+        if lock.quota_slot.swap(false, Ordering::SeqCst) {
+            self.release_held(&lock.resource);
+        }
+    }
+
+    /// `release_held` returns a slot of every `max_held` quota registered for the prefixes of
+    /// `resource`.
+    fn release_held(&self, resource: &[u8]) {
+        let held: Vec<(&String, &Quota)> = self
+            .quotas
+            .iter()
+            .filter(|(p, q)| resource.starts_with(p.as_bytes()) && q.max_held.is_some())
+            .map(|(p, q)| (p, q))
+            .collect();
+        self.return_held(&held);
+    }
+
+    /// `return_held` returns a slot of each of the `max_held` quotas in `held`.
+    fn return_held(&self, held: &[(&String, &Quota)]) {
+        for (prefix, quota) in held {
+            self.incr_counter(&held_key(prefix), -1, quota.held_window);
+        }
+    }
+
+    /// `next_sequence` advances the acquisition sequence of `lock`'s resource. The highest value
+    /// returned by a quorum is then written back to a quorum, so that any later quorum, which
+    /// overlaps with it, observes it and numbers its acquisition higher.
+    fn next_sequence(&self, lock: &Lock) -> Result<u64, MultiError> {
+        let mut errors = MultiError::with_limit(self.error_limit);
+        let mut sequence = 0;
+        let mut n = 0;
+
+        for instance in &self.cluster {
+            match instance.advance_sequence(lock, 0, 1) {
+                Ok(value) => {
+                    sequence = sequence.max(value);
+                    n += 1;
+                }
+                Err(e) => errors.push(e),
+            };
+        }
+        if n < self.quorum {
+            return Err(errors);
+        }
+
+        n = 0;
+        for instance in &self.cluster {
+            match instance.advance_sequence(lock, sequence, 0) {
+                Ok(_) => n += 1,
+                Err(e) => errors.push(e),
+            };
+        }
+        if n < self.quorum {
+            return Err(errors);
+        }
+
+        Ok(sequence as u64)
+    }
+
+    /// `incr_counter` increments a counter on the instance that `key` hashes to, so that every
+    /// increment of the counter, from any manager of the same cluster, lands on one instance.
+    fn incr_counter(&self, key: &str, delta: i64, expiry: Duration) -> Option<i64> {
+        let i = (fnv1a(key.as_bytes()) % self.cluster.len() as u64) as usize;
+        self.cluster[i].incr(key, delta, expiry).ok()
+    }
+
+    /// `call` makes up to the configured number of attempts for `call`, reusing `lock` and the
+    /// errors of the previous attempt rather than allocating them again for each one.
+    fn call(
+        &self,
+        call: Call,
+        lock: Lock,
+        deadline: Option<Instant>,
+    ) -> Result<Lock, RedsyncError> {
+        #[cfg(feature = "opentelemetry")]
+        let telemetry = crate::otel::CallTelemetry::start(
+            &call,
+            &lock.resource,
+            self.quorum,
+            self.cluster.len(),
+        );
+
+        let result = self.retry(call, lock, deadline);
+
+        #[cfg(feature = "opentelemetry")]
+        telemetry.finish(&result);
+        result
+    }
+
+    /// `retry` makes the attempts of `call`, sleeping between them, until none can start before
+    /// `deadline`.
+    fn retry(
+        &self,
+        call: Call,
+        mut lock: Lock,
+        deadline: Option<Instant>,
+    ) -> Result<Lock, RedsyncError> {
+        let mut errors = MultiError::with_limit(self.error_limit);
+        let retry_count = match call {
+            Call::Lock { .. } | Call::Reacquire => self.retry_count,
+            Call::Extend => self.extend_retry_count,
+        };
+
+        let started = Instant::now();
+        let mut starved = false;
+
+        for attempt in 1..=retry_count {
+            if self.attempt(&call, &mut lock, &mut errors) {
+                return Ok(lock);
+            }
+            // A resource past the expected version never returns to it
+            if errors
+                .iter()
+                .any(|e| matches!(e.inner(), RedsyncError::VersionMismatch { .. }))
+            {
+                break;
+            }
+
+            if let (Call::Lock { .. }, Some((threshold, hook)), false) =
+                (&call, &self.starvation, starved)
+            {
+                if started.elapsed() >= *threshold {
+                    starved = true;
+                    hook(&Starvation {
+                        resource: lock.resource.clone(),
+                        waited: started.elapsed(),
+                        attempts: attempt,
+                        waiters: self.waiters.count(&lock.resource),
+                    });
+                }
+            }
+
+            if attempt < retry_count {
+                let delay = match call {
+                    Call::Lock { .. } | Call::Reacquire => self.get_retry_delay(&lock.resource),
+                    Call::Extend => self.extend_retry_delay,
+                };
+                if deadline.is_some_and(|deadline| Instant::now() + delay >= deadline) {
+                    break;
+                }
+                errors.reset();
+                self.sleep(delay);
+            }
+        }
+
+        if self.config_snapshot {
+            errors.set_config(Some(self.config()));
+        }
+        match call {
+            Call::Lock { .. } | Call::Reacquire => {
+                if errors.kind() == ErrorKind::Contention {
+                    errors.set_retry_after(self.retry_after(&lock.resource));
+                }
+                Err(RedsyncError::LockRetriesExceeded(errors))
+            }
+            Call::Extend => Err(RedsyncError::ExtendRetriesExceeded(errors)),
+        }
+    }
+
+    /// `retry_after` estimates how long until `resource` can be locked: once the lock held on it
+    /// has expired on a quorum of instances, plus the time taken to reach an instance. Returns
+    /// None if too few instances could be inspected, or the lock does not expire.
+    fn retry_after(&self, resource: &[u8]) -> Option<Duration> {
+        let quorum = self.quorum as usize;
+        let start = Instant::now();
+        let mut remaining: Vec<Duration> = self
+            .cluster
+            .iter()
+            .filter_map(|instance| instance.peek(resource).ok())
+            .map(Option::unwrap_or_default)
+            .collect();
+        let latency = start.elapsed() / self.cluster.len().max(1) as u32;
+
+        if quorum == 0 || remaining.len() < quorum {
+            return None;
+        }
+        remaining.sort_unstable();
+        match remaining[quorum - 1] {
+            Duration::MAX => None,
+            ttl => Some(ttl + latency),
+        }
+    }
+
+    /// `sleep` waits for `delay` with the configured sleep function, or the current thread.
+    fn sleep(&self, delay: Duration) {
+        match &self.sleep {
+            Some(sleep) => sleep(delay),
+            None => thread::sleep(delay),
+        }
+    }
+
+    /// `attempt` makes a single attempt for `call` with `lock`, returning whether it reached a
+    /// quorum. The fields of `lock` that depend on when the attempt started are reset, so that
+    /// the lock can be reused across attempts.
+    pub(crate) fn attempt(&self, call: &Call, lock: &mut Lock, errors: &mut MultiError) -> bool {
+        if self.cluster.len() == 1 {
+            return self.attempt_single(call, lock, errors);
+        }
+
+        let ttl = lock.ttl;
+        let drift = self.get_drift(ttl);
+
+        let mut votes = 0;
+        let start = Instant::now();
+
+        lock.expiry = start + ttl - drift;
+        lock.history = RenewalHistory::new(start);
+        lock.lost_instances.clear();
+        lock.sequence = None;
+        lock.report = AcquireReport {
+            started: start,
+            ttl,
+            io: Duration::ZERO,
+            drift,
+        };
+
+        let mut rollback = Rollback::new(self, lock);
+        let (lock, targets) = rollback.parts();
+
+        let mut domains = HashSet::new();
+
+        // Pipelined acquisitions are sent to every instance before any reply is read. The
+        // instances are rolled back unless they definitely do not hold the lock, as one that
+        // failed or panicked mid-call may have granted it
+        let send: Option<for<'a> fn(&'a I, &Lock) -> PendingCall<'a>> = match call {
+            Call::Lock {
+                durability: None,
+                version: None,
+            } if self.pipelined => Some(I::send_acquire),
+            Call::Extend if self.pipelined => Some(I::send_extend),
+            _ => None,
+        };
+        let mut pending: Vec<_> = match send {
+            Some(send) => self
+                .cluster
+                .iter()
+                .enumerate()
+                .map(|(i, instance)| {
+                    targets.push(i);
+                    Some(send(instance, lock))
+                })
+                .collect(),
+            None => Vec::new(),
+        };
+        let pipelined = !pending.is_empty();
+
+        for (i, instance) in self.cluster.iter().enumerate() {
+            // A reacquired lock may be the caller's existing lease, which is left in place
+            if !pipelined && !matches!(call, Call::Reacquire) {
+                targets.push(i);
+            }
+            let result = match pending.get_mut(i).and_then(Option::take) {
+                Some(pending) => pending.wait(lock),
+                None => call_instance(instance, call, lock),
+            };
+
+            match result {
+                Ok(()) => {
+                    votes += 1;
+                    if let Some(domain) = self.failure_domains.get(i) {
+                        domains.insert(domain);
+                    }
+                }
+                Err(RedsyncError::InvalidLease) if matches!(call, Call::Extend) => {
+                    targets.retain(|&target| target != i);
+                    lock.lost_instances.push(i);
+                    errors.push(RedsyncError::InstanceError(
+                        i,
+                        Box::new(RedsyncError::InvalidLease),
+                    ));
+                }
+                Err(e @ (RedsyncError::ResourceLocked | RedsyncError::VersionMismatch { .. })) => {
+                    targets.retain(|&target| target != i);
+                    errors.push(RedsyncError::InstanceError(i, Box::new(e)));
+                }
+                Err(e) => errors.push(RedsyncError::InstanceError(i, Box::new(e))),
+            }
+
+            // Every pipelined instance may have granted the lock, so every reply is read
+            let remaining = (self.cluster.len() - i - 1) as u32;
+            let reached = votes >= self.quorum && domains.len() >= self.domain_quorum;
+            if !pipelined
+                && self
+                    .short_circuit
+                    .is_decided(votes, remaining, self.quorum, reached)
+            {
+                break;
+            }
+        }
+
+        lock.report.io = start.elapsed();
+
+        let reached = votes >= self.quorum
+            && domains.len() >= self.domain_quorum
+            && lock.expiry > Instant::now();
+        #[cfg(feature = "opentelemetry")]
+        crate::otel::attempted(call, votes, reached);
+
+        if self.conclude(call, lock, errors, reached) {
+            rollback.disarm();
+            return true;
+        }
+
+        false
+    }
+
+    /// `attempt_single` makes a single attempt for `call` on a manager of one instance, the
+    /// common deployment of a single Redis server. With no other instance to agree with, it skips
+    /// counting votes, but still reserves the drift estimate from the TTL, as the clock of the
+    /// instance can drift from ours all the same, and still requires the failure domain quorum.
+    /// A lock that a failed call may have granted is released directly instead of through a
+    /// `Rollback`; if the instance panics, it is left to expire at the end of its TTL.
+    fn attempt_single(&self, call: &Call, lock: &mut Lock, errors: &mut MultiError) -> bool {
+        let instance = &self.cluster[0];
+        let ttl = lock.ttl;
+        let drift = self.get_drift(ttl);
+        let start = Instant::now();
+
+        lock.expiry = start + ttl - drift;
+        lock.history = RenewalHistory::new(start);
+        lock.lost_instances.clear();
+        lock.sequence = None;
+        lock.report = AcquireReport {
+            started: start,
+            ttl,
+            io: Duration::ZERO,
+            drift,
+        };
+
+        let result = call_instance(instance, call, lock);
+        lock.report.io = start.elapsed();
+
+        let granted = result.is_ok();
+        let held = match result {
+            Ok(()) => true,
+            Err(RedsyncError::InvalidLease) if matches!(call, Call::Extend) => {
+                lock.lost_instances.push(0);
+                errors.push(RedsyncError::InstanceError(
+                    0,
+                    Box::new(RedsyncError::InvalidLease),
+                ));
+                false
+            }
+            Err(e @ (RedsyncError::ResourceLocked | RedsyncError::VersionMismatch { .. })) => {
+                errors.push(RedsyncError::InstanceError(0, Box::new(e)));
+                false
+            }
+            Err(e) => {
+                errors.push(RedsyncError::InstanceError(0, Box::new(e)));
+                true
+            }
+        };
+
+        let domains = usize::from(granted && !self.failure_domains.is_empty());
+        let reached = granted && domains >= self.domain_quorum && lock.expiry > Instant::now();
+        #[cfg(feature = "opentelemetry")]
+        crate::otel::attempted(call, u32::from(granted), reached);
+
+        if self.conclude(call, lock, errors, reached) {
+            return true;
+        }
+
+        if held && self.rollback == RollbackPolicy::Release && !matches!(call, Call::Reacquire) {
+            let _ = instance.release(lock);
+        }
+        false
+    }
+
+    /// `conclude` records the outcome of an attempt for `call`, and returns whether it succeeded.
+    /// An attempt that `reached` a quorum still fails if `lock` cannot be numbered when
+    /// sequences are enabled.
+    fn conclude(
+        &self,
+        call: &Call,
+        lock: &mut Lock,
+        errors: &mut MultiError,
+        reached: bool,
+    ) -> bool {
+        if reached {
+            if let Some(backoff) = &self.backoff {
+                backoff.record_success(&lock.resource);
+            }
+            if self.partial_extend == PartialExtend::Repair {
+                let mut lost = std::mem::take(&mut lock.lost_instances);
+                lost.retain(|&i| self.cluster[i].acquire(lock).is_err());
+                lock.lost_instances = lost;
+            }
+            if matches!(call, Call::Lock { .. }) && self.sequences {
+                match self.next_sequence(lock) {
+                    Ok(sequence) => lock.sequence = Some(sequence),
+                    Err(mut e) => {
+                        errors.append(&mut e);
+                        return false;
+                    }
+                }
+            }
+            #[cfg(feature = "leak-detector")]
+            crate::leak::track(lock);
+            if let Some(journal) = &self.journal {
+                let _ = journal.record(&journal_entry(lock));
+            }
+            if let Call::Lock { .. } = call {
+                self.record_stats(&lock.resource, &[(ACQUISITIONS, 1)]);
+                #[cfg(feature = "lock-order")]
+                crate::lockorder::acquired(&lock.resource);
+            }
+            return true;
+        }
+
+        if errors.includes(RedsyncError::ResourceLocked) {
+            if let Some(backoff) = &self.backoff {
+                backoff.record_contention(&lock.resource);
+            }
+            self.record_stats(&lock.resource, &[(CONTENTIONS, 1)]);
+        }
+
+        false
+    }
+
+    /// `unlock_owned` releases `lock` like `unlock`, consuming it so that it cannot be unlocked
+    /// twice.
+    pub fn unlock_owned(&self, lock: Lock) -> Result<(), RedsyncError> {
+        self.unlock(&lock)
+    }
+
+    /// `unlock` releases `lock`, failing with `LockAlreadyExpired` if it had expired on every
+    /// instance, or `UnlockFailed` if it could not be released on a quorum for any other reason.
+    /// Unlocking a lock again fails with `LockAlreadyExpired`; prefer `unlock_owned` unless the
+    /// lock must outlive its release.
+    pub fn unlock(&self, lock: &Lock) -> Result<(), RedsyncError> {
+        self.unlock_with(lock, |lock| self.release_report(lock))
+    }
+
+    /// `unlock_with` releases `lock` like `unlock`, releasing it on the instances with `release`.
+    fn unlock_with(
+        &self,
+        lock: &Lock,
+        release: impl FnOnce(&Lock) -> UnlockReport,
+    ) -> Result<(), RedsyncError> {
+        self.check_manager(lock)?;
+        let report = self.unlock_report_with(lock, release);
+        if report.is_expired() {
+            return Err(RedsyncError::LockAlreadyExpired);
+        }
+        if report.released < self.quorum as usize {
+            let mut errors = report.into_errors();
+            if self.config_snapshot {
+                errors.set_config(Some(self.config()));
+            }
+            return Err(RedsyncError::UnlockFailed(errors));
+        }
+
+        Ok(())
+    }
+
+    /// `unlock_report` releases `lock` like `unlock`, but reports how many instances released
+    /// it, how many no longer held it, and which failed, instead of failing without a quorum.
+    pub fn unlock_report(&self, lock: &Lock) -> UnlockReport {
+        self.unlock_report_with(lock, |lock| self.release_report(lock))
+    }
+
+    /// `unlock_report_with` releases `lock` like `unlock_report`, releasing it on the instances
+    /// with `release`.
+    fn unlock_report_with(
+        &self,
+        lock: &Lock,
+        release: impl FnOnce(&Lock) -> UnlockReport,
+    ) -> UnlockReport {
+        if let Err(e) = self.check_manager(lock) {
+            let mut report = UnlockReport::default();
+            report.errors.push(e);
+            return report;
+        }
+        #[cfg(feature = "leak-detector")]
+        crate::leak::untrack(lock);
+        #[cfg(feature = "lock-order")]
+        crate::lockorder::released(&lock.resource);
+        if let Some(journal) = &self.journal {
+            let _ = journal.remove(&lock.resource, &lock.value);
+        }
+
+        let report = release(lock);
+        // A lock left on a quorum keeps its slot, to be returned once it is unlocked
+        if report.released >= self.quorum as usize || report.is_expired() {
+            self.release_quotas(lock);
+        }
+        if report.released >= self.quorum as usize {
+            // The lock was acquired at roughly its expiry less its TTL, ignoring drift
+            let remaining = lock.expiry.saturating_duration_since(Instant::now());
+            let held = lock.ttl.saturating_sub(remaining).as_millis() as i64;
+            self.record_stats(&lock.resource, &[(RELEASES, 1), (HOLD_MS, held)]);
+        }
+
+        if self.audit.is_some() {
+            let result = if report.released >= self.quorum as usize {
+                Ok(())
+            } else if report.is_expired() {
+                Err(RedsyncError::LockAlreadyExpired)
+            } else {
+                Err(RedsyncError::UnlockFailed(MultiError::new()))
+            };
+            self.audit(AuditAction::Release, &lock.resource, &result);
+        }
+
+        report
+    }
+
+    /// `release_on` releases `lock` on the instances at `indices`, ignoring failures.
+    pub(crate) fn release_on(&self, lock: &Lock, indices: &[usize]) {
+        match self.pipelined {
+            true => {
+                let pending: Vec<_> = indices
+                    .iter()
+                    .map(|&i| self.cluster[i].send_release(lock))
+                    .collect();
+                for pending in pending {
+                    let _ = pending.wait(lock);
+                }
+            }
+            false => {
+                for &i in indices {
+                    let _ = self.cluster[i].release(lock);
+                }
+            }
+        }
+    }
+
+    /// `release_report` releases `lock` on every instance and reports the outcome.
+    fn release_report(&self, lock: &Lock) -> UnlockReport {
+        let mut report = UnlockReport {
+            errors: MultiError::with_limit(self.error_limit),
+            ..UnlockReport::default()
+        };
+
+        // Pipelined releases are sent to every instance before any reply is read
+        let pending: Vec<_> = self
+            .cluster
+            .iter()
+            .map(|instance| match self.pipelined {
+                true => instance.send_release(lock),
+                false => PendingCall::done(instance.release(lock)),
+            })
+            .collect();
+
+        for pending in pending {
+            match pending.wait(lock) {
+                Ok(()) => report.released += 1,
+                Err(RedsyncError::InvalidLease) => report.expired += 1,
+                Err(e) => report.errors.push(e),
+            };
+        }
+
+        report
+    }
+
+    /// `quorum` returns the number of instances a lock must be acquired on to be held.
+    pub fn quorum(&self) -> u32 {
+        self.quorum
+    }
+
+    /// `cluster_size` returns the number of instances managed by the manager.
+    pub fn cluster_size(&self) -> usize {
+        self.cluster.len()
+    }
+
+    /// `config` returns a snapshot of the configuration that shapes the calls of the manager.
+    pub fn config(&self) -> ConfigSnapshot {
+        ConfigSnapshot {
+            cluster_size: self.cluster.len(),
+            quorum: self.quorum,
+            retry_count: self.retry_count,
+            retry_delay: self.retry_delay,
+            retry_jitter: self.retry_jitter,
+            extend_retry_count: self.extend_retry_count,
+            extend_retry_delay: self.extend_retry_delay,
+            drift_factor: self.drift_factor,
+            interop: self.interop,
+        }
+    }
+
+    /// `id` returns the identity of the manager, recorded on each lock it issues.
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// `adopt` takes over `lock` from another manager, or a lock constructed by hand, so that it
+    /// can be extended and unlocked through this manager. The caller is responsible for the lock
+    /// being held on this manager's cluster.
+    pub fn adopt(&self, mut lock: Lock) -> Lock {
+        lock.manager = self.id;
+        lock
+    }
+
+    /// `check_manager` fails with `ForeignLock` if `lock` was issued by another manager.
+    fn check_manager(&self, lock: &Lock) -> Result<(), RedsyncError> {
+        if lock.manager != self.id {
+            return Err(RedsyncError::ForeignLock(lock.manager));
+        }
+
+        Ok(())
+    }
+
+    /// `recover` re-adopts the unexpired locks recorded in `journal`, e.g. by a previous run of
+    /// the process that crashed. Locks still held on a quorum of instances are returned, to be
+    /// used or unlocked by the caller; all other entries are removed from the journal.
+    pub fn recover(&self, journal: &dyn LockJournal) -> Result<Vec<Lock>, RedsyncError> {
+        let now = SystemTime::now();
+        let mut locks = Vec::new();
+
+        for entry in journal.entries()? {
+            let remaining = entry.expires_at.duration_since(now).unwrap_or_default();
+            let start = Instant::now();
+            let mut lock = Lock::new(
+                entry.resource,
+                entry.value,
+                entry.ttl,
+                start + remaining,
+                self.id,
+            );
+
+            match self.remaining_ttl(&lock) {
+                Ok(remaining) if !remaining.is_zero() => {
+                    lock.expiry = start + remaining;
+                    locks.push(lock);
+                }
+                _ => journal.remove(&lock.resource, &lock.value)?,
+            }
+        }
+
+        Ok(locks)
+    }
+
+    /// `stats` returns the lock statistics recorded for `resource` when statistics are enabled
+    /// through `RedsyncBuilder::stats`.
+    pub fn stats(&self, resource: impl AsRef<[u8]>) -> Result<ResourceStats, RedsyncError> {
+        let mut errors = MultiError::with_limit(self.error_limit);
+
+        for instance in &self.cluster {
+            match instance.read_stats(resource.as_ref()) {
+                Ok(fields) => return Ok(ResourceStats::from_fields(fields)),
+                Err(e) => errors.push(e),
+            };
+        }
+
+        Err(RedsyncError::StatsFailed(errors))
+    }
+
+    /// `record_stats` samples an update to the statistics of `resource`, writing it to the first
+    /// instance that accepts it. Sampled updates are weighted to estimate the true totals.
+    fn record_stats(&self, resource: &[u8], fields: &[(&str, i64)]) {
+        let sample_rate = match self.stats_sample_rate {
+            Some(rate) if rate > 0.0 && thread_rng().gen_bool(rate.min(1.0)) => rate.min(1.0),
+            _ => return,
+        };
+
+        let weight = (1.0 / sample_rate).round() as i64;
+        let fields: Vec<_> = fields.iter().map(|(f, v)| (*f, v * weight)).collect();
+
+        for instance in &self.cluster {
+            if instance.incr_stats(resource, &fields).is_ok() {
+                return;
+            }
+        }
+    }
+
+    /// `audit` appends an event recording `action` on `resource` to the audit stream of the
+    /// first instance that accepts it, if the audit log is enabled.
+    fn audit<T>(&self, action: AuditAction, resource: &[u8], result: &Result<T, RedsyncError>) {
+        let audit = match &self.audit {
+            Some(audit) => audit,
+            None => return,
+        };
+
+        let fields = audit.fields(action, &String::from_utf8_lossy(resource), result);
+        for instance in &self.cluster {
+            if instance.append_audit(&fields, audit.retention).is_ok() {
+                return;
+            }
+        }
+    }
+
+    /// `audit_tail` returns up to `n` of the newest events of the audit log, newest first, read
+    /// from the first instance that answers. Events are only appended to a single instance, so
+    /// those written while it was unreachable are found on the others.
+    pub fn audit_tail(&self, n: usize) -> Result<Vec<AuditEvent>, RedsyncError> {
+        let mut errors = MultiError::with_limit(self.error_limit);
+
+        for instance in &self.cluster {
+            match instance.read_audit(n) {
+                Ok(events) => return Ok(events),
+                Err(e) => errors.push(e),
+            };
+        }
+
+        Err(RedsyncError::AuditFailed(errors))
+    }
+
+    /// `force_unlock_prefix` deletes every lock whose resource starts with `prefix` on every
+    /// instance, returning the resources found, or only lists them if `dry_run` is set.
+    ///
+    /// Locks are recognized by their `RecordVersion::V2` tag. Locks written as bare tokens, by
+    /// managers using `RecordVersion::V1` or `interop` and by other implementations, are only
+    /// deleted if `bare_tokens` is set, as are any other strings with an expiry under `prefix`.
+    ///
+    /// This is destructive: locks are deleted regardless of who holds them, so their holders will
+    /// keep working on resources that are no longer protected. Reserve it for recovering from
+    /// stuck locks, such as after a tenant is removed, and prefer a dry run first.
+    pub fn force_unlock_prefix(
+        &self,
+        prefix: &str,
+        dry_run: bool,
+        bare_tokens: bool,
+    ) -> Result<Vec<String>, RedsyncError> {
+        let mut resources = Vec::new();
+        let mut errors = MultiError::with_limit(self.error_limit);
+
+        for instance in &self.cluster {
+            match instance.force_release_prefix(prefix, dry_run, bare_tokens) {
+                Ok(keys) => resources.extend(keys),
+                Err(e) => errors.push(e),
+            };
+        }
+
+        if !errors.is_empty() {
+            return Err(RedsyncError::ForceUnlockFailed(errors));
+        }
+
+        resources.sort_unstable();
+        resources.dedup();
+        Ok(resources)
+    }
+
+    /// `can_lock` predicts whether locking `resource` would succeed now, and otherwise when it is
+    /// expected to free up, using read-only queries that leave every instance unmodified.
+    pub fn can_lock(&self, resource: impl AsRef<[u8]>) -> Result<LockPreview, RedsyncError> {
+        let resource = &*self.check_resource(resource.as_ref())?;
+        let mut ttls = Vec::with_capacity(self.cluster.len());
+        let mut errors = MultiError::with_limit(self.error_limit);
+
+        for instance in &self.cluster {
+            match instance.peek(resource) {
+                Ok(ttl) => ttls.push(ttl),
+                Err(e) => errors.push(e),
+            };
+        }
+
+        LockPreview::from_ttls(ttls, self.quorum as usize)
+            .ok_or(RedsyncError::PreviewFailed(errors))
+    }
+
+    /// `preflight` inspects every instance for configuration that undermines Redlock safety.
+    pub fn preflight(&self) -> PreflightReport {
+        PreflightReport {
+            nodes: self.cluster.iter().map(Instance::diagnostics).collect(),
+        }
+    }
+
+    /// `calibrate_drift` samples the server time of every instance and updates the drift factor
+    /// measured since the previous calibration. Call it periodically to keep the estimate fresh.
+    pub fn calibrate_drift(&self) -> Result<Option<f64>, RedsyncError> {
+        let mut samples = Vec::with_capacity(self.cluster.len());
+        let mut errors = MultiError::with_limit(self.error_limit);
+
+        for instance in &self.cluster {
+            let before = Instant::now();
+            let result = instance.time();
+            let midpoint = before + before.elapsed() / 2;
+
+            match result {
+                Ok(time) => samples.push(Some((midpoint, time))),
+                Err(e) => {
+                    samples.push(None);
+                    errors.push(e);
+                }
+            };
+        }
+
+        if (samples.iter().flatten().count() as u32) < self.quorum {
+            return Err(RedsyncError::CalibrationFailed(errors));
+        }
+
+        let rates = self.drift.record(samples);
+        if rates.len() as u32 >= self.quorum {
+            self.drift.set_factor(rates.into_iter().fold(0.0, f64::max));
+        }
+
+        Ok(self.drift.factor())
+    }
+
+    /// `measured_drift_factor` returns the drift factor measured by `calibrate_drift`, if any.
+    pub fn measured_drift_factor(&self) -> Option<f64> {
+        self.drift.factor()
+    }
+
+    /// `remaining_ttl` returns how long `lock` remains valid for, as seen by a quorum of instances
+    /// and less the elapsed time and clock drift. `MaintainedLock::maintain` uses it to schedule
+    /// renewals from the instances' view of the lock.
+    pub fn remaining_ttl(&self, lock: &Lock) -> Result<Duration, RedsyncError> {
+        let drift = self.get_drift(lock.ttl);
+
+        let start = Instant::now();
+        let mut ttls = Vec::with_capacity(self.cluster.len());
+        let mut errors = MultiError::with_limit(self.error_limit);
+
+        for instance in &self.cluster {
+            match instance.ttl(lock) {
+                Ok(ttl) => ttls.push(ttl),
+                Err(e) => errors.push(e),
+            };
+        }
+
+        if (ttls.len() as u32) < self.quorum {
+            return Err(RedsyncError::TtlFailed(errors));
+        }
+
+        ttls.sort_unstable_by(|a, b| b.cmp(a));
+        let ttl = ttls[self.quorum as usize - 1];
+
+        Ok(ttl.saturating_sub(start.elapsed() + drift))
+    }
+
+    fn get_drift(&self, ttl: Duration) -> Duration {
+        let drift_factor = match self.dynamic_drift {
+            true => self.drift.factor().unwrap_or(self.drift_factor),
+            false => self.drift_factor,
+        };
+
+        Duration::from_millis((ttl.as_millis() as f64 * drift_factor) as u64) + DRIFT_MARGIN
+    }
+
+    fn get_unique_lock_id(&self) -> Vec<u8> {
+        let token: String = thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(20)
+            .map(char::from)
+            .collect();
+
+        LockRecord::new(self.record_version, &token).encode()
+    }
+
+    /// `keepalive` pings every instance, keeping idle connections alive. Call it periodically,
+    /// at an interval shorter than the server's idle timeout.
+    pub fn keepalive(&self) -> Result<(), RedsyncError> {
+        ping_cluster(&self.cluster)
+    }
+
+    /// `maintain` wraps `lock` in a `MaintainedLock`, which renews it according to `schedule`
+    /// whenever `MaintainedLock::maintain` is polled.
+    pub fn maintain(&self, lock: Lock, schedule: KeepaliveSchedule) -> MaintainedLock<'_, I> {
+        MaintainedLock::new(self, lock, schedule)
+    }
+
+    /// `contention_report` returns the number of local callers currently blocked in `lock` on
+    /// each resource, and how long the longest of them has been waiting.
+    pub fn contention_report(&self) -> Vec<ResourceContention> {
+        self.waiters.report()
+    }
+
+    /// `retry_backoff` returns the current retry delay multiplier of each contended resource.
+    pub fn retry_backoff(&self) -> HashMap<Vec<u8>, f64> {
+        self.backoff
+            .as_ref()
+            .map(AdaptiveBackoff::snapshot)
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn get_retry_delay(&self, resource: &[u8]) -> Duration {
+        let scale = self
+            .backoff
+            .as_ref()
+            .map_or(1.0, |backoff| backoff.scale(resource));
+
+        let retry_delay = self.retry_delay.mul_f64(scale);
+        let jitter = thread_rng().gen_range(-1.0..1.0) * self.retry_jitter * scale;
+        if jitter > 0.0 {
+            retry_delay.add(Duration::from_millis(jitter as u64))
+        } else {
+            retry_delay.sub(Duration::from_millis(-jitter as u64))
+        }
+    }
+}
+
+/// `RELEASE_THREAD_NAME` is the name of the threads spawned by `Redsync::release_async` and
+/// `Redsync::unlock_within`.
+pub(crate) const RELEASE_THREAD_NAME: &str = "redsync-release";
+
+impl<I: Instance + Send + Sync + 'static> Redsync<I> {
+    /// `release_async` unlocks `lock` on a background thread and returns immediately, for
+    /// latency-sensitive paths that should not wait on the round trips of an unlock. The returned
+    /// handle yields the result of the unlock, and can be dropped if it is of no interest; a lock
+    /// that fails to release still expires at the end of its TTL.
+    ///
+    /// The thread is named `redsync-release`, so that it can be told apart in debuggers and
+    /// profilers. Fails with `ThreadSpawnFailed` if the thread cannot be spawned, in which case
+    /// the lock is left to expire.
+    pub fn release_async(
+        self: &Arc<Self>,
+        lock: Lock,
+    ) -> Result<JoinHandle<Result<(), RedsyncError>>, RedsyncError> {
+        let dlm = Arc::clone(self);
+        thread::Builder::new()
+            .name(String::from(RELEASE_THREAD_NAME))
+            .spawn(move || dlm.unlock_owned(lock))
+            .map_err(|e| RedsyncError::ThreadSpawnFailed(e.to_string()))
+    }
+
+    /// `watchdog` renews `lock` on a background thread according to `schedule`, until the returned
+    /// `Watchdog` is stopped. Use `MaintainedLock` instead where spawning a thread per lock is
+    /// unsuitable.
+    ///
+    /// The thread is named `redsync-watchdog`. Fails with `ThreadSpawnFailed` if the thread cannot
+    /// be spawned, in which case the lock is left as it is.
+    pub fn watchdog(
+        self: &Arc<Self>,
+        lock: Lock,
+        schedule: KeepaliveSchedule,
+    ) -> Result<Watchdog, RedsyncError> {
+        Watchdog::spawn(Arc::clone(self), lock, schedule)
+    }
+
+    /// `unlock_within` releases `lock` like `unlock`, but contacts every instance concurrently
+    /// and stops waiting once `timeout` has elapsed, so that a slow instance delays the release
+    /// by at most `timeout` rather than the sum of the latencies of the instances. Instances that
+    /// have not responded by then count as failed with `ReleaseTimedOut`; their releases carry
+    /// on in the background, and a lock that fails to release still expires at the end of its
+    /// TTL.
+    ///
+    /// The releases run on a pool of one `redsync-release` thread per instance, started on first
+    /// use and shared by every call, so that the number of threads stays bounded however many
+    /// locks are released at once. Fails with `ThreadSpawnFailed` if the pool cannot be started.
+    pub fn unlock_within(
+        self: &Arc<Self>,
+        lock: &Lock,
+        timeout: Duration,
+    ) -> Result<(), RedsyncError> {
+        let mut spawn_error = None;
+        let result = self.unlock_with(lock, |lock| {
+            self.release_parallel(lock, timeout).unwrap_or_else(|e| {
+                spawn_error = Some(e);
+                UnlockReport::default()
+            })
+        });
+
+        match spawn_error {
+            Some(e) => Err(e),
+            None => result,
+        }
+    }
+
+    /// `release_parallel` releases `lock` on every instance through the release pool, and reports
+    /// the outcome of those that respond within `timeout`.
+    fn release_parallel(
+        self: &Arc<Self>,
+        lock: &Lock,
+        timeout: Duration,
+    ) -> Result<UnlockReport, RedsyncError> {
+        let deadline = Instant::now() + timeout;
+        let released = Arc::new(Lock::pending(
+            lock.resource.clone(),
+            lock.value.clone(),
+            lock.ttl,
+            lock.manager,
+        ));
+        let (sender, receiver) = mpsc::channel();
+        for i in 0..self.cluster.len() {
+            let dlm = Arc::clone(self);
+            let lock = Arc::clone(&released);
+            let sender = sender.clone();
+            self.release_pool.execute(move || {
+                let _ = sender.send(dlm.cluster[i].release(&lock));
+            })?;
+        }
+        drop(sender);
+
+        let mut report = UnlockReport {
+            errors: MultiError::with_limit(self.error_limit),
+            ..UnlockReport::default()
+        };
+        let mut responded = 0;
+        while responded < self.cluster.len() {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            match receiver.recv_timeout(remaining) {
+                Ok(result) => match result {
+                    Ok(()) => report.released += 1,
+                    Err(RedsyncError::InvalidLease) => report.expired += 1,
+                    Err(e) => report.errors.push(e),
+                },
+                Err(_) => break,
+            }
+            responded += 1;
+        }
+        for _ in responded..self.cluster.len() {
+            report.errors.push(RedsyncError::ReleaseTimedOut);
+        }
+
+        Ok(report)
+    }
+}
+
+/// `call_instance` makes `call` for `lock` on `instance`.
+fn call_instance<I: Instance>(instance: &I, call: &Call, lock: &Lock) -> Result<(), RedsyncError> {
+    match call {
+        Call::Lock {
+            durability,
+            version: Some(version),
+        } => instance.acquire_if_version(lock, *version, durability.as_ref()),
+        Call::Lock {
+            durability: Some(durability),
+            version: None,
+        } => instance.acquire_replicated(lock, durability),
+        Call::Lock {
+            durability: None,
+            version: None,
+        } => instance.acquire(lock),
+        Call::Extend => instance.extend(lock),
+        Call::Reacquire => instance.reacquire(lock),
+    }
+}
+
+/// `journal_entry` returns the journal entry recording `lock`.
+fn journal_entry(lock: &Lock) -> JournalEntry {
+    let remaining = lock.expiry.saturating_duration_since(Instant::now());
+    JournalEntry {
+        resource: lock.resource.clone(),
+        value: lock.value.clone(),
+        ttl: lock.ttl,
+        expires_at: SystemTime::now() + remaining,
+    }
+}
+
+/// `ping_cluster` pings every instance, failing with the errors of those that are unreachable.
+pub(crate) fn ping_cluster<I: Instance>(cluster: &[I]) -> Result<(), RedsyncError> {
+    let mut errors = MultiError::new();
+    for (i, instance) in cluster.iter().enumerate() {
+        if let Err(e) = instance.ping() {
+            errors.push(RedsyncError::InstanceError(i, Box::new(e)));
+        }
+    }
+
+    if !errors.is_empty() {
+        return Err(RedsyncError::UnreachableInstances(errors));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::attempts::AttemptOutcome;
+    use crate::guard::take_panic_releases;
+    use crate::job::{JobOutcome, SingletonJob};
+    use crate::policy::PolicyViolation;
+    use crate::preflight::{NodeDiagnostics, PreflightWarning};
+    use crate::quota::{held_key, Quota};
+    use crate::sequence::sequence_key;
+    use crate::sharded::ShardedRedsync;
+    use crate::stripe::LockStripe;
+    use std::matches;
+    use std::ops::Range;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::{Arc, Mutex};
+    use std::time::SystemTime;
+
+    struct FakeInstance {
+        acquire: i32,
+        extend: i32,
+        release: i32,
+        ttl: i64,
+        keys: Vec<String>,
+        reachable: bool,
+        stats: Mutex<HashMap<String, i64>>,
+        audit: Mutex<Vec<AuditEvent>>,
+        calls: AtomicU32,
+        releases: AtomicU32,
+        replicas: u32,
+    }
+
+    impl FakeInstance {
+        pub fn new(acquire: i32, extend: i32, release: i32) -> Self {
+            Self {
+                acquire,
+                extend,
+                release,
+                ttl: 1000,
+                keys: vec![],
+                reachable: true,
+                stats: Mutex::new(HashMap::new()),
+                audit: Mutex::new(Vec::new()),
+                calls: AtomicU32::new(0),
+                releases: AtomicU32::new(0),
+                replicas: 0,
+            }
+        }
+
+        pub fn unreachable(mut self) -> Self {
+            self.reachable = false;
+            self
+        }
+
+        pub fn keys(mut self, keys: &[&str]) -> Self {
+            self.keys = keys.iter().map(|key| String::from(*key)).collect();
+            self
+        }
+
+        pub fn ttl(mut self, ttl: i64) -> Self {
+            self.ttl = ttl;
+            self
+        }
+
+        pub fn replicas(mut self, replicas: u32) -> Self {
+            self.replicas = replicas;
+            self
+        }
+    }
+
+    impl Instance for FakeInstance {
+        fn acquire(&self, _lock: &Lock) -> Result<(), RedsyncError> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            match self.acquire {
+                1 => Ok(()),
+                2 => panic!("acquire panicked"),
+                3 => Err(RedsyncError::InjectedFault),
+                _ => Err(RedsyncError::ResourceLocked),
+            }
+        }
+
+        fn acquire_replicated(
+            &self,
+            lock: &Lock,
+            durability: &Durability,
+        ) -> Result<(), RedsyncError> {
+            self.acquire(lock)?;
+            durability.check(self.replicas)
+        }
+
+        fn acquire_if_version(
+            &self,
+            lock: &Lock,
+            version: u64,
+            durability: Option<&Durability>,
+        ) -> Result<(), RedsyncError> {
+            let actual = self
+                .stats
+                .lock()
+                .unwrap()
+                .get(&*String::from_utf8_lossy(&sequence_key(&lock.resource)))
+                .copied()
+                .unwrap_or_default() as u64;
+            if actual > version {
+                return Err(RedsyncError::VersionMismatch {
+                    expected: version,
+                    actual,
+                });
+            }
+
+            match durability {
+                Some(durability) => self.acquire_replicated(lock, durability),
+                None => self.acquire(lock),
+            }
+        }
+
+        fn reacquire(&self, _lock: &Lock) -> Result<(), RedsyncError> {
+            match (self.acquire, self.extend) {
+                (1, _) | (_, 1) => Ok(()),
+                _ => Err(RedsyncError::ResourceLocked),
+            }
+        }
+
+        fn extend(&self, _lock: &Lock) -> Result<(), RedsyncError> {
+            match self.extend {
+                1 => Ok(()),
+                _ => Err(RedsyncError::InvalidLease),
+            }
+        }
+
+        fn release(&self, _lock: &Lock) -> Result<(), RedsyncError> {
+            self.releases.fetch_add(1, Ordering::SeqCst);
+            match self.release {
+                1 => Ok(()),
+                2 => {
+                    thread::sleep(Duration::from_millis(500));
+                    Ok(())
+                }
+                _ => Err(RedsyncError::InvalidLease),
+            }
+        }
+
+        fn ttl(&self, _lock: &Lock) -> Result<Duration, RedsyncError> {
+            match self.ttl {
+                n if n >= 0 => Ok(Duration::from_millis(n as u64)),
+                _ => Err(RedsyncError::InvalidLease),
+            }
+        }
+
+        fn guarded_set(&self, _lock: &Lock, _key: &str, _value: &str) -> Result<(), RedsyncError> {
+            match self.extend {
+                1 => Ok(()),
+                _ => Err(RedsyncError::InvalidLease),
+            }
+        }
+
+        fn peek(&self, _resource: &[u8]) -> Result<Option<Duration>, RedsyncError> {
+            match (self.reachable, self.acquire) {
+                (false, _) => Err(RedsyncError::InjectedFault),
+                (true, 1) => Ok(None),
+                (true, _) => Ok(Some(Duration::from_millis(self.ttl as u64))),
+            }
+        }
+
+        fn ping(&self) -> Result<(), RedsyncError> {
+            match self.reachable {
+                true => Ok(()),
+                false => Err(RedsyncError::InjectedFault),
+            }
+        }
+
+        fn diagnostics(&self) -> Result<NodeDiagnostics, RedsyncError> {
+            match self.reachable {
+                true => Ok(NodeDiagnostics {
+                    maxmemory_policy: Some(String::from("noeviction")),
+                    ..Default::default()
+                }),
+                false => Err(RedsyncError::InjectedFault),
+            }
+        }
+
+        fn time(&self) -> Result<SystemTime, RedsyncError> {
+            match self.reachable {
+                true => Ok(SystemTime::now()),
+                false => Err(RedsyncError::InjectedFault),
+            }
+        }
+
+        fn force_release_prefix(
+            &self,
+            prefix: &str,
+            _dry_run: bool,
+            _bare_tokens: bool,
+        ) -> Result<Vec<String>, RedsyncError> {
+            match self.release {
+                1 => Ok(self
+                    .keys
+                    .iter()
+                    .filter(|key| key.starts_with(prefix))
+                    .cloned()
+                    .collect()),
+                _ => Err(RedsyncError::InvalidLease),
+            }
+        }
+
+        fn incr(&self, key: &str, delta: i64, _expiry: Duration) -> Result<i64, RedsyncError> {
+            let mut stats = self.stats.lock().unwrap();
+            let value = stats.entry(String::from(key)).or_default();
+            *value = (*value + delta).max(0);
+            Ok(*value)
+        }
+
+        fn incr_stats(&self, _resource: &[u8], fields: &[(&str, i64)]) -> Result<(), RedsyncError> {
+            let mut stats = self.stats.lock().unwrap();
+            for (field, value) in fields {
+                *stats.entry(String::from(*field)).or_default() += value;
+            }
+            Ok(())
+        }
+
+        fn read_stats(&self, _resource: &[u8]) -> Result<Vec<(String, i64)>, RedsyncError> {
+            Ok(self.stats.lock().unwrap().clone().into_iter().collect())
+        }
+
+        fn advance_sequence(
+            &self,
+            lock: &Lock,
+            floor: i64,
+            step: i64,
+        ) -> Result<i64, RedsyncError> {
+            let mut stats = self.stats.lock().unwrap();
+            let value = stats
+                .entry(String::from_utf8_lossy(&sequence_key(&lock.resource)).into_owned())
+                .or_default();
+            *value = (*value + step).max(floor);
+            Ok(*value)
+        }
+
+        fn append_audit(
+            &self,
+            fields: &[(&str, String)],
+            _retention: usize,
+        ) -> Result<(), RedsyncError> {
+            let mut audit = self.audit.lock().unwrap();
+            let id = format!("{}-0", audit.len() + 1);
+            let fields = fields
+                .iter()
+                .map(|(field, value)| (String::from(*field), value.clone()))
+                .collect();
+            audit.extend(AuditEvent::from_entry(id, fields));
+            Ok(())
+        }
+
+        fn read_audit(&self, count: usize) -> Result<Vec<AuditEvent>, RedsyncError> {
+            let audit = self.audit.lock().unwrap();
+            Ok(audit.iter().rev().take(count).cloned().collect())
+        }
+
+        fn reserve(&self, _reservation: &Reservation) -> Result<(), RedsyncError> {
+            match self.acquire {
+                1 => Ok(()),
+                _ => Err(RedsyncError::ResourceReserved),
+            }
+        }
+
+        fn cancel_reservation(&self, _reservation: &Reservation) -> Result<(), RedsyncError> {
+            self.releases.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn lock() {
+        let dlm = Redsync::new(vec![
+            FakeInstance::new(1, 1, 1),
+            FakeInstance::new(1, 1, 1),
+            FakeInstance::new(0, 1, 1),
+        ]);
+
+        let attempt = dlm.lock("test", Duration::from_secs(1));
+        assert!(attempt.is_ok());
+
+        let lock = attempt.unwrap();
+        assert_eq!(lock.resource, b"test");
+        assert!(!lock.value.is_empty());
+        assert_eq!(lock.ttl, Duration::from_secs(1));
+    }
+
+    #[test]
+    fn lock_error() {
+        let dlm = Redsync::new(vec![
+            FakeInstance::new(0, 1, 1),
+            FakeInstance::new(0, 1, 1),
+            FakeInstance::new(1, 1, 1),
+        ]);
+
+        let attempt = dlm.lock("test", Duration::from_secs(1));
+        assert!(matches!(
+            attempt,
+            Err(RedsyncError::LockRetriesExceeded { .. })
+        ));
+    }
+
+    #[test]
+    fn lock_default() -> Result<(), RedsyncError> {
+        let cluster = vec![FakeInstance::new(1, 1, 1)];
+        let dlm = RedsyncBuilder::new(cluster)
+            .default_ttl(Duration::from_secs(30))
+            .build();
+
+        let lock = dlm.lock_default("test")?;
+        assert_eq!(lock.resource, b"test");
+        assert_eq!(lock.ttl, Duration::from_secs(30));
+
+        Ok(())
+    }
+
+    #[test]
+    fn lock_invalid_ttl() {
+        let dlm = Redsync::new(vec![FakeInstance::new(1, 1, 1)]);
+
+        let attempt = dlm.lock("test", Duration::from_secs(0));
+        assert!(matches!(attempt, Err(RedsyncError::InvalidTtl { .. })));
+    }
+
+    #[test]
+    fn lock_error_display() {
+        let dlm = RedsyncBuilder::new(vec![
+            FakeInstance::new(0, 1, 1),
+            FakeInstance::new(0, 1, 1),
+            FakeInstance::new(1, 1, 1),
+            FakeInstance::new(1, 1, 1),
+            FakeInstance::new(3, 1, 1),
+        ])
+        .retry_count(1)
+        .build();
+
+        let e = match dlm.lock("test", Duration::from_secs(1)) {
+            Err(e) => e,
+            Ok(_) => panic!("expected lock to fail"),
+        };
+        assert_eq!(
+            e.to_string(),
+            "lock attempt failed: max retries exceeded: 3 errors occurred:\n\t * requested resource is current locked \u{d7}2 (nodes 0,1)\n\t * injected fault (nodes 4)"
+        );
+    }
+
+    #[test]
+    fn lock_failure_domains() {
+        let cluster = vec![
+            FakeInstance::new(1, 1, 1),
+            FakeInstance::new(1, 1, 1),
+            FakeInstance::new(1, 1, 1),
+            FakeInstance::new(1, 1, 1),
+            FakeInstance::new(0, 1, 1),
+            FakeInstance::new(0, 1, 1),
+        ];
+        let dlm = RedsyncBuilder::new(cluster)
+            .failure_domains(&["a", "a", "b", "b", "c", "c"], 2)
+            .build();
+
+        let attempt = dlm.lock("test", Duration::from_secs(1));
+        assert!(attempt.is_ok());
+    }
+
+    #[test]
+    fn lock_failure_domains_error() {
+        let cluster = vec![
+            FakeInstance::new(1, 1, 1),
+            FakeInstance::new(1, 1, 1),
+            FakeInstance::new(1, 1, 1),
+            FakeInstance::new(1, 1, 1),
+            FakeInstance::new(0, 1, 1),
+            FakeInstance::new(0, 1, 1),
+        ];
+        let dlm = RedsyncBuilder::new(cluster)
+            .retry_count(1)
+            .failure_domains(&["a", "a", "a", "a", "b", "c"], 2)
+            .build();
+
+        let attempt = dlm.lock("test", Duration::from_secs(1));
+        assert!(matches!(
+            attempt,
+            Err(RedsyncError::LockRetriesExceeded { .. })
+        ));
+    }
+
+    #[test]
+    fn lock_attempts() -> Result<(), RedsyncError> {
+        let dlm = Redsync::new(vec![
+            FakeInstance::new(1, 1, 1),
+            FakeInstance::new(1, 1, 1),
+            FakeInstance::new(0, 1, 1),
+        ]);
+
+        let mut attempts = dlm.lock_attempts("test", Duration::from_secs(1))?;
+        match attempts.next() {
+            Some(AttemptOutcome::Acquired(lock)) => assert_eq!(lock.resource, b"test"),
+            _ => panic!("expected lock to be acquired"),
+        }
+        assert_eq!(attempts.attempts(), 1);
+        assert!(attempts.next().is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn lock_attempts_error() -> Result<(), RedsyncError> {
+        let dlm = Redsync::new(vec![
+            FakeInstance::new(0, 1, 1),
+            FakeInstance::new(0, 1, 1),
+            FakeInstance::new(1, 1, 1),
+        ]);
+
+        let mut attempts = dlm.lock_attempts("test", Duration::from_secs(1))?;
+        for _ in 0..5 {
+            match attempts.next_attempt() {
+                AttemptOutcome::Failed(errors) => assert!(errors.all_resource_locked()),
+                _ => panic!("expected lock attempt to fail"),
+            }
+        }
+        assert_eq!(attempts.attempts(), 5);
+
+        let dlm = RedsyncBuilder::new(vec![
+            FakeInstance::new(0, 1, 1),
+            FakeInstance::new(0, 1, 1),
+            FakeInstance::new(1, 1, 1),
+        ])
+        .error_limit(1)
+        .build();
+
+        let mut attempts = dlm.lock_attempts("test", Duration::from_secs(1))?;
+        match attempts.next_attempt() {
+            AttemptOutcome::Failed(errors) => {
+                assert_eq!(errors.len(), 1);
+                assert_eq!(errors.truncated(), 1);
+            }
+            _ => panic!("expected lock attempt to fail"),
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn lock_attempts_checks() -> Result<(), RedsyncError> {
+        let dlm = RedsyncBuilder::new(vec![FakeInstance::new(1, 1, 1)])
+            .quota("tenant:", Quota::new().max_held(1))
+            .audit_log("worker-1", 100)
+            .build();
+
+        let mut attempts = dlm.lock_attempts("tenant:a", Duration::from_secs(1))?;
+        dlm.pause();
+        match attempts.next_attempt() {
+            AttemptOutcome::Failed(errors) => {
+                assert_eq!(*errors, vec![RedsyncError::ManagerPaused]);
+            }
+            _ => panic!("expected lock attempt to fail"),
+        }
+        dlm.resume();
+
+        let _lock = dlm.lock("tenant:b", Duration::from_secs(1))?;
+        match attempts.next_attempt() {
+            AttemptOutcome::Failed(errors) => assert_eq!(
+                *errors,
+                vec![RedsyncError::QuotaExceeded(String::from("tenant:"))]
+            ),
+            _ => panic!("expected lock attempt to fail"),
+        }
+
+        let events = dlm.audit_tail(10)?;
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].resource, "tenant:b");
+
+        Ok(())
+    }
+
+    #[test]
+    fn can_lock() -> Result<(), RedsyncError> {
+        let dlm = Redsync::new(vec![
+            FakeInstance::new(1, 1, 1),
+            FakeInstance::new(0, 1, 1).ttl(300),
+            FakeInstance::new(0, 1, 1).ttl(200),
+        ]);
+
+        let preview = dlm.can_lock("test")?;
+        assert!(!preview.available);
+        assert_eq!(preview.free_in, Duration::from_millis(200));
+
+        Ok(())
+    }
+
+    #[test]
+    fn can_lock_error() {
+        let dlm = Redsync::new(vec![
+            FakeInstance::new(0, 1, 1),
+            FakeInstance::new(1, 1, 1).unreachable(),
+            FakeInstance::new(1, 1, 1).unreachable(),
+        ]);
+
+        let attempt = dlm.can_lock("test");
+        assert!(matches!(attempt, Err(RedsyncError::PreviewFailed(_))));
+    }
+
+    #[test]
+    fn lock_policy() -> Result<(), RedsyncError> {
+        let dlm = RedsyncBuilder::new(vec![FakeInstance::new(1, 1, 1)])
+            .policy(
+                |resource: &str, _ttl: Duration| match resource.starts_with("banned") {
+                    true => Err(PolicyViolation::new("banned resource")),
+                    false => Ok(()),
+                },
+            )
+            .build();
+
+        dlm.lock("test", Duration::from_secs(1))?;
+
+        let attempt = dlm.lock("banned", Duration::from_secs(1));
+        assert!(matches!(
+            attempt,
+            Err(RedsyncError::PolicyViolation(violation)) if violation.reason == "banned resource"
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn lock_short_circuit_impossible() {
+        let dlm = RedsyncBuilder::new(vec![
+            FakeInstance::new(0, 1, 1),
+            FakeInstance::new(0, 1, 1),
+            FakeInstance::new(1, 1, 1),
+        ])
+        .retry_count(1)
+        .short_circuit(ShortCircuit::Impossible)
+        .build();
+
+        let attempt = dlm.lock("test", Duration::from_secs(1));
+        assert!(attempt.is_err());
+        assert_eq!(dlm.cluster[2].calls.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn lock_short_circuit_decided() -> Result<(), RedsyncError> {
+        let dlm = RedsyncBuilder::new(vec![
+            FakeInstance::new(1, 1, 1),
+            FakeInstance::new(1, 1, 1),
+            FakeInstance::new(1, 1, 1),
+        ])
+        .short_circuit(ShortCircuit::Decided)
+        .build();
+
+        dlm.lock("test", Duration::from_secs(1))?;
+        assert_eq!(dlm.cluster[2].calls.load(Ordering::SeqCst), 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn lock_quota_held() -> Result<(), RedsyncError> {
+        let dlm = RedsyncBuilder::new(vec![FakeInstance::new(1, 1, 1)])
+            .quota("tenant:", Quota::new().max_held(1))
+            .build();
+
+        let lock = dlm.lock("tenant:a", Duration::from_secs(1))?;
+        let attempt = dlm.lock("tenant:b", Duration::from_secs(1));
+        assert!(matches!(attempt, Err(RedsyncError::QuotaExceeded(_))));
+        dlm.lock("other", Duration::from_secs(1))?;
+
+        dlm.unlock(&lock)?;
+        dlm.lock("tenant:b", Duration::from_secs(1))?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn lock_quota_released_once() -> Result<(), RedsyncError> {
+        let dlm = RedsyncBuilder::new(vec![FakeInstance::new(1, 1, 1)])
+            .quota("tenant:", Quota::new().max_held(2))
+            .build();
+        let held = || dlm.cluster[0].stats.lock().unwrap()[&held_key("tenant:")];
+
+        let lock = dlm.lock("tenant:a", Duration::from_secs(1))?;
+        assert!(lock.quota_slot.load(Ordering::SeqCst));
+        assert_eq!(held(), 1);
+
+        let extended = dlm.extend(&lock, Duration::from_secs(1))?;
+        assert!(!lock.quota_slot.load(Ordering::SeqCst));
+        assert!(extended.quota_slot.load(Ordering::SeqCst));
+
+        dlm.unlock(&extended)?;
+        assert_eq!(held(), 0);
+        let _ = dlm.unlock(&extended);
+        let _ = dlm.unlock(&lock);
+        assert_eq!(held(), 0);
+
+        let lock = dlm.reacquire_if_owner("tenant:b", "token", Duration::from_secs(1))?;
+        assert!(!lock.quota_slot.load(Ordering::SeqCst));
+        dlm.unlock(&lock)?;
+        assert_eq!(held(), 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn lock_quota_pinned() -> Result<(), RedsyncError> {
+        let dlm = RedsyncBuilder::new(vec![
+            FakeInstance::new(1, 1, 1),
+            FakeInstance::new(1, 1, 1),
+            FakeInstance::new(1, 1, 1),
+        ])
+        .quota("tenant:", Quota::new().max_held(1))
+        .build();
+        let key = held_key("tenant:");
+        let pinned = (fnv1a(key.as_bytes()) % 3) as usize;
+
+        let lock = dlm.lock("tenant:a", Duration::from_secs(1))?;
+        for (i, instance) in dlm.cluster.iter().enumerate() {
+            let counted = instance.stats.lock().unwrap().get(&key).copied();
+            assert_eq!(counted, if i == pinned { Some(1) } else { None });
+        }
+
+        dlm.unlock(&lock)?;
+        assert_eq!(dlm.cluster[pinned].stats.lock().unwrap()[&key], 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn lock_quota_rate() -> Result<(), RedsyncError> {
+        let dlm = RedsyncBuilder::new(vec![FakeInstance::new(1, 1, 1)])
+            .quota("tenant:", Quota::new().max_rate(2, Duration::from_secs(1)))
+            .build();
+
+        dlm.lock("tenant:a", Duration::from_secs(1))?;
+        dlm.lock("tenant:b", Duration::from_secs(1))?;
+        let attempt = dlm.lock("tenant:c", Duration::from_secs(1));
+        assert!(matches!(attempt, Err(RedsyncError::QuotaExceeded(_))));
+
+        Ok(())
+    }
+
+    #[test]
+    fn contention_report() {
+        let dlm = Arc::new(
+            RedsyncBuilder::new(vec![FakeInstance::new(0, 1, 1)])
+                .retry_count(3)
+                .retry_delay(Duration::from_millis(100))
+                .build(),
+        );
+
+        let handle = {
+            let dlm = dlm.clone();
+            thread::spawn(move || dlm.lock("test", Duration::from_secs(1)).is_ok())
+        };
+
+        thread::sleep(Duration::from_millis(50));
+        let report = dlm.contention_report();
+        assert_eq!(report.len(), 1);
+        assert_eq!(report[0].resource, b"test");
+        assert_eq!(report[0].waiters, 1);
+
+        assert!(!handle.join().unwrap());
+        assert!(dlm.contention_report().is_empty());
+    }
+
+    #[test]
+    fn starvation_monitor() {
+        let starved = Arc::new(Mutex::new(Vec::new()));
+        let hook = {
+            let starved = Arc::clone(&starved);
+            move |starvation: &Starvation| starved.lock().unwrap().push(starvation.clone())
+        };
+        let dlm = RedsyncBuilder::new(vec![
+            FakeInstance::new(0, 1, 1),
+            FakeInstance::new(0, 1, 1),
+            FakeInstance::new(0, 1, 1),
+        ])
+        .sleep_fn(|_| {})
+        .starvation_monitor(Duration::ZERO, hook)
+        .build();
+
+        let attempt = dlm.lock("starvation_monitor", Duration::from_secs(1));
+        assert!(attempt.is_err());
+
+        let starved = starved.lock().unwrap();
+        assert_eq!(starved.len(), 1);
+        assert_eq!(starved[0].resource, b"starvation_monitor");
+        assert_eq!(starved[0].attempts, 1);
+        assert_eq!(starved[0].waiters, 1);
+    }
+
+    #[test]
+    fn extend() -> Result<(), RedsyncError> {
+        let dlm = Redsync::new(vec![
+            FakeInstance::new(1, 1, 1),
+            FakeInstance::new(1, 1, 1),
+            FakeInstance::new(1, 0, 1),
+        ]);
+        let lock = dlm.lock("test", Duration::from_secs(1))?;
+
+        let attempt = dlm.extend(&lock, Duration::from_secs(2));
+        assert!(attempt.is_ok());
+
+        let lock = attempt.unwrap();
+        assert_eq!(lock.resource, b"test");
+        assert!(!lock.value.is_empty());
+        assert_eq!(lock.ttl, Duration::from_secs(2));
+
+        Ok(())
+    }
+
+    #[test]
+    fn extend_error() -> Result<(), RedsyncError> {
+        let dlm = Redsync::new(vec![
+            FakeInstance::new(1, 0, 1),
+            FakeInstance::new(1, 0, 1),
+            FakeInstance::new(1, 1, 1),
+        ]);
+        let lock = dlm.lock("test", Duration::from_secs(1))?;
+
+        let attempt = dlm.extend(&lock, Duration::from_secs(2));
+        assert!(matches!(
+            attempt,
+            Err(RedsyncError::ExtendRetriesExceeded { .. })
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn extend_retries() -> Result<(), RedsyncError> {
+        let cluster = || {
+            vec![
+                FakeInstance::new(1, 0, 1),
+                FakeInstance::new(1, 0, 1),
+                FakeInstance::new(1, 1, 1),
+            ]
+        };
+        let dlm = RedsyncBuilder::new(cluster())
+            .retry_delay(Duration::from_secs(1))
+            .build();
+        let lock = dlm.lock("test", Duration::from_secs(2))?;
+
+        let start = Instant::now();
+        assert!(dlm.extend(&lock, Duration::from_secs(2)).is_err());
+        assert!(start.elapsed() < Duration::from_millis(500));
+
+        let dlm = RedsyncBuilder::new(cluster())
+            .extend_retry_count(3)
+            .extend_retry_delay(Duration::from_millis(100))
+            .build();
+        let lock = dlm.lock("test", Duration::from_secs(2))?;
+
+        let start = Instant::now();
+        assert!(dlm.extend(&lock, Duration::from_secs(2)).is_err());
+        assert!(start.elapsed() >= Duration::from_millis(200));
+
+        Ok(())
+    }
+
+    #[test]
+    fn sleep_fn() {
+        let sleeps = Arc::new(Mutex::new(Vec::new()));
+        let recorded = sleeps.clone();
+        let dlm = RedsyncBuilder::new(vec![FakeInstance::new(0, 1, 1)])
+            .retry_count(3)
+            .retry_delay(Duration::from_secs(60))
+            .sleep_fn(move |delay| recorded.lock().unwrap().push(delay))
+            .build();
+
+        let start = Instant::now();
+        assert!(dlm.lock("test", Duration::from_secs(1)).is_err());
+        assert!(start.elapsed() < Duration::from_secs(1));
+        let sleeps = sleeps.lock().unwrap();
+        assert_eq!(sleeps.len(), 2);
+        assert!(sleeps.iter().all(|&delay| delay >= Duration::from_secs(30)));
+    }
+
+    #[test]
+    fn extend_many() -> Result<(), RedsyncError> {
+        let dlm = Redsync::new(vec![
+            FakeInstance::new(1, 1, 1),
+            FakeInstance::new(1, 1, 1),
+            FakeInstance::new(1, 0, 1),
+        ]);
+        let a = dlm.lock("extend_many_a", Duration::from_secs(1))?;
+        let b = dlm.lock("extend_many_b", Duration::from_secs(1))?;
+
+        let results = dlm.extend_many(&[&a, &b], Duration::from_secs(2))?;
+        assert_eq!(results.len(), 2);
+        for (result, lock) in results.iter().zip(&[&a, &b]) {
+            let extended = result.as_ref().unwrap();
+            assert_eq!(extended.resource, lock.resource);
+            assert_eq!(extended.ttl, Duration::from_secs(2));
+            assert_eq!(extended.lost_instances, [2]);
+        }
+
+        let dlm = Redsync::new(vec![
+            FakeInstance::new(1, 0, 1),
+            FakeInstance::new(1, 0, 1),
+            FakeInstance::new(1, 1, 1),
+        ]);
+        let c = dlm.lock("extend_many_c", Duration::from_secs(1))?;
+        let results = dlm.extend_many(&[&c], Duration::from_secs(2))?;
+        assert!(matches!(
+            results[0],
+            Err(RedsyncError::ExtendRetriesExceeded { .. })
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn reacquire_if_owner() -> Result<(), RedsyncError> {
+        let dlm = Redsync::new(vec![
+            FakeInstance::new(0, 1, 1),
+            FakeInstance::new(0, 1, 1),
+            FakeInstance::new(1, 0, 1),
+        ]);
+        let lock = dlm.reacquire_if_owner("test", "token", Duration::from_secs(1))?;
+        assert_eq!(lock.value, b"token");
+
+        let dlm = Redsync::new(vec![
+            FakeInstance::new(0, 0, 1),
+            FakeInstance::new(0, 0, 1),
+            FakeInstance::new(1, 0, 1),
+        ]);
+        let attempt = dlm.reacquire_if_owner("test", "token", Duration::from_secs(1));
+        assert!(matches!(
+            attempt,
+            Err(RedsyncError::LockRetriesExceeded { .. })
+        ));
+        // The lease the caller may still hold is not released by the failed attempts
+        let releases: Vec<_> = dlm
+            .cluster
+            .iter()
+            .map(|instance| instance.releases.load(Ordering::SeqCst))
+            .collect();
+        assert_eq!(releases, [0, 0, 0]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn singleton_job() -> Result<(), RedsyncError> {
+        let dlm = Redsync::new(vec![FakeInstance::new(1, 1, 1)]);
+        let missed = Arc::new(Mutex::new(Vec::new()));
+        let recorded = missed.clone();
+        let mut job = SingletonJob::new(&dlm, "report", Duration::from_millis(20))
+            .on_missed(move |name, windows| recorded.lock().unwrap().push((name.into(), windows)));
+
+        assert_eq!(job.run(|| 42)?, JobOutcome::Ran(42));
+        let first = job.window(SystemTime::now());
+        assert!(job.resource(first).starts_with("job:report:"));
+        assert!(missed.lock().unwrap().is_empty());
+
+        thread::sleep(Duration::from_millis(70));
+        job.run(|| ())?;
+        let missed: Vec<(String, Range<u64>)> = missed.lock().unwrap().drain(..).collect();
+        assert_eq!(missed.len(), 1);
+        assert_eq!(missed[0].0, "report");
+        let Range { start, end } = missed[0].1;
+        assert!(start <= first + 1 && end - start >= 2);
+
+        let dlm = Redsync::new(vec![FakeInstance::new(0, 1, 1)]);
+        let mut job = SingletonJob::new(&dlm, "report", Duration::from_secs(60));
+        assert_eq!(job.run(|| 42)?, JobOutcome::Skipped);
+
+        Ok(())
+    }
+
+    #[test]
+    fn sharded() -> Result<(), RedsyncError> {
+        let shards = |names: &[&'static str]| {
+            ShardedRedsync::new(
+                names
+                    .iter()
+                    .map(|&name| (name, Redsync::new(vec![FakeInstance::new(1, 1, 1)])))
+                    .collect(),
+            )
+        };
+        let dlm = shards(&["a", "b", "c"]);
+
+        let resources: Vec<String> = (0..300).map(|i| format!("sharded_{}", i)).collect();
+        let routes: Vec<&str> = resources.iter().map(|r| dlm.shard_name(r)).collect();
+        for name in ["a", "b", "c"] {
+            let count = routes.iter().filter(|&&route| route == name).count();
+            assert!(
+                count > 50,
+                "shard {} only received {} resources",
+                name,
+                count
+            );
+        }
+
+        // Adding a shard only moves the resources it takes over
+        let grown = shards(&["a", "b", "c", "d"]);
+        let moved = resources
+            .iter()
+            .zip(&routes)
+            .filter(|(r, &route)| grown.shard_name(r) != route)
+            .inspect(|(r, _)| assert_eq!(grown.shard_name(r), "d"))
+            .count();
+        assert!(moved > 0 && moved < 150);
+
+        let lock = dlm.lock("sharded_0", Duration::from_secs(1))?;
+        let lock = dlm.extend(&lock, Duration::from_secs(1))?;
+        dlm.unlock(&lock)?;
+        let shard = dlm.shard("sharded_0");
+        assert_eq!(shard.cluster[0].releases.load(Ordering::SeqCst), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn lock_stripe() -> Result<(), RedsyncError> {
+        let dlm = Redsync::new(vec![FakeInstance::new(1, 1, 1)]);
+        let stripe = LockStripe::new(&dlm, "accounts", 8);
+
+        let stripes: Vec<usize> = (0..64).map(|id| stripe.stripe(id.to_string())).collect();
+        assert!(stripes.iter().all(|&i| i < 8));
+        assert!(stripes.iter().any(|&i| i != stripes[0]));
+
+        let lock = stripe.lock_for("42", Duration::from_secs(1))?;
+        let expected = format!("accounts:stripe:{}", stripe.stripe("42"));
+        assert_eq!(lock.resource, expected.as_bytes());
+
+        Ok(())
+    }
+
+    #[test]
+    fn lock_scoped() -> Result<(), RedsyncError> {
+        let dlm = Redsync::new(vec![
+            FakeInstance::new(1, 1, 1),
+            FakeInstance::new(1, 1, 1),
+            FakeInstance::new(1, 1, 1),
+        ]);
+
+        let guard = dlm.lock_scoped("lock_scoped_a", Duration::from_secs(1))?;
+        assert_eq!(guard.lock().resource, b"lock_scoped_a");
+        guard.unlock()?;
+
+        let attempt = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            let _guard = dlm
+                .lock_scoped("lock_scoped_b", Duration::from_secs(1))
+                .unwrap();
+            panic!("critical section panicked");
+        }));
+        assert!(attempt.is_err());
+        for instance in &dlm.cluster {
+            assert_eq!(instance.releases.load(Ordering::SeqCst), 2);
+        }
+
+        let releases = take_panic_releases();
+        let release = releases
+            .iter()
+            .find(|release| release.resource == b"lock_scoped_b")
+            .unwrap();
+        assert!(release.result.is_ok());
+
+        Ok(())
+    }
+
+    #[test]
+    fn scope() -> Result<(), RedsyncError> {
+        let dlm = Redsync::new(vec![
+            FakeInstance::new(1, 1, 1),
+            FakeInstance::new(1, 1, 1),
+            FakeInstance::new(1, 1, 1),
+        ]);
+
+        let resources = dlm.scope(|s| {
+            let a = s.lock("scope_a", Duration::from_secs(1))?;
+            let b = s.lock(b"scope_\xff", Duration::from_secs(1))?;
+            s.extend(b, Duration::from_secs(2))?;
+            s.unlock(a)?;
+            assert!(s.get(a).is_none());
+            Ok::<_, RedsyncError>(s.get(b).map(|lock| lock.ttl))
+        })?;
+        assert_eq!(resources, Some(Duration::from_secs(2)));
+        for instance in &dlm.cluster {
+            assert_eq!(instance.releases.load(Ordering::SeqCst), 2);
+        }
+
+        let attempt = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            dlm.scope(|s| {
+                s.lock("scope_c", Duration::from_secs(1)).unwrap();
+                panic!("critical section panicked");
+            })
+        }));
+        assert!(attempt.is_err());
+        for instance in &dlm.cluster {
+            assert_eq!(instance.releases.load(Ordering::SeqCst), 3);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn guarded_set() -> Result<(), RedsyncError> {
+        let dlm = Redsync::new(vec![
+            FakeInstance::new(1, 1, 1),
+            FakeInstance::new(1, 1, 1),
+            FakeInstance::new(1, 0, 1),
+        ]);
+        let lock = dlm.lock("test", Duration::from_secs(1))?;
+
+        let attempt = dlm.guarded_set(&lock, "data", "value");
+        assert!(attempt.is_ok());
+
+        Ok(())
+    }
+
+    #[test]
+    fn guarded_set_error() -> Result<(), RedsyncError> {
+        let dlm = Redsync::new(vec![
+            FakeInstance::new(1, 0, 1),
+            FakeInstance::new(1, 0, 1),
+            FakeInstance::new(1, 1, 1),
+        ]);
+        let lock = dlm.lock("test", Duration::from_secs(1))?;
+
+        let attempt = dlm.guarded_set(&lock, "data", "value");
+        assert!(matches!(
+            attempt,
+            Err(RedsyncError::GuardedWriteFailed { .. })
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn extend_until() -> Result<(), RedsyncError> {
+        let sleeps = Arc::new(AtomicU32::new(0));
+        let counter = sleeps.clone();
+        let dlm = RedsyncBuilder::new(vec![
+            FakeInstance::new(1, 0, 1),
+            FakeInstance::new(1, 0, 1),
+            FakeInstance::new(1, 0, 1),
+        ])
+        .extend_retry_count(10)
+        .extend_retry_delay(Duration::from_millis(100))
+        .sleep_fn(move |delay| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            thread::sleep(delay);
+        })
+        .build();
+        let lock = dlm.lock("test", Duration::from_secs(1))?;
+
+        // Attempts start at 0ms, 100ms and 200ms; a fourth would start after the deadline
+        let deadline = Instant::now() + Duration::from_millis(250);
+        let attempt = dlm.extend_until(&lock, Duration::from_secs(1), deadline);
+        assert!(matches!(
+            attempt,
+            Err(RedsyncError::ExtendRetriesExceeded(_))
+        ));
+        assert_eq!(sleeps.load(Ordering::SeqCst), 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn unlock() -> Result<(), RedsyncError> {
+        let dlm = Redsync::new(vec![
+            FakeInstance::new(1, 1, 1),
+            FakeInstance::new(1, 1, 1),
+            FakeInstance::new(1, 1, 0),
+        ]);
+        let lock = dlm.lock("test", Duration::from_secs(1))?;
+
+        let attempt = dlm.unlock(&lock);
+        assert!(attempt.is_ok());
+
+        Ok(())
+    }
+
+    #[test]
+    fn unlock_owned() -> Result<(), RedsyncError> {
+        let dlm = Redsync::new(vec![
+            FakeInstance::new(1, 1, 1),
+            FakeInstance::new(1, 1, 1),
+            FakeInstance::new(1, 1, 0),
+        ]);
+        let lock = dlm.lock("test", Duration::from_secs(1))?;
+
+        dlm.unlock_owned(lock)?;
+        for instance in &dlm.cluster {
+            assert_eq!(instance.releases.load(Ordering::SeqCst), 1);
+        }
+
+        Ok(())
+    }
+
+    struct MinimalInstance;
+
+    impl Instance for MinimalInstance {
+        fn acquire(&self, _lock: &Lock) -> Result<(), RedsyncError> {
+            Ok(())
+        }
+
+        fn extend(&self, _lock: &Lock) -> Result<(), RedsyncError> {
+            Ok(())
+        }
+
+        fn release(&self, _lock: &Lock) -> Result<(), RedsyncError> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn minimal_instance() -> Result<(), RedsyncError> {
+        let dlm = Redsync::new(vec![MinimalInstance, MinimalInstance, MinimalInstance]);
+        let lock = dlm.lock("test", Duration::from_secs(1))?;
+        dlm.extend(&lock, Duration::from_secs(1))?;
+        dlm.unlock(&lock)?;
+
+        assert_eq!(
+            dlm.cluster[0].ping(),
+            Err(RedsyncError::Unsupported("ping"))
+        );
+        assert_eq!(RedsyncError::Unsupported("ping").kind(), ErrorKind::Config);
+
+        Ok(())
+    }
+
+    struct PipelinedInstance {
+        index: usize,
+        granted: bool,
+        events: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl Instance for PipelinedInstance {
+        fn acquire(&self, _lock: &Lock) -> Result<(), RedsyncError> {
+            panic!("expected acquisition to be pipelined")
+        }
+
+        fn send_acquire(&self, _lock: &Lock) -> PendingCall<'_> {
+            self.events
+                .lock()
+                .unwrap()
+                .push(format!("send {}", self.index));
+            PendingCall::waiting(move |_| {
+                self.events
+                    .lock()
+                    .unwrap()
+                    .push(format!("wait {}", self.index));
+                match self.granted {
+                    true => Ok(()),
+                    false => Err(RedsyncError::ResourceLocked),
+                }
+            })
+        }
+
+        fn extend(&self, _lock: &Lock) -> Result<(), RedsyncError> {
+            panic!("expected extension to be pipelined")
+        }
+
+        fn send_extend(&self, _lock: &Lock) -> PendingCall<'_> {
+            self.events
+                .lock()
+                .unwrap()
+                .push(format!("extend {}", self.index));
+            PendingCall::done(Ok(()))
+        }
+
+        fn release(&self, _lock: &Lock) -> Result<(), RedsyncError> {
+            panic!("expected release to be pipelined")
+        }
+
+        fn send_release(&self, _lock: &Lock) -> PendingCall<'_> {
+            self.events
+                .lock()
+                .unwrap()
+                .push(format!("release {}", self.index));
+            PendingCall::waiting(move |_| Ok(()))
+        }
+    }
+
+    #[test]
+    fn pipelined() -> Result<(), RedsyncError> {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let cluster = (0..3)
+            .map(|index| PipelinedInstance {
+                index,
+                granted: index == 2,
+                events: events.clone(),
+            })
+            .collect();
+        let dlm = RedsyncBuilder::new(cluster)
+            .pipelined(true)
+            .short_circuit(ShortCircuit::Impossible)
+            .retry_count(1)
+            .build();
+
+        let attempt = dlm.lock("test", Duration::from_secs(1));
+        assert!(matches!(
+            attempt,
+            Err(RedsyncError::LockRetriesExceeded { .. })
+        ));
+        assert_eq!(
+            *events.lock().unwrap(),
+            [
+                "send 0",
+                "send 1",
+                "send 2",
+                "wait 0",
+                "wait 1",
+                "wait 2",
+                "release 2"
+            ]
+        );
+
+        let lock = Lock::pending(
+            String::from("test"),
+            String::from("1"),
+            Duration::from_secs(1),
+            dlm.id,
+        );
+        events.lock().unwrap().clear();
+        let lock = dlm.extend(&lock, Duration::from_secs(1))?;
+        dlm.unlock(&lock)?;
+        assert_eq!(
+            *events.lock().unwrap(),
+            [
+                "extend 0",
+                "extend 1",
+                "extend 2",
+                "release 0",
+                "release 1",
+                "release 2"
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn accessors() {
+        let dlm = RedsyncBuilder::new(vec![
+            FakeInstance::new(1, 1, 1),
+            FakeInstance::new(1, 1, 1),
+            FakeInstance::new(1, 1, 1),
+            FakeInstance::new(1, 1, 1),
+            FakeInstance::new(1, 1, 1),
+        ])
+        .retry_count(7)
+        .build();
+
+        assert_eq!(dlm.quorum(), 3);
+        assert_eq!(dlm.cluster_size(), 5);
+
+        let config = dlm.config();
+        assert_eq!(config.quorum, dlm.quorum());
+        assert_eq!(config.cluster_size, dlm.cluster_size());
+        assert_eq!(config.retry_count, 7);
+    }
+
+    #[test]
+    fn config_snapshot() {
+        let dlm = RedsyncBuilder::new(vec![
+            FakeInstance::new(0, 1, 1),
+            FakeInstance::new(0, 1, 1),
+            FakeInstance::new(1, 1, 1),
+        ])
+        .retry_count(1)
+        .config_snapshot(true)
+        .build();
+
+        match dlm.lock("config_snapshot", Duration::from_secs(1)) {
+            Err(RedsyncError::LockRetriesExceeded(errors)) => {
+                assert_eq!(errors.config(), Some(&dlm.config()));
+                assert!(errors
+                    .to_string()
+                    .contains("\n\tconfig: quorum=2/3 retry_count=1"));
+            }
+            _ => panic!("expected LockRetriesExceeded error"),
+        }
+
+        let dlm = RedsyncBuilder::new(vec![FakeInstance::new(0, 1, 1)])
+            .retry_count(1)
+            .build();
+        match dlm.lock("config_snapshot", Duration::from_secs(1)) {
+            Err(RedsyncError::LockRetriesExceeded(errors)) => assert!(errors.config().is_none()),
+            _ => panic!("expected LockRetriesExceeded error"),
+        }
+    }
+
+    #[test]
+    fn unlock_error() -> Result<(), RedsyncError> {
+        let dlm = Redsync::new(vec![
+            FakeInstance::new(1, 1, 0),
+            FakeInstance::new(1, 1, 0),
+            FakeInstance::new(1, 1, 1),
+        ]);
+        let lock = dlm.lock("test", Duration::from_secs(1))?;
+
+        let attempt = dlm.unlock(&lock);
+        assert!(matches!(attempt, Err(RedsyncError::UnlockFailed { .. })));
+
+        Ok(())
+    }
+
+    #[test]
+    fn release_async() -> Result<(), RedsyncError> {
+        let dlm = Arc::new(Redsync::new(vec![
+            FakeInstance::new(1, 1, 1),
+            FakeInstance::new(1, 1, 1),
+            FakeInstance::new(1, 1, 1),
+        ]));
+        let lock = dlm.lock("test", Duration::from_secs(1))?;
+
+        let handle = dlm.release_async(lock)?;
+        assert_eq!(handle.thread().name(), Some("redsync-release"));
+        assert!(handle.join().unwrap().is_ok());
+        for instance in &dlm.cluster {
+            assert_eq!(instance.releases.load(Ordering::SeqCst), 1);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn watchdog() -> Result<(), RedsyncError> {
+        let dlm = Arc::new(Redsync::new(vec![FakeInstance::new(1, 1, 1)]));
+        let lock = dlm.lock("test", Duration::from_millis(100))?;
+        let expiry = lock.expiry;
+
+        let schedule = KeepaliveSchedule::new(0.5).jitter(0.0);
+        let watchdog = dlm.watchdog(lock, schedule)?;
+        thread::sleep(Duration::from_millis(250));
+        assert!(watchdog.is_running());
+
+        let lock = watchdog.stop()?;
+        assert!(lock.expiry > expiry);
+
+        // A failed renewal stops the watchdog with its error
+        let dlm = Arc::new(Redsync::new(vec![FakeInstance::new(1, 0, 1)]));
+        let lock = dlm.lock("test", Duration::from_millis(100))?;
+        let watchdog = dlm.watchdog(lock, schedule)?;
+        thread::sleep(Duration::from_millis(150));
+        assert!(!watchdog.is_running());
+        assert!(matches!(
+            watchdog.stop(),
+            Err(RedsyncError::ExtendRetriesExceeded { .. })
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn unlock_within() -> Result<(), RedsyncError> {
+        let dlm = Arc::new(Redsync::new(vec![
+            FakeInstance::new(1, 1, 1),
+            FakeInstance::new(1, 1, 1),
+            FakeInstance::new(1, 1, 2),
+        ]));
+        let lock = dlm.lock("test", Duration::from_secs(1))?;
+
+        let start = Instant::now();
+        assert!(dlm.unlock_within(&lock, Duration::from_millis(100)).is_ok());
+        assert!(start.elapsed() < Duration::from_millis(500));
+
+        let dlm = Arc::new(Redsync::new(vec![
+            FakeInstance::new(1, 1, 1),
+            FakeInstance::new(1, 1, 2),
+            FakeInstance::new(1, 1, 2),
+        ]));
+        let lock = dlm.lock("test", Duration::from_secs(1))?;
+
+        let start = Instant::now();
+        match dlm.unlock_within(&lock, Duration::from_millis(100)) {
+            Err(RedsyncError::UnlockFailed(errors)) => {
+                assert_eq!(errors.len(), 2);
+                assert!(errors
+                    .iter()
+                    .all(|e| matches!(e, RedsyncError::ReleaseTimedOut)));
+            }
+            _ => panic!("expected UnlockFailed"),
+        }
+        assert!(start.elapsed() < Duration::from_millis(500));
+
+        Ok(())
+    }
+
+    #[test]
+    fn foreign_lock() -> Result<(), RedsyncError> {
+        let dlm = Redsync::new(vec![FakeInstance::new(1, 1, 1)]);
+        let other = Redsync::new(vec![FakeInstance::new(1, 1, 1)]);
+        assert_ne!(dlm.id(), other.id());
+
+        let lock = dlm.lock("test", Duration::from_secs(1))?;
+        assert_eq!(lock.manager, dlm.id());
+
+        let id = dlm.id();
+        assert_eq!(other.unlock(&lock), Err(RedsyncError::ForeignLock(id)));
+        assert!(matches!(
+            other.extend(&lock, Duration::from_secs(1)),
+            Err(RedsyncError::ForeignLock(_))
+        ));
+        assert_eq!(other.cluster[0].releases.load(Ordering::SeqCst), 0);
+
+        let lock = other.adopt(lock);
+        let lock = other.extend(&lock, Duration::from_secs(1))?;
+        assert_eq!(lock.manager, other.id());
+        other.unlock(&lock)?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn unlock_expired() -> Result<(), RedsyncError> {
+        let dlm = Redsync::new(vec![
+            FakeInstance::new(1, 1, 0),
+            FakeInstance::new(1, 1, 0),
+            FakeInstance::new(1, 1, 0),
+        ]);
+        let lock = dlm.lock("test", Duration::from_secs(1))?;
+
+        let attempt = dlm.unlock(&lock);
+        assert!(matches!(attempt, Err(RedsyncError::LockAlreadyExpired)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn unlock_report() -> Result<(), RedsyncError> {
+        let dlm = Redsync::new(vec![
+            FakeInstance::new(1, 1, 1),
+            FakeInstance::new(1, 1, 0),
+            FakeInstance::new(1, 1, 0),
+        ]);
+        let lock = dlm.lock("test", Duration::from_secs(1))?;
+
+        let report = dlm.unlock_report(&lock);
+        assert_eq!(report.released, 1);
+        assert_eq!(report.expired, 2);
+        assert!(report.errors.is_empty());
+        assert!(!report.is_expired());
+
+        Ok(())
+    }
+
+    #[test]
+    fn lock_resource_rules() -> Result<(), RedsyncError> {
+        let dlm = RedsyncBuilder::new(vec![
+            FakeInstance::new(1, 1, 1),
+            FakeInstance::new(1, 1, 1),
+            FakeInstance::new(1, 1, 1),
+        ])
+        .resource_rules(ResourceRules::new().lowercase(true))
+        .build();
+
+        let lock = dlm.lock("Orders:42", Duration::from_secs(1))?;
+        assert_eq!(lock.resource, b"orders:42");
+
+        let attempt = dlm.lock("orders 42", Duration::from_secs(1));
+        assert!(matches!(attempt, Err(RedsyncError::InvalidResourceName(_))));
+
+        Ok(())
+    }
+
+    #[test]
+    fn lock_binary() -> Result<(), RedsyncError> {
+        let dlm = Redsync::new(vec![
+            FakeInstance::new(1, 1, 1),
+            FakeInstance::new(1, 1, 1),
+            FakeInstance::new(1, 1, 1),
+        ]);
+
+        let resource = [0xff, 0x00, 0xfe];
+        let lock = dlm.lock(resource, Duration::from_secs(1))?;
+        assert_eq!(lock.resource, resource);
+        let lock = dlm.extend(&lock, Duration::from_secs(1))?;
+        dlm.unlock(&lock)?;
+
+        let token = [0x00, 0x9f, 0x92, 0x96];
+        let lock = dlm.reacquire_if_owner(resource, token, Duration::from_secs(1))?;
+        assert_eq!(lock.value, token);
+
+        let dlm = RedsyncBuilder::new(vec![FakeInstance::new(1, 1, 1)])
+            .resource_rules(ResourceRules::new())
+            .build();
+        let attempt = dlm.lock(resource, Duration::from_secs(1));
+        assert!(matches!(attempt, Err(RedsyncError::InvalidResourceName(_))));
+
+        Ok(())
+    }
+
+    #[test]
+    fn lock_rollback_on_panic() {
+        let dlm = Redsync::new(vec![
+            FakeInstance::new(1, 1, 1),
+            FakeInstance::new(2, 1, 1),
+            FakeInstance::new(1, 1, 1),
+        ]);
+
+        let attempt = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            dlm.lock("test", Duration::from_secs(1))
+        }));
+        assert!(attempt.is_err());
+        // The panicking instance may have granted the lock, but the last was never contacted
+        let releases: Vec<_> = dlm
+            .cluster
+            .iter()
+            .map(|instance| instance.releases.load(Ordering::SeqCst))
+            .collect();
+        assert_eq!(releases, [1, 1, 0]);
+    }
+
+    #[test]
+    fn lock_rollback_granted() {
+        let dlm = RedsyncBuilder::new(vec![
+            FakeInstance::new(1, 1, 1),
+            FakeInstance::new(0, 1, 1),
+            FakeInstance::new(3, 1, 1),
+        ])
+        .retry_count(1)
+        .build();
+
+        let attempt = dlm.lock("lock_rollback_granted", Duration::from_secs(1));
+        assert!(attempt.is_err());
+        let releases: Vec<_> = dlm
+            .cluster
+            .iter()
+            .map(|instance| instance.releases.load(Ordering::SeqCst))
+            .collect();
+        assert_eq!(releases, [1, 0, 1]);
+    }
+
+    #[test]
+    fn lock_single_instance() -> Result<(), RedsyncError> {
+        let dlm = Redsync::new(vec![FakeInstance::new(1, 1, 1)]);
+        assert_eq!(dlm.quorum, 1);
+
+        let lock = dlm.lock("lock_single_instance", Duration::from_secs(1))?;
+        // Drift is reserved from the TTL as with several instances
+        assert_eq!(lock.report.drift, dlm.get_drift(Duration::from_secs(1)));
+        assert!(lock.validity_at_acquisition() <= Duration::from_secs(1) - lock.report.drift);
+        dlm.unlock(&lock)?;
+        assert_eq!(dlm.cluster[0].calls.load(Ordering::SeqCst), 1);
+        assert_eq!(dlm.cluster[0].releases.load(Ordering::SeqCst), 1);
+
+        // A contended lock is not rolled back, as the instance answered that it is held elsewhere
+        let dlm = RedsyncBuilder::new(vec![FakeInstance::new(0, 1, 1)])
+            .retry_count(1)
+            .build();
+        assert!(dlm
+            .lock("lock_single_instance", Duration::from_secs(1))
+            .is_err());
+        assert_eq!(dlm.cluster[0].calls.load(Ordering::SeqCst), 1);
+        assert_eq!(dlm.cluster[0].releases.load(Ordering::SeqCst), 0);
+
+        // The failure domain quorum still applies to a single instance
+        let dlm = RedsyncBuilder::new(vec![FakeInstance::new(1, 1, 1)])
+            .retry_count(1)
+            .failure_domains(&["a"], 2)
+            .build();
+        assert!(dlm
+            .lock("lock_single_instance", Duration::from_secs(1))
+            .is_err());
+        assert_eq!(dlm.cluster[0].releases.load(Ordering::SeqCst), 1);
+
+        // A failed call may have granted the lock, so it is released
+        let dlm = RedsyncBuilder::new(vec![FakeInstance::new(3, 1, 1)])
+            .retry_count(1)
+            .build();
+        assert!(dlm
+            .lock("lock_single_instance", Duration::from_secs(1))
+            .is_err());
+        assert_eq!(dlm.cluster[0].releases.load(Ordering::SeqCst), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn lock_paused() -> Result<(), RedsyncError> {
+        let paused = Arc::new(AtomicBool::new(false));
+        let cluster = || {
+            vec![
+                FakeInstance::new(1, 1, 1),
+                FakeInstance::new(1, 1, 1),
+                FakeInstance::new(1, 1, 1),
+            ]
+        };
+        let dlm = RedsyncBuilder::new(cluster())
+            .pause_flag(paused.clone())
+            .build();
+        let other = RedsyncBuilder::new(cluster()).pause_flag(paused).build();
+
+        let lock = dlm.lock("test", Duration::from_secs(1))?;
+        dlm.pause();
+        assert!(other.is_paused());
+        assert!(matches!(
+            other.lock("other", Duration::from_secs(1)),
+            Err(RedsyncError::ManagerPaused)
+        ));
+        assert!(dlm.extend(&lock, Duration::from_secs(1)).is_ok());
+
+        other.resume();
+        assert!(dlm.lock("other", Duration::from_secs(1)).is_ok());
+
+        Ok(())
+    }
+
+    #[test]
+    fn from_array() {
+        const QUORUM: usize = quorum_for(3);
+
+        let dlm = Redsync::from_array([
+            FakeInstance::new(1, 1, 1),
+            FakeInstance::new(1, 1, 1),
+            FakeInstance::new(0, 1, 1),
+        ]);
+        assert_eq!(dlm.quorum as usize, QUORUM);
+        assert!(dlm.lock("test", Duration::from_secs(1)).is_ok());
+    }
+
+    #[test]
+    fn config_warnings() -> Result<(), RedsyncError> {
+        let warnings = Arc::new(Mutex::new(Vec::new()));
+        let recorded = warnings.clone();
+        let dlm = RedsyncBuilder::new(vec![FakeInstance::new(1, 1, 1)])
+            .retry_delay(Duration::ZERO)
+            .on_config_warning(move |w| recorded.lock().unwrap().push(w.clone()))
+            .build();
+
+        assert_eq!(dlm.config_warnings(), [ConfigWarning::SingleInstance]);
+        dlm.lock("test", Duration::from_secs(1))?;
+        dlm.lock("other", Duration::from_secs(1))?;
+
+        let warnings = warnings.lock().unwrap();
+        assert_eq!(warnings.len(), 2);
+        assert_eq!(warnings[0], ConfigWarning::SingleInstance);
+        assert!(matches!(
+            warnings[1],
+            ConfigWarning::RetryDelayBelowRoundTrip { .. }
+        ));
+
+        Ok(())
+    }
+
+    #[derive(Clone, Default)]
+    struct MemoryJournal(Arc<Mutex<Vec<JournalEntry>>>);
+
+    impl LockJournal for MemoryJournal {
+        fn record(&self, entry: &JournalEntry) -> Result<(), RedsyncError> {
+            let mut entries = self.0.lock().unwrap();
+            entries.retain(|e| !(e.resource == entry.resource && e.value == entry.value));
+            entries.push(entry.clone());
+            Ok(())
+        }
+
+        fn remove(&self, resource: &[u8], value: &[u8]) -> Result<(), RedsyncError> {
+            let mut entries = self.0.lock().unwrap();
+            entries.retain(|e| !(e.resource == resource && e.value == value));
+            Ok(())
+        }
+
+        fn entries(&self) -> Result<Vec<JournalEntry>, RedsyncError> {
+            Ok(self.0.lock().unwrap().clone())
+        }
+    }
+
+    #[test]
+    fn recover() -> Result<(), RedsyncError> {
+        let journal = MemoryJournal::default();
+        let cluster = |ttl| {
+            vec![
+                FakeInstance::new(1, 1, 1).ttl(ttl),
+                FakeInstance::new(1, 1, 1).ttl(ttl),
+                FakeInstance::new(1, 1, 1).ttl(ttl),
+            ]
+        };
+        let dlm = RedsyncBuilder::new(cluster(1000))
+            .journal(journal.clone())
+            .build();
+
+        let lock = dlm.lock("released", Duration::from_secs(1))?;
+        dlm.unlock(&lock)?;
+        let lock = dlm.lock("crashed", Duration::from_secs(1))?;
+        assert_eq!(journal.entries()?.len(), 1);
+
+        let restarted = Redsync::new(cluster(1000));
+        let locks = restarted.recover(&journal)?;
+        assert_eq!(locks.len(), 1);
+        assert_eq!(locks[0].resource, b"crashed");
+        assert_eq!(locks[0].value, lock.value);
+
+        let expired = Redsync::new(cluster(-2));
+        assert!(expired.recover(&journal)?.is_empty());
+        assert!(journal.entries()?.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn lock_sequences() -> Result<(), RedsyncError> {
+        let dlm = RedsyncBuilder::new(vec![
+            FakeInstance::new(1, 1, 1),
+            FakeInstance::new(1, 1, 1),
+            FakeInstance::new(1, 1, 1),
+        ])
+        .sequences(true)
+        .build();
+
+        let lock = dlm.lock("test", Duration::from_secs(1))?;
+        assert_eq!(lock.sequence, Some(1));
+        dlm.unlock(&lock)?;
+
+        // Advance one instance ahead, as if it alone granted other acquisitions
+        dlm.cluster[0].advance_sequence(&lock, 5, 0)?;
+        let lock = dlm.lock("test", Duration::from_secs(1))?;
+        assert_eq!(lock.sequence, Some(6));
+
+        let extended = dlm.extend(&lock, Duration::from_secs(1))?;
+        assert_eq!(extended.sequence, Some(6));
+
+        let lock = dlm.lock("other", Duration::from_secs(1))?;
+        assert_eq!(lock.sequence, Some(1));
+
+        Ok(())
+    }
+
+    #[test]
+    fn lock_if_version() -> Result<(), RedsyncError> {
+        let dlm = RedsyncBuilder::new(vec![
+            FakeInstance::new(1, 1, 1),
+            FakeInstance::new(1, 1, 1),
+            FakeInstance::new(1, 1, 1),
+        ])
+        .sequences(true)
+        .build();
+
+        let lock = dlm.lock_if_version("test", 0, Duration::from_secs(1))?;
+        assert_eq!(lock.sequence, Some(1));
+        dlm.unlock(&lock)?;
+
+        let lock = dlm.lock("test", Duration::from_secs(1))?;
+        dlm.unlock(&lock)?;
+
+        let attempt = dlm.lock_if_version("test", 1, Duration::from_secs(1));
+        assert!(matches!(
+            attempt,
+            Err(RedsyncError::VersionMismatch {
+                expected: 1,
+                actual: 2
+            })
+        ));
+        // The stale attempt neither acquires the resource nor advances its version
+        assert_eq!(dlm.cluster[0].calls.load(Ordering::SeqCst), 2);
+        assert_eq!(dlm.cluster[0].releases.load(Ordering::SeqCst), 2);
+
+        let lock = dlm.lock_if_version("test", 2, Duration::from_secs(1))?;
+        assert_eq!(lock.sequence, Some(3));
+
+        let dlm = Redsync::new(vec![FakeInstance::new(1, 1, 1)]);
+        let attempt = dlm.lock_if_version("test", 0, Duration::from_secs(1));
+        assert!(matches!(attempt, Err(RedsyncError::InvalidConfig(_))));
+
+        Ok(())
+    }
+
+    #[test]
+    fn force_unlock_prefix() -> Result<(), RedsyncError> {
+        let dlm = Redsync::new(vec![
+            FakeInstance::new(1, 1, 1).keys(&["tenant:42:a", "tenant:42:b", "tenant:7:a"]),
+            FakeInstance::new(1, 1, 1).keys(&["tenant:42:a"]),
+            FakeInstance::new(1, 1, 1).keys(&["tenant:42:c"]),
+        ]);
+
+        let resources = dlm.force_unlock_prefix("tenant:42:", true, false)?;
+        assert_eq!(resources, ["tenant:42:a", "tenant:42:b", "tenant:42:c"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn force_unlock_prefix_error() {
+        let dlm = Redsync::new(vec![
+            FakeInstance::new(1, 1, 1),
+            FakeInstance::new(1, 1, 1),
+            FakeInstance::new(1, 1, 0),
+        ]);
+
+        let attempt = dlm.force_unlock_prefix("tenant:42:", false, false);
+        assert!(matches!(
+            attempt,
+            Err(RedsyncError::ForceUnlockFailed { .. })
+        ));
+    }
+
+    #[test]
+    fn preflight() {
+        let dlm = Redsync::new(vec![
+            FakeInstance::new(1, 1, 1),
+            FakeInstance::new(1, 1, 1).unreachable(),
+        ]);
+
+        let report = dlm.preflight();
+        assert_eq!(report.nodes.len(), 2);
+        assert_eq!(
+            report.warnings(),
+            [PreflightWarning::Unreachable(
+                1,
+                String::from("injected fault")
+            )]
+        );
+    }
+
+    #[test]
+    fn calibrate_drift() -> Result<(), RedsyncError> {
+        let cluster = vec![
+            FakeInstance::new(1, 1, 1),
+            FakeInstance::new(1, 1, 1),
+            FakeInstance::new(1, 1, 1).unreachable(),
+        ];
+        let dlm = RedsyncBuilder::new(cluster).dynamic_drift(true).build();
+
+        assert_eq!(dlm.calibrate_drift()?, None);
+        assert_eq!(
+            dlm.get_drift(Duration::from_secs(1)),
+            Duration::from_millis(12)
+        );
+
+        thread::sleep(Duration::from_millis(10));
+        let factor = dlm.calibrate_drift()?;
+        assert!(matches!(factor, Some(factor) if factor < 0.01));
+        assert!(dlm.get_drift(Duration::from_secs(1)) < Duration::from_millis(12));
+
+        Ok(())
     }
 
-    pub fn extend(&self, lock: &Lock, ttl: Duration) -> Result<Lock, RedsyncError> {
-        self.call(Call::Extend, &lock.resource, &lock.value, ttl)
+    #[test]
+    fn calibrate_drift_error() {
+        let dlm = Redsync::new(vec![
+            FakeInstance::new(1, 1, 1),
+            FakeInstance::new(1, 1, 1).unreachable(),
+            FakeInstance::new(1, 1, 1).unreachable(),
+        ]);
+
+        let attempt = dlm.calibrate_drift();
+        assert!(matches!(
+            attempt,
+            Err(RedsyncError::CalibrationFailed { .. })
+        ));
     }
 
-    fn call(
-        &self,
-        call: Call,
-        resource: &str,
-        value: &str,
-        ttl: Duration,
-    ) -> Result<Lock, RedsyncError> {
-        let drift = Duration::from_millis((ttl.as_millis() as f64 * self.drift_factor) as u64 + 2);
+    #[test]
+    fn remaining_ttl() -> Result<(), RedsyncError> {
+        let dlm = Redsync::new(vec![
+            FakeInstance::new(1, 1, 1).ttl(900),
+            FakeInstance::new(1, 1, 1).ttl(800),
+            FakeInstance::new(1, 1, 1).ttl(100),
+        ]);
+        let lock = dlm.lock("test", Duration::from_secs(1))?;
 
-        let mut errors = MultiError::new();
+        let ttl = dlm.remaining_ttl(&lock)?;
+        let (min, max) = (Duration::from_millis(700), Duration::from_millis(800));
+        assert!(
+            min < ttl && ttl <= max,
+            "expected remaining ttl to be between {:?} and {:?}, but got {:?}",
+            min,
+            max,
+            ttl,
+        );
 
-        for attempt in 1..=self.retry_count {
-            let mut votes = 0;
-            let start = Instant::now();
+        Ok(())
+    }
 
-            let lock = Lock {
-                resource: String::from(resource),
-                value: String::from(value),
-                ttl,
-                expiry: start + ttl - drift,
-            };
+    #[test]
+    fn remaining_ttl_error() -> Result<(), RedsyncError> {
+        let dlm = Redsync::new(vec![
+            FakeInstance::new(1, 1, 1).ttl(-1),
+            FakeInstance::new(1, 1, 1).ttl(-1),
+            FakeInstance::new(1, 1, 1),
+        ]);
+        let lock = dlm.lock("test", Duration::from_secs(1))?;
 
-            for instance in &self.cluster {
-                let result = match call {
-                    Call::Lock => instance.acquire(&lock),
-                    Call::Extend => instance.extend(&lock),
-                };
+        let attempt = dlm.remaining_ttl(&lock);
+        assert!(matches!(attempt, Err(RedsyncError::TtlFailed { .. })));
 
-                match result {
-                    Ok(()) => votes += 1,
-                    Err(e) => errors.push(e),
-                }
-            }
+        Ok(())
+    }
 
-            if votes >= self.quorum && lock.expiry > Instant::now() {
-                return Ok(lock);
-            }
+    #[test]
+    fn try_build_verify() {
+        let cluster = vec![
+            FakeInstance::new(1, 1, 1),
+            FakeInstance::new(1, 1, 1).unreachable(),
+            FakeInstance::new(1, 1, 1),
+        ];
 
-            let _ = self.unlock(&lock);
-            if attempt < self.retry_count {
-                errors.reset();
-                thread::sleep(self.get_retry_delay());
+        let attempt = RedsyncBuilder::new(cluster)
+            .verify_on_build(true)
+            .try_build();
+        match attempt {
+            Err(RedsyncError::UnreachableInstances(errors)) => {
+                assert_eq!(
+                    *errors,
+                    [RedsyncError::InstanceError(
+                        1,
+                        Box::new(RedsyncError::InjectedFault)
+                    )]
+                );
             }
+            _ => panic!("expected UnreachableInstances error"),
         }
+    }
 
-        match call {
-            Call::Lock => Err(RedsyncError::LockRetriesExceeded(errors)),
-            Call::Extend => Err(RedsyncError::ExtendRetriesExceeded(errors)),
-        }
+    #[test]
+    fn keepalive() {
+        let dlm = Redsync::new(vec![
+            FakeInstance::new(1, 1, 1),
+            FakeInstance::new(1, 1, 1).unreachable(),
+        ]);
+
+        let attempt = dlm.keepalive();
+        assert!(matches!(
+            attempt,
+            Err(RedsyncError::UnreachableInstances { .. })
+        ));
     }
 
-    pub fn unlock(&self, lock: &Lock) -> Result<(), RedsyncError> {
-        let mut n = 0;
-        let mut errors = MultiError::new();
+    #[test]
+    fn maintain() -> Result<(), RedsyncError> {
+        let dlm = Redsync::new(vec![FakeInstance::new(1, 1, 1)]);
+        let lock = dlm.lock("test", Duration::from_secs(1))?;
 
-        for instance in &self.cluster {
-            match instance.release(lock) {
-                Ok(()) => n += 1,
-                Err(e) => errors.push(e),
-            };
-        }
+        let schedule = KeepaliveSchedule::new(0.5).jitter(0.0);
+        let mut lock = dlm.maintain(lock, schedule);
+        let deadline = lock.next_deadline();
 
-        if n < self.quorum {
-            return Err(RedsyncError::UnlockFailed(errors));
-        }
+        assert!(!lock.maintain(Instant::now())?);
+        assert!(lock.maintain(deadline)?);
+        assert!(lock.next_deadline() > deadline);
+
+        let expiry = lock.lock().expiry;
+        assert_eq!(lock.maintain(expiry), Err(RedsyncError::InvalidLease));
 
         Ok(())
     }
 
-    fn get_unique_lock_id(&self) -> String {
-        thread_rng()
-            .sample_iter(&Alphanumeric)
-            .take(20)
-            .map(char::from)
-            .collect()
-    }
+    #[test]
+    fn maintain_remaining_ttl() -> Result<(), RedsyncError> {
+        // The instance reports less time left than the local estimate after each extension
+        let dlm = Redsync::new(vec![FakeInstance::new(1, 1, 1).ttl(400)]);
+        let lock = dlm.lock("test", Duration::from_secs(1))?;
 
-    fn get_retry_delay(&self) -> Duration {
-        let jitter = thread_rng().gen_range(-1.0..1.0) * self.retry_jitter;
-        if jitter > 0.0 {
-            self.retry_delay.add(Duration::from_millis(jitter as u64))
-        } else {
-            self.retry_delay.sub(Duration::from_millis(-jitter as u64))
-        }
-    }
-}
+        let schedule = KeepaliveSchedule::new(0.5).jitter(0.0);
+        let mut lock = dlm.maintain(lock, schedule);
+        assert!(lock.maintain(lock.next_deadline())?);
+        assert!(lock.lock().expiry <= Instant::now() + Duration::from_millis(400));
+        assert!(lock.next_deadline() <= Instant::now());
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::matches;
+        // Nothing is left on a quorum of instances, so the lease is lost
+        let dlm = Redsync::new(vec![FakeInstance::new(1, 1, 1).ttl(0)]);
+        let lock = dlm.lock("test", Duration::from_secs(1))?;
+        let mut lock = dlm.maintain(lock, schedule);
+        assert_eq!(
+            lock.maintain(lock.next_deadline()),
+            Err(RedsyncError::InvalidLease)
+        );
 
-    struct FakeInstance {
-        acquire: i32,
-        extend: i32,
-        release: i32,
+        Ok(())
     }
 
-    impl FakeInstance {
-        pub fn new(acquire: i32, extend: i32, release: i32) -> Self {
-            Self {
-                acquire,
-                extend,
-                release,
+    #[test]
+    fn durability() -> Result<(), RedsyncError> {
+        let dlm = RedsyncBuilder::new(vec![
+            FakeInstance::new(1, 1, 1).replicas(1),
+            FakeInstance::new(1, 1, 1).replicas(1),
+            FakeInstance::new(1, 1, 1),
+        ])
+        .durability(Durability::new(1, Duration::from_millis(10)))
+        .build();
+        dlm.lock("durability", Duration::from_secs(1))?;
+
+        let dlm = RedsyncBuilder::new(vec![
+            FakeInstance::new(1, 1, 1).replicas(1),
+            FakeInstance::new(1, 1, 1),
+            FakeInstance::new(1, 1, 1),
+        ])
+        .durability(Durability::new(1, Duration::from_millis(10)))
+        .retry_count(1)
+        .build();
+
+        match dlm.lock("durability", Duration::from_secs(1)) {
+            Err(RedsyncError::LockRetriesExceeded(errors)) => {
+                assert!(errors.includes(RedsyncError::ReplicationUnconfirmed {
+                    acknowledged: 0,
+                    required: 1
+                }));
             }
+            _ => panic!("expected LockRetriesExceeded error"),
+        }
+        // The unconfirmed instances still hold the lock, so they are rolled back too
+        for instance in &dlm.cluster {
+            assert_eq!(instance.releases.load(Ordering::SeqCst), 1);
         }
+
+        Ok(())
     }
 
-    impl Instance for FakeInstance {
-        fn acquire(&self, _lock: &Lock) -> Result<(), RedsyncError> {
-            match self.acquire {
-                1 => Ok(()),
-                _ => Err(RedsyncError::ResourceLocked),
-            }
-        }
+    #[test]
+    fn lock_with_durability() -> Result<(), RedsyncError> {
+        let dlm = RedsyncBuilder::new(vec![
+            FakeInstance::new(1, 1, 1).replicas(1),
+            FakeInstance::new(1, 1, 1),
+            FakeInstance::new(1, 1, 1),
+        ])
+        .retry_count(1)
+        .build();
+        dlm.lock("durability", Duration::from_secs(1))?;
 
-        fn extend(&self, _lock: &Lock) -> Result<(), RedsyncError> {
-            match self.extend {
-                1 => Ok(()),
-                _ => Err(RedsyncError::InvalidLease),
-            }
-        }
+        let options = LockOptions::new().durability(1, Duration::from_millis(10));
+        assert!(matches!(
+            dlm.lock_with("durable", Duration::from_secs(1), &options),
+            Err(RedsyncError::LockRetriesExceeded(_))
+        ));
 
-        fn release(&self, _lock: &Lock) -> Result<(), RedsyncError> {
-            match self.release {
-                1 => Ok(()),
-                _ => Err(RedsyncError::InvalidLease),
-            }
-        }
+        Ok(())
     }
 
     #[test]
-    fn lock() {
+    fn reserve() -> Result<(), RedsyncError> {
         let dlm = Redsync::new(vec![
             FakeInstance::new(1, 1, 1),
             FakeInstance::new(1, 1, 1),
             FakeInstance::new(0, 1, 1),
         ]);
+        let at = SystemTime::now() + Duration::from_secs(60);
+        let reservation = dlm.reserve("reserve", at, Duration::from_secs(60))?;
+        assert_eq!(reservation.start, at);
 
-        let attempt = dlm.lock("test", Duration::from_secs(1));
-        assert!(attempt.is_ok());
+        let lock = dlm.claim(&reservation, Duration::from_secs(1));
+        assert_eq!(lock?.value, reservation.value);
 
-        let lock = attempt.unwrap();
-        assert_eq!(lock.resource, "test");
-        assert!(lock.value.len() > 0);
-        assert_eq!(lock.ttl, Duration::from_secs(1));
+        let dlm = Redsync::new(vec![
+            FakeInstance::new(1, 1, 1),
+            FakeInstance::new(0, 1, 1),
+            FakeInstance::new(0, 1, 1),
+        ]);
+        match dlm.reserve("reserve", at, Duration::from_secs(60)) {
+            Err(RedsyncError::ReservationFailed(errors)) => {
+                assert!(errors.includes(RedsyncError::ResourceReserved));
+            }
+            _ => panic!("expected ReservationFailed error"),
+        }
+        // The reservation placed on a minority is cancelled
+        for instance in &dlm.cluster {
+            assert_eq!(instance.releases.load(Ordering::SeqCst), 1);
+        }
+
+        let attempt = dlm.reserve("reserve", SystemTime::now(), Duration::ZERO);
+        assert!(matches!(attempt, Err(RedsyncError::InvalidTtl(_))));
+
+        Ok(())
     }
 
     #[test]
-    fn lock_error() {
-        let dlm = Redsync::new(vec![
+    fn rollback_disabled() {
+        let dlm = RedsyncBuilder::new(vec![
+            FakeInstance::new(1, 1, 1),
             FakeInstance::new(0, 1, 1),
             FakeInstance::new(0, 1, 1),
+        ])
+        .rollback(RollbackPolicy::Disabled)
+        .sleep_fn(|_| {})
+        .build();
+
+        let attempt = dlm.lock("rollback_disabled", Duration::from_secs(1));
+        assert!(attempt.is_err());
+        for instance in &dlm.cluster {
+            assert_eq!(instance.releases.load(Ordering::SeqCst), 0);
+        }
+    }
+
+    #[test]
+    fn retry_after() {
+        let dlm = RedsyncBuilder::new(vec![
+            FakeInstance::new(0, 1, 1).ttl(300),
+            FakeInstance::new(0, 1, 1).ttl(100),
             FakeInstance::new(1, 1, 1),
-        ]);
+        ])
+        .sleep_fn(|_| {})
+        .build();
 
-        let attempt = dlm.lock("test", Duration::from_secs(1));
-        assert!(matches!(
-            attempt,
-            Err(RedsyncError::LockRetriesExceeded { .. })
-        ));
+        let attempt = dlm.lock("retry_after", Duration::from_secs(1));
+        let retry_after = attempt.err().and_then(|e| e.retry_after()).unwrap();
+        assert!(
+            retry_after >= Duration::from_millis(100) && retry_after < Duration::from_millis(300),
+            "expected retry after to be about 100ms, but got {:?}",
+            retry_after
+        );
+
+        let dlm = RedsyncBuilder::new(vec![
+            FakeInstance::new(0, 1, 1).unreachable(),
+            FakeInstance::new(0, 1, 1).unreachable(),
+            FakeInstance::new(0, 1, 1),
+        ])
+        .sleep_fn(|_| {})
+        .build();
+
+        let attempt = dlm.lock("retry_after", Duration::from_secs(1));
+        assert!(matches!(attempt, Err(e) if e.retry_after().is_none()));
     }
 
     #[test]
-    fn extend() -> Result<(), RedsyncError> {
+    fn audit_tail() -> Result<(), RedsyncError> {
+        let dlm = RedsyncBuilder::new(vec![FakeInstance::new(1, 1, 1)])
+            .audit_log("worker-1", 100)
+            .build();
+
+        let lock = dlm.lock("audit_tail", Duration::from_secs(1))?;
+        dlm.unlock(&lock)?;
+
+        let events = dlm.audit_tail(10)?;
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].action, AuditAction::Release);
+        assert_eq!(events[1].action, AuditAction::Acquire);
+        assert!(events
+            .iter()
+            .all(|e| e.is_ok() && e.identity == "worker-1" && e.resource == "audit_tail"));
+
+        let dlm = RedsyncBuilder::new(vec![FakeInstance::new(0, 1, 1)])
+            .audit_log("worker-1", 100)
+            .sleep_fn(|_| {})
+            .build();
+        assert!(dlm.lock("audit_tail", Duration::from_secs(1)).is_err());
+
+        let events = dlm.audit_tail(1)?;
+        assert_eq!(events[0].outcome, "lock retries exceeded");
+
+        Ok(())
+    }
+
+    #[test]
+    fn stats() -> Result<(), RedsyncError> {
+        let dlm = RedsyncBuilder::new(vec![FakeInstance::new(1, 1, 1)])
+            .stats(1.0)
+            .build();
+
+        let lock = dlm.lock("test", Duration::from_secs(1))?;
+        dlm.unlock(&lock)?;
+
+        let stats = dlm.stats("test")?;
+        assert_eq!(stats.acquisitions, 1);
+        assert_eq!(stats.releases, 1);
+        assert!(stats.average_hold().is_some());
+
+        Ok(())
+    }
+
+    #[test]
+    fn extend_partial() -> Result<(), RedsyncError> {
         let dlm = Redsync::new(vec![
             FakeInstance::new(1, 1, 1),
             FakeInstance::new(1, 1, 1),
             FakeInstance::new(1, 0, 1),
         ]);
         let lock = dlm.lock("test", Duration::from_secs(1))?;
+        assert!(lock.lost_instances.is_empty());
 
-        let attempt = dlm.extend(&lock, Duration::from_secs(2));
-        assert!(attempt.is_ok());
-
-        let lock = attempt.unwrap();
-        assert_eq!(lock.resource, "test");
-        assert!(lock.value.len() > 0);
-        assert_eq!(lock.ttl, Duration::from_secs(2));
+        let lock = dlm.extend(&lock, Duration::from_secs(1))?;
+        assert_eq!(lock.lost_instances, vec![2]);
 
         Ok(())
     }
 
     #[test]
-    fn extend_error() -> Result<(), RedsyncError> {
-        let dlm = Redsync::new(vec![
-            FakeInstance::new(1, 0, 1),
-            FakeInstance::new(1, 0, 1),
+    fn extend_partial_repair() -> Result<(), RedsyncError> {
+        let dlm = RedsyncBuilder::new(vec![
             FakeInstance::new(1, 1, 1),
-        ]);
+            FakeInstance::new(1, 1, 1),
+            FakeInstance::new(1, 0, 1),
+        ])
+        .partial_extend(PartialExtend::Repair)
+        .build();
         let lock = dlm.lock("test", Duration::from_secs(1))?;
 
-        let attempt = dlm.extend(&lock, Duration::from_secs(2));
-        assert!(matches!(
-            attempt,
-            Err(RedsyncError::ExtendRetriesExceeded { .. })
-        ));
+        let lock = dlm.extend(&lock, Duration::from_secs(1))?;
+        assert!(lock.lost_instances.is_empty());
 
         Ok(())
     }
 
     #[test]
-    fn unlock() -> Result<(), RedsyncError> {
-        let dlm = Redsync::new(vec![
-            FakeInstance::new(1, 1, 1),
-            FakeInstance::new(1, 1, 1),
-            FakeInstance::new(1, 1, 0),
-        ]);
+    fn validity_at_acquisition() -> Result<(), RedsyncError> {
+        let dlm = Redsync::new(vec![FakeInstance::new(1, 1, 1)]);
         let lock = dlm.lock("test", Duration::from_secs(1))?;
 
-        let attempt = dlm.unlock(&lock);
-        assert!(attempt.is_ok());
+        let validity = lock.validity_at_acquisition();
+        assert!(validity <= Duration::from_millis(988));
+        assert!(validity > Duration::from_millis(900));
+        assert!(lock.report.validity_consumed_by_io() < 0.1);
 
         Ok(())
     }
 
     #[test]
-    fn unlock_error() -> Result<(), RedsyncError> {
-        let dlm = Redsync::new(vec![
-            FakeInstance::new(1, 1, 0),
-            FakeInstance::new(1, 1, 0),
-            FakeInstance::new(1, 1, 1),
-        ]);
+    fn with_lock() -> Result<(), RedsyncError> {
+        let dlm = Redsync::new(vec![FakeInstance::new(1, 1, 1)]);
+
+        let extends = dlm.with_lock("test", Duration::from_secs(1), |ctx| {
+            assert!(!ctx.should_abort());
+            assert!(ctx.remaining() <= Duration::from_secs(1));
+
+            ctx.extend(Duration::from_secs(1))?;
+            Ok::<_, RedsyncError>(ctx.lock().history.extends)
+        })??;
+        assert_eq!(extends, 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn extend_history() -> Result<(), RedsyncError> {
+        let dlm = Redsync::new(vec![FakeInstance::new(1, 1, 1)]);
         let lock = dlm.lock("test", Duration::from_secs(1))?;
 
-        let attempt = dlm.unlock(&lock);
-        assert!(matches!(attempt, Err(RedsyncError::UnlockFailed { .. })));
+        let extended = dlm.extend(&lock, Duration::from_secs(1))?;
+        let extended = dlm.extend(&extended, Duration::from_secs(1))?;
+        assert_eq!(extended.history.acquired_at, lock.history.acquired_at);
+        assert_eq!(extended.history.extends, 2);
+        assert!(extended.history.last_extended.is_some());
 
         Ok(())
     }
@@ -287,12 +4052,41 @@ mod tests {
         assert!(value.is_ascii());
     }
 
+    #[test]
+    fn get_unique_lock_id_v2() {
+        let cluster = vec![FakeInstance::new(1, 1, 1)];
+        let dlm = RedsyncBuilder::new(cluster)
+            .record_version(RecordVersion::V2)
+            .build();
+
+        let value = dlm.get_unique_lock_id();
+        assert!(value.starts_with(b"redsync/2;"));
+
+        let record = LockRecord::decode(&value);
+        assert_eq!(record.version, RecordVersion::V2);
+        assert_eq!(record.token.len(), 20);
+    }
+
+    #[test]
+    fn get_unique_lock_id_interop() {
+        let cluster = vec![FakeInstance::new(1, 1, 1)];
+        let dlm = RedsyncBuilder::new(cluster)
+            .record_version(RecordVersion::V2)
+            .interop(true)
+            .build();
+        assert!(dlm.is_interop());
+
+        let value = dlm.get_unique_lock_id();
+        assert_eq!(value.len(), 20);
+        assert!(value.iter().all(u8::is_ascii_alphanumeric));
+    }
+
     #[test]
     fn get_retry_delay() {
         let cluster = vec![FakeInstance::new(1, 1, 1)];
         let dlm = Redsync::new(cluster);
 
-        let retry_delay = dlm.get_retry_delay();
+        let retry_delay = dlm.get_retry_delay(b"test");
         let (min, max) = (Duration::from_millis(100), Duration::from_millis(300));
         assert!(
             min < retry_delay && retry_delay < max,
@@ -302,4 +4096,32 @@ mod tests {
             retry_delay,
         );
     }
+
+    #[test]
+    fn get_retry_delay_adaptive() {
+        let cluster = vec![
+            FakeInstance::new(0, 1, 1),
+            FakeInstance::new(0, 1, 1),
+            FakeInstance::new(1, 1, 1),
+        ];
+        let dlm = RedsyncBuilder::new(cluster)
+            .retry_count(2)
+            .retry_delay(Duration::from_millis(10))
+            .adaptive_retry(true)
+            .build();
+
+        let attempt = dlm.lock("test", Duration::from_secs(1));
+        assert!(attempt.is_err());
+        assert_eq!(dlm.retry_backoff().get(&b"test"[..]), Some(&4.0));
+
+        let retry_delay = dlm.get_retry_delay(b"test");
+        let (min, max) = (Duration::from_millis(20), Duration::from_millis(60));
+        assert!(
+            min < retry_delay && retry_delay < max,
+            "expected retry delay to be between {:?} and {:?}, but got {:?}",
+            min,
+            max,
+            retry_delay,
+        );
+    }
 }
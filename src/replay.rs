@@ -0,0 +1,549 @@
+use crate::audit::AuditEvent;
+use crate::durability::Durability;
+use crate::errors::RedsyncError;
+use crate::instance::Instance;
+use crate::preflight::NodeDiagnostics;
+use crate::redsync::{Lock, Redsync};
+use crate::reservation::Reservation;
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+/// `OK` is the outcome recorded for successful calls. Failed calls record the `kind` of their
+/// error.
+const OK: &str = "ok";
+
+/// `Operation` is the instance call recorded by an `Interaction`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Operation {
+    Acquire,
+    Extend,
+    Reacquire,
+    Release,
+}
+
+impl Operation {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Operation::Acquire => "acquire",
+            Operation::Extend => "extend",
+            Operation::Reacquire => "reacquire",
+            Operation::Release => "release",
+        }
+    }
+
+    fn parse(operation: &str) -> Option<Self> {
+        match operation {
+            "acquire" => Some(Operation::Acquire),
+            "extend" => Some(Operation::Extend),
+            "reacquire" => Some(Operation::Reacquire),
+            "release" => Some(Operation::Release),
+            _ => None,
+        }
+    }
+}
+
+/// `Interaction` is a single call made to an instance of a recorded cluster.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Interaction {
+    /// `instance` is the index of the instance in the cluster.
+    pub instance: usize,
+    pub operation: Operation,
+    /// `elapsed` is how long the instance took to respond.
+    pub elapsed: Duration,
+    /// `outcome` is "ok" if the call succeeded, or the `kind` of its error otherwise.
+    pub outcome: String,
+}
+
+impl Interaction {
+    /// `result` returns the response to replay. `ResourceLocked` and `InvalidLease` are replayed
+    /// as is, as the lock algorithm acts on them; any other error is replayed as `InjectedFault`.
+    fn result(&self) -> Result<(), RedsyncError> {
+        match self.outcome.as_str() {
+            OK => Ok(()),
+            "resource locked" => Err(RedsyncError::ResourceLocked),
+            "invalid lease" => Err(RedsyncError::InvalidLease),
+            _ => Err(RedsyncError::InjectedFault),
+        }
+    }
+}
+
+/// `Fixture` is a replayable record of every call made to a cluster, in order, captured with a
+/// `Recording`. Fixtures are written as text with one interaction per line, so that they can be
+/// attached to bug reports and replayed with `Redsync::replay`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Fixture {
+    pub cluster_size: usize,
+    pub interactions: Vec<Interaction>,
+}
+
+impl Fixture {
+    /// `instances` returns a ReplayInstance for each instance of the recorded cluster.
+    pub fn instances(&self) -> Vec<ReplayInstance> {
+        (0..self.cluster_size)
+            .map(|i| ReplayInstance {
+                interactions: Mutex::new(
+                    self.interactions
+                        .iter()
+                        .filter(|interaction| interaction.instance == i)
+                        .cloned()
+                        .collect(),
+                ),
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for Fixture {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "cluster {}", self.cluster_size)?;
+        for interaction in &self.interactions {
+            writeln!(
+                f,
+                "{} {} {} {}",
+                interaction.instance,
+                interaction.operation.as_str(),
+                interaction.elapsed.as_micros(),
+                interaction.outcome
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for Fixture {
+    type Err = RedsyncError;
+
+    fn from_str(fixture: &str) -> Result<Self, Self::Err> {
+        let invalid =
+            |line: &str| RedsyncError::InvalidConfig(format!("invalid fixture: {}", line));
+
+        let mut lines = fixture.lines().filter(|line| !line.trim().is_empty());
+        let header = lines.next().unwrap_or_default();
+        let cluster_size = header
+            .strip_prefix("cluster ")
+            .and_then(|size| size.parse().ok())
+            .ok_or_else(|| invalid(header))?;
+
+        let mut interactions = Vec::new();
+        for line in lines {
+            let fields: Vec<&str> = line.splitn(4, ' ').collect();
+            let interaction = match fields.as_slice() {
+                [instance, operation, elapsed, outcome] => Interaction {
+                    instance: instance.parse().map_err(|_| invalid(line))?,
+                    operation: Operation::parse(operation).ok_or_else(|| invalid(line))?,
+                    elapsed: Duration::from_micros(elapsed.parse().map_err(|_| invalid(line))?),
+                    outcome: String::from(*outcome),
+                },
+                _ => return Err(invalid(line)),
+            };
+            if interaction.instance >= cluster_size {
+                return Err(invalid(line));
+            }
+            interactions.push(interaction);
+        }
+
+        Ok(Self {
+            cluster_size,
+            interactions,
+        })
+    }
+}
+
+/// `Recording` captures the calls made to a cluster into a `Fixture`.
+///
+/// ```ignore
+/// let recording = Recording::new();
+/// let dlm = Redsync::new(recording.instances(cluster));
+///
+/// if dlm.lock("resource", Duration::from_secs(1)).is_err() {
+///     std::fs::write("lock.fixture", recording.take().to_string())?;
+/// }
+/// ```
+#[derive(Clone, Default)]
+pub struct Recording {
+    fixture: Arc<Mutex<Fixture>>,
+}
+
+impl Recording {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// `instances` wraps each instance of `cluster` so that its calls are recorded.
+    pub fn instances<I: Instance>(&self, cluster: Vec<I>) -> Vec<RecordedInstance<I>> {
+        self.fixture.lock().unwrap().cluster_size = cluster.len();
+        cluster
+            .into_iter()
+            .enumerate()
+            .map(|(index, inner)| RecordedInstance {
+                inner,
+                index,
+                fixture: self.fixture.clone(),
+            })
+            .collect()
+    }
+
+    /// `take` returns the calls recorded so far, and starts a new recording.
+    pub fn take(&self) -> Fixture {
+        let mut fixture = self.fixture.lock().unwrap();
+        Fixture {
+            cluster_size: fixture.cluster_size,
+            interactions: std::mem::take(&mut fixture.interactions),
+        }
+    }
+}
+
+/// `RecordedInstance` wraps an Instance and records its acquire, extend, reacquire and release
+/// calls into a `Recording`.
+pub struct RecordedInstance<I: Instance> {
+    inner: I,
+    index: usize,
+    fixture: Arc<Mutex<Fixture>>,
+}
+
+impl<I: Instance> RecordedInstance<I> {
+    pub fn inner(&self) -> &I {
+        &self.inner
+    }
+
+    fn record(
+        &self,
+        operation: Operation,
+        call: impl FnOnce() -> Result<(), RedsyncError>,
+    ) -> Result<(), RedsyncError> {
+        let start = Instant::now();
+        let result = call();
+        let outcome = match &result {
+            Ok(()) => OK,
+            Err(e) => e.name(),
+        };
+
+        self.fixture.lock().unwrap().interactions.push(Interaction {
+            instance: self.index,
+            operation,
+            elapsed: start.elapsed(),
+            outcome: String::from(outcome),
+        });
+
+        result
+    }
+}
+
+impl<I: Instance> Instance for RecordedInstance<I> {
+    fn acquire(&self, lock: &Lock) -> Result<(), RedsyncError> {
+        self.record(Operation::Acquire, || self.inner.acquire(lock))
+    }
+
+    fn acquire_replicated(&self, lock: &Lock, durability: &Durability) -> Result<(), RedsyncError> {
+        self.record(Operation::Acquire, || {
+            self.inner.acquire_replicated(lock, durability)
+        })
+    }
+
+    fn acquire_if_version(
+        &self,
+        lock: &Lock,
+        version: u64,
+        durability: Option<&Durability>,
+    ) -> Result<(), RedsyncError> {
+        self.record(Operation::Acquire, || {
+            self.inner.acquire_if_version(lock, version, durability)
+        })
+    }
+
+    fn extend(&self, lock: &Lock) -> Result<(), RedsyncError> {
+        self.record(Operation::Extend, || self.inner.extend(lock))
+    }
+
+    fn reacquire(&self, lock: &Lock) -> Result<(), RedsyncError> {
+        self.record(Operation::Reacquire, || self.inner.reacquire(lock))
+    }
+
+    fn release(&self, lock: &Lock) -> Result<(), RedsyncError> {
+        self.record(Operation::Release, || self.inner.release(lock))
+    }
+
+    fn ttl(&self, lock: &Lock) -> Result<Duration, RedsyncError> {
+        self.inner.ttl(lock)
+    }
+
+    fn guarded_set(&self, lock: &Lock, key: &str, value: &str) -> Result<(), RedsyncError> {
+        self.inner.guarded_set(lock, key, value)
+    }
+
+    fn peek(&self, resource: &[u8]) -> Result<Option<Duration>, RedsyncError> {
+        self.inner.peek(resource)
+    }
+
+    fn ping(&self) -> Result<(), RedsyncError> {
+        self.inner.ping()
+    }
+
+    fn diagnostics(&self) -> Result<NodeDiagnostics, RedsyncError> {
+        self.inner.diagnostics()
+    }
+
+    fn time(&self) -> Result<SystemTime, RedsyncError> {
+        self.inner.time()
+    }
+
+    fn force_release_prefix(
+        &self,
+        prefix: &str,
+        dry_run: bool,
+        bare_tokens: bool,
+    ) -> Result<Vec<String>, RedsyncError> {
+        self.inner
+            .force_release_prefix(prefix, dry_run, bare_tokens)
+    }
+
+    fn incr(&self, key: &str, delta: i64, expiry: Duration) -> Result<i64, RedsyncError> {
+        self.inner.incr(key, delta, expiry)
+    }
+
+    fn incr_stats(&self, resource: &[u8], fields: &[(&str, i64)]) -> Result<(), RedsyncError> {
+        self.inner.incr_stats(resource, fields)
+    }
+
+    fn read_stats(&self, resource: &[u8]) -> Result<Vec<(String, i64)>, RedsyncError> {
+        self.inner.read_stats(resource)
+    }
+
+    fn advance_sequence(&self, lock: &Lock, floor: i64, step: i64) -> Result<i64, RedsyncError> {
+        self.inner.advance_sequence(lock, floor, step)
+    }
+
+    fn append_audit(
+        &self,
+        fields: &[(&str, String)],
+        retention: usize,
+    ) -> Result<(), RedsyncError> {
+        self.inner.append_audit(fields, retention)
+    }
+
+    fn read_audit(&self, count: usize) -> Result<Vec<AuditEvent>, RedsyncError> {
+        self.inner.read_audit(count)
+    }
+
+    fn reserve(&self, reservation: &Reservation) -> Result<(), RedsyncError> {
+        self.inner.reserve(reservation)
+    }
+
+    fn cancel_reservation(&self, reservation: &Reservation) -> Result<(), RedsyncError> {
+        self.inner.cancel_reservation(reservation)
+    }
+}
+
+/// `ReplayInstance` is an Instance that answers acquire, extend, reacquire and release calls with
+/// the responses, and after the delays, recorded for one instance of a `Fixture`. Once a call
+/// diverges from the recording, or the recording runs out, it fails with `InjectedFault`. Other
+/// calls are not recorded: `peek` finds no lock, and the rest fail with `InjectedFault`.
+pub struct ReplayInstance {
+    interactions: Mutex<VecDeque<Interaction>>,
+}
+
+impl ReplayInstance {
+    fn replay(&self, operation: Operation) -> Result<(), RedsyncError> {
+        let mut interactions = self.interactions.lock().unwrap();
+        match interactions.front() {
+            Some(interaction) if interaction.operation == operation => {
+                let interaction = interactions.pop_front().unwrap();
+                drop(interactions);
+                thread::sleep(interaction.elapsed);
+                interaction.result()
+            }
+            _ => Err(RedsyncError::InjectedFault),
+        }
+    }
+}
+
+impl Instance for ReplayInstance {
+    fn acquire(&self, _lock: &Lock) -> Result<(), RedsyncError> {
+        self.replay(Operation::Acquire)
+    }
+
+    fn acquire_replicated(
+        &self,
+        _lock: &Lock,
+        _durability: &Durability,
+    ) -> Result<(), RedsyncError> {
+        self.replay(Operation::Acquire)
+    }
+
+    fn acquire_if_version(
+        &self,
+        _lock: &Lock,
+        _version: u64,
+        _durability: Option<&Durability>,
+    ) -> Result<(), RedsyncError> {
+        self.replay(Operation::Acquire)
+    }
+
+    fn extend(&self, _lock: &Lock) -> Result<(), RedsyncError> {
+        self.replay(Operation::Extend)
+    }
+
+    fn reacquire(&self, _lock: &Lock) -> Result<(), RedsyncError> {
+        self.replay(Operation::Reacquire)
+    }
+
+    fn release(&self, _lock: &Lock) -> Result<(), RedsyncError> {
+        self.replay(Operation::Release)
+    }
+
+    fn ttl(&self, _lock: &Lock) -> Result<Duration, RedsyncError> {
+        Err(RedsyncError::InjectedFault)
+    }
+
+    fn guarded_set(&self, _lock: &Lock, _key: &str, _value: &str) -> Result<(), RedsyncError> {
+        Err(RedsyncError::InjectedFault)
+    }
+
+    fn peek(&self, _resource: &[u8]) -> Result<Option<Duration>, RedsyncError> {
+        Ok(None)
+    }
+
+    fn ping(&self) -> Result<(), RedsyncError> {
+        Err(RedsyncError::InjectedFault)
+    }
+
+    fn diagnostics(&self) -> Result<NodeDiagnostics, RedsyncError> {
+        Err(RedsyncError::InjectedFault)
+    }
+
+    fn time(&self) -> Result<SystemTime, RedsyncError> {
+        Err(RedsyncError::InjectedFault)
+    }
+
+    fn force_release_prefix(
+        &self,
+        _prefix: &str,
+        _dry_run: bool,
+        _bare_tokens: bool,
+    ) -> Result<Vec<String>, RedsyncError> {
+        Err(RedsyncError::InjectedFault)
+    }
+
+    fn incr(&self, _key: &str, _delta: i64, _expiry: Duration) -> Result<i64, RedsyncError> {
+        Err(RedsyncError::InjectedFault)
+    }
+
+    fn incr_stats(&self, _resource: &[u8], _fields: &[(&str, i64)]) -> Result<(), RedsyncError> {
+        Err(RedsyncError::InjectedFault)
+    }
+
+    fn read_stats(&self, _resource: &[u8]) -> Result<Vec<(String, i64)>, RedsyncError> {
+        Err(RedsyncError::InjectedFault)
+    }
+
+    fn advance_sequence(&self, _lock: &Lock, _floor: i64, _step: i64) -> Result<i64, RedsyncError> {
+        Err(RedsyncError::InjectedFault)
+    }
+
+    fn append_audit(
+        &self,
+        _fields: &[(&str, String)],
+        _retention: usize,
+    ) -> Result<(), RedsyncError> {
+        Err(RedsyncError::InjectedFault)
+    }
+
+    fn read_audit(&self, _count: usize) -> Result<Vec<AuditEvent>, RedsyncError> {
+        Err(RedsyncError::InjectedFault)
+    }
+
+    fn reserve(&self, _reservation: &Reservation) -> Result<(), RedsyncError> {
+        Err(RedsyncError::InjectedFault)
+    }
+
+    fn cancel_reservation(&self, _reservation: &Reservation) -> Result<(), RedsyncError> {
+        Err(RedsyncError::InjectedFault)
+    }
+}
+
+impl Redsync<ReplayInstance> {
+    /// `replay` creates a manager whose cluster replays the calls recorded in `fixture`. The
+    /// manager should be configured, e.g. with `RedsyncBuilder::new(fixture.instances())`, like
+    /// the one that was recorded, for its calls to follow the recording.
+    pub fn replay(fixture: &Fixture) -> Self {
+        Redsync::new(fixture.instances())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::RedsyncBuilder;
+
+    const FIXTURE: &str = "cluster 3
+0 acquire 100 ok
+1 acquire 200 resource locked
+2 acquire 100 io error
+0 release 100 ok
+2 release 100 ok
+";
+
+    #[test]
+    fn fixture() -> Result<(), RedsyncError> {
+        let fixture: Fixture = FIXTURE.parse()?;
+        assert_eq!(fixture.cluster_size, 3);
+        assert_eq!(fixture.interactions.len(), 5);
+        assert_eq!(
+            fixture.interactions[1],
+            Interaction {
+                instance: 1,
+                operation: Operation::Acquire,
+                elapsed: Duration::from_micros(200),
+                outcome: String::from("resource locked"),
+            }
+        );
+        assert_eq!(fixture.to_string(), FIXTURE);
+
+        Ok(())
+    }
+
+    #[test]
+    fn fixture_invalid() {
+        assert!("".parse::<Fixture>().is_err());
+        assert!("cluster 1\n1 acquire 100 ok".parse::<Fixture>().is_err());
+        assert!("cluster 1\n0 lock 100 ok".parse::<Fixture>().is_err());
+    }
+
+    #[test]
+    fn replay() -> Result<(), RedsyncError> {
+        let fixture: Fixture = FIXTURE.parse()?;
+        assert!(Redsync::replay(&fixture)
+            .lock("replay", Duration::from_secs(1))
+            .is_err());
+
+        // Recording the replay reproduces the fixture, other than its timings
+        let recording = Recording::new();
+        let dlm = RedsyncBuilder::new(recording.instances(fixture.instances()))
+            .retry_count(1)
+            .build();
+        assert!(dlm.lock("replay", Duration::from_secs(1)).is_err());
+
+        let recorded = recording.take();
+        assert_eq!(recorded.cluster_size, 3);
+        let calls = |fixture: &Fixture| -> Vec<_> {
+            fixture
+                .interactions
+                .iter()
+                .map(|i| (i.instance, i.operation, i.outcome.clone()))
+                .collect()
+        };
+        let mut expected = calls(&fixture);
+        // The replayed io error is recorded as the injected fault it is replayed as
+        expected[2].2 = String::from("injected fault");
+        assert_eq!(calls(&recorded), expected);
+        assert!(recording.take().interactions.is_empty());
+
+        Ok(())
+    }
+}
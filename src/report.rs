@@ -0,0 +1,96 @@
+use crate::errors::{MultiError, RedsyncError};
+
+use std::time::{Duration, Instant};
+
+/// `AcquireReport` accounts for how the TTL of a lock was spent by the attempt that acquired or
+/// last extended it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AcquireReport {
+    pub started: Instant,
+    pub ttl: Duration,
+    /// `io` is the time spent contacting instances.
+    pub io: Duration,
+    /// `drift` is the time reserved for clock drift between instances.
+    pub drift: Duration,
+}
+
+impl AcquireReport {
+    /// `validity` returns how much of the TTL remained once the attempt completed.
+    pub fn validity(&self) -> Duration {
+        self.ttl.saturating_sub(self.io + self.drift)
+    }
+
+    /// `validity_consumed_by_io` returns the fraction of the TTL spent contacting instances.
+    pub fn validity_consumed_by_io(&self) -> f64 {
+        if self.ttl.is_zero() {
+            return 1.0;
+        }
+
+        (self.io.as_secs_f64() / self.ttl.as_secs_f64()).min(1.0)
+    }
+}
+
+/// `UnlockReport` describes the outcome of releasing a lock on each instance.
+#[derive(Debug, Default, PartialEq)]
+pub struct UnlockReport {
+    /// `released` is the number of instances the lock was released on.
+    pub released: usize,
+    /// `expired` is the number of instances on which the lock had already expired or was held
+    /// by another caller.
+    pub expired: usize,
+    /// `errors` holds the errors from instances that could not be contacted or failed.
+    pub errors: MultiError,
+}
+
+impl UnlockReport {
+    /// `is_expired` returns true if the lock had expired on every instance without errors,
+    /// meaning there was nothing left to release.
+    pub fn is_expired(&self) -> bool {
+        self.released == 0 && self.expired > 0 && self.errors.is_empty()
+    }
+
+    /// `into_errors` returns every failure, counting expired instances as `InvalidLease`.
+    pub(crate) fn into_errors(self) -> MultiError {
+        let mut errors = self.errors;
+        errors.extend((0..self.expired).map(|_| RedsyncError::InvalidLease));
+        errors
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validity() {
+        let report = AcquireReport {
+            started: Instant::now(),
+            ttl: Duration::from_secs(1),
+            io: Duration::from_millis(250),
+            drift: Duration::from_millis(12),
+        };
+
+        assert_eq!(report.validity(), Duration::from_millis(738));
+        assert_eq!(report.validity_consumed_by_io(), 0.25);
+    }
+
+    #[test]
+    fn unlock_report() {
+        let report = UnlockReport {
+            released: 0,
+            expired: 3,
+            errors: MultiError::new(),
+        };
+        assert!(report.is_expired());
+        assert_eq!(report.into_errors().len(), 3);
+
+        let mut errors = MultiError::new();
+        errors.push(RedsyncError::InjectedFault);
+        let report = UnlockReport {
+            released: 0,
+            expired: 2,
+            errors,
+        };
+        assert!(!report.is_expired());
+    }
+}
@@ -0,0 +1,85 @@
+#[cfg(any(feature = "redis", feature = "resp", test))]
+use std::time::UNIX_EPOCH;
+use std::time::{Duration, SystemTime};
+
+/// `reservation_key` returns the key of the reservation marker of `resource`. The marker holds
+/// the start of the reservation and its value, and expires at the end of the reservation.
+#[cfg(any(feature = "redis", feature = "resp", test))]
+pub(crate) fn reservation_key(resource: &[u8]) -> Vec<u8> {
+    [b"redsync:reservation:", resource].concat()
+}
+
+/// `Reservation` is a window of time during which a resource can only be locked through
+/// `Redsync::claim`, placed with `Redsync::reserve`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Reservation {
+    pub resource: Vec<u8>,
+    /// `value` is the value of the locks claimed with the reservation.
+    pub(crate) value: Vec<u8>,
+    pub start: SystemTime,
+    pub end: SystemTime,
+}
+
+impl Reservation {
+    pub(crate) fn new(resource: Vec<u8>, value: Vec<u8>, start: SystemTime, ttl: Duration) -> Self {
+        Self {
+            resource,
+            value,
+            start,
+            end: start + ttl,
+        }
+    }
+
+    #[cfg(any(feature = "redis", feature = "resp", test))]
+    pub(crate) fn key(&self) -> Vec<u8> {
+        reservation_key(&self.resource)
+    }
+
+    /// `marker` returns the value of the reservation marker, read by the acquire, reacquire and
+    /// extend scripts as `<start in ms since the epoch>:<value>`.
+    #[cfg(any(feature = "redis", feature = "resp", test))]
+    pub(crate) fn marker(&self) -> Vec<u8> {
+        [
+            self.start_millis().to_string().as_bytes(),
+            b":",
+            &self.value,
+        ]
+        .concat()
+    }
+
+    #[cfg(any(feature = "redis", feature = "resp", test))]
+    pub(crate) fn start_millis(&self) -> u64 {
+        self.start
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64
+    }
+
+    /// `remaining` returns how long until the reservation ends.
+    pub fn remaining(&self) -> Duration {
+        self.end
+            .duration_since(SystemTime::now())
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn marker() {
+        let start = UNIX_EPOCH + Duration::from_millis(1_700_000_000_123);
+        let reservation = Reservation::new(
+            b"maintenance".to_vec(),
+            b"abc".to_vec(),
+            start,
+            Duration::from_secs(60),
+        );
+
+        assert_eq!(reservation.key(), b"redsync:reservation:maintenance");
+        assert_eq!(reservation.marker(), b"1700000000123:abc");
+        assert_eq!(reservation.end, start + Duration::from_secs(60));
+        assert_eq!(reservation.remaining(), Duration::ZERO);
+    }
+}
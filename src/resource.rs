@@ -0,0 +1,84 @@
+use crate::errors::RedsyncError;
+
+use std::borrow::Cow;
+
+/// `ResourceRules` validates and normalizes resource names before they are locked. By default,
+/// empty names and names containing whitespace or control characters are rejected.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ResourceRules {
+    max_len: Option<usize>,
+    forbidden: Vec<char>,
+    lowercase: bool,
+}
+
+impl ResourceRules {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// `max_len` rejects resource names longer than `max_len` bytes.
+    pub fn max_len(mut self, max_len: usize) -> Self {
+        self.max_len = Some(max_len);
+        self
+    }
+
+    /// `forbid` rejects resource names containing any of the characters in `chars`.
+    pub fn forbid(mut self, chars: &str) -> Self {
+        self.forbidden.extend(chars.chars());
+        self
+    }
+
+    /// `lowercase` normalizes resource names to lowercase, so that names differing only in case
+    /// refer to the same lock.
+    pub fn lowercase(mut self, lowercase: bool) -> Self {
+        self.lowercase = lowercase;
+        self
+    }
+
+    /// `apply` returns the normalized form of `resource`, or `RedsyncError::InvalidResourceName`
+    /// if it breaks a rule.
+    pub fn apply<'a>(&self, resource: &'a str) -> Result<Cow<'a, str>, RedsyncError> {
+        let invalid = || Err(RedsyncError::InvalidResourceName(String::from(resource)));
+
+        if resource.is_empty() {
+            return invalid();
+        }
+        if matches!(self.max_len, Some(max_len) if resource.len() > max_len) {
+            return invalid();
+        }
+        if resource
+            .chars()
+            .any(|c| c.is_whitespace() || c.is_control() || self.forbidden.contains(&c))
+        {
+            return invalid();
+        }
+
+        if self.lowercase && resource.chars().any(char::is_uppercase) {
+            return Ok(Cow::Owned(resource.to_lowercase()));
+        }
+
+        Ok(Cow::Borrowed(resource))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resource_rules() {
+        let rules = ResourceRules::new();
+        assert_eq!(rules.apply("Orders:42").unwrap(), "Orders:42");
+        assert!(rules.apply("").is_err());
+        assert!(rules.apply("orders 42").is_err());
+        assert!(rules.apply("orders\n42").is_err());
+
+        let rules = ResourceRules::new().max_len(8).forbid("*?").lowercase(true);
+        assert_eq!(rules.apply("Orders:4").unwrap(), "orders:4");
+        assert!(matches!(
+            rules.apply("orders:42"),
+            Err(RedsyncError::InvalidResourceName(_))
+        ));
+        assert!(rules.apply("orders:*").is_err());
+    }
+}
@@ -0,0 +1,66 @@
+use crate::instance::Instance;
+use crate::redsync::{Lock, Redsync};
+
+use std::panic::{self, AssertUnwindSafe};
+use std::thread;
+
+/// `RollbackPolicy` configures whether the locks granted by some instances during a failed lock
+/// or extend attempt are released before the next attempt.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RollbackPolicy {
+    /// The lock is released after each failed attempt, so that the resource is freed for other
+    /// callers as soon as possible.
+    Release,
+    /// The lock is left to expire at the end of its TTL, halving the commands sent by failed
+    /// attempts during lock storms. Until then, the instances that granted it are unavailable to
+    /// other callers, and to later attempts of the same call.
+    Disabled,
+}
+
+/// `Rollback` guards the lock of an in-progress attempt and releases it on the targeted
+/// instances when dropped, unless the attempt succeeded. Releasing on drop covers attempts that
+/// unwind because an Instance implementation panicked, as well as those that failed to reach a
+/// quorum.
+pub(crate) struct Rollback<'a, 'l, I: Instance> {
+    redsync: &'a Redsync<I>,
+    lock: &'l mut Lock,
+    targets: Vec<usize>,
+    armed: bool,
+}
+
+impl<'a, 'l, I: Instance> Rollback<'a, 'l, I> {
+    pub(crate) fn new(redsync: &'a Redsync<I>, lock: &'l mut Lock) -> Self {
+        Self {
+            redsync,
+            lock,
+            targets: Vec::new(),
+            armed: true,
+        }
+    }
+
+    /// `parts` returns the guarded lock and the indices of the instances to release it on.
+    pub(crate) fn parts(&mut self) -> (&mut Lock, &mut Vec<usize>) {
+        (self.lock, &mut self.targets)
+    }
+
+    /// `disarm` keeps the lock from being released.
+    pub(crate) fn disarm(mut self) {
+        self.armed = false;
+    }
+}
+
+impl<I: Instance> Drop for Rollback<'_, '_, I> {
+    fn drop(&mut self) {
+        if !self.armed || self.redsync.rollback == RollbackPolicy::Disabled {
+            return;
+        }
+
+        let (redsync, lock, targets) = (self.redsync, &*self.lock, &self.targets);
+        if thread::panicking() {
+            // A second panic while unwinding would abort the process
+            let _ = panic::catch_unwind(AssertUnwindSafe(|| redsync.release_on(lock, targets)));
+        } else {
+            redsync.release_on(lock, targets);
+        }
+    }
+}
@@ -0,0 +1,79 @@
+use crate::errors::RedsyncError;
+use crate::instance::Instance;
+use crate::redsync::{Lock, Redsync};
+use crate::ttl::IntoLeaseTtl;
+
+use std::panic::{self, AssertUnwindSafe};
+use std::thread;
+
+/// `ScopedLock` identifies a lock acquired through a `LockScope`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ScopedLock(usize);
+
+/// `LockScope` is passed to the closure run by `Redsync::scope`. Every lock acquired through it
+/// is released, in reverse order of acquisition, when the scope exits, whether it returns or
+/// panics.
+pub struct LockScope<'a, I: Instance> {
+    redsync: &'a Redsync<I>,
+    locks: Vec<Option<Lock>>,
+}
+
+impl<'a, I: Instance> LockScope<'a, I> {
+    pub(crate) fn new(redsync: &'a Redsync<I>) -> Self {
+        Self {
+            redsync,
+            locks: Vec::new(),
+        }
+    }
+
+    /// `lock` locks `resource` for the rest of the scope.
+    pub fn lock(
+        &mut self,
+        resource: impl AsRef<[u8]>,
+        ttl: impl IntoLeaseTtl,
+    ) -> Result<ScopedLock, RedsyncError> {
+        let lock = self.redsync.lock(resource, ttl)?;
+        self.locks.push(Some(lock));
+        Ok(ScopedLock(self.locks.len() - 1))
+    }
+
+    /// `get` returns the lock identified by `scoped`, or `None` if it was already unlocked.
+    pub fn get(&self, scoped: ScopedLock) -> Option<&Lock> {
+        self.locks[scoped.0].as_ref()
+    }
+
+    /// `extend` resets the TTL of the lock identified by `scoped` to `ttl`.
+    pub fn extend(
+        &mut self,
+        scoped: ScopedLock,
+        ttl: impl IntoLeaseTtl,
+    ) -> Result<(), RedsyncError> {
+        let lock = self.locks[scoped.0]
+            .as_mut()
+            .ok_or(RedsyncError::InvalidLease)?;
+        *lock = self.redsync.extend(lock, ttl)?;
+        Ok(())
+    }
+
+    /// `unlock` unlocks the lock identified by `scoped` before the scope exits.
+    pub fn unlock(&mut self, scoped: ScopedLock) -> Result<(), RedsyncError> {
+        match self.locks[scoped.0].take() {
+            Some(lock) => self.redsync.unlock_owned(lock),
+            None => Err(RedsyncError::InvalidLease),
+        }
+    }
+}
+
+impl<I: Instance> Drop for LockScope<'_, I> {
+    fn drop(&mut self) {
+        let redsync = self.redsync;
+        for lock in self.locks.drain(..).rev().flatten() {
+            if thread::panicking() {
+                // A second panic while unwinding would abort the process
+                let _ = panic::catch_unwind(AssertUnwindSafe(|| redsync.unlock(&lock)));
+            } else {
+                let _ = redsync.unlock_owned(lock);
+            }
+        }
+    }
+}
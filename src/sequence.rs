@@ -0,0 +1,6 @@
+/// `sequence_key` returns the key of the acquisition sequence counter of `resource`. Sequence
+/// counters never expire, so that they keep increasing across idle periods.
+#[cfg(any(feature = "redis", feature = "resp", test))]
+pub(crate) fn sequence_key(resource: &[u8]) -> Vec<u8> {
+    [b"redsync:seq:", resource].concat()
+}
@@ -0,0 +1,96 @@
+use crate::errors::RedsyncError;
+use crate::instance::Instance;
+use crate::redsync::{Lock, Redsync};
+use crate::stripe::fnv1a;
+use crate::ttl::IntoLeaseTtl;
+
+/// `VIRTUAL_NODES` is the number of points each shard is given on the hash ring, evening out
+/// the share of the keyspace routed to each shard.
+const VIRTUAL_NODES: usize = 128;
+
+/// `ShardedRedsync` routes each resource to one of several independent Redlock clusters by
+/// consistent hashing, so that the keyspace and load can grow beyond a single cluster. Adding or
+/// removing a shard only moves the resources between it and its neighbours on the ring.
+///
+/// Shards are placed on the ring by name, so every process routing the same keyspace must be
+/// configured with the same shard names.
+pub struct ShardedRedsync<I: Instance> {
+    shards: Vec<(String, Redsync<I>)>,
+    ring: Vec<(u64, usize)>,
+}
+
+impl<I: Instance> ShardedRedsync<I> {
+    /// `new` creates a ShardedRedsync over the named `shards`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `shards` is empty.
+    pub fn new<S: Into<String>>(shards: Vec<(S, Redsync<I>)>) -> Self {
+        assert!(
+            !shards.is_empty(),
+            "a ShardedRedsync needs at least one shard"
+        );
+
+        let shards: Vec<(String, Redsync<I>)> = shards
+            .into_iter()
+            .map(|(name, redsync)| (name.into(), redsync))
+            .collect();
+
+        let mut ring = Vec::with_capacity(shards.len() * VIRTUAL_NODES);
+        for (i, (name, _)) in shards.iter().enumerate() {
+            for node in 0..VIRTUAL_NODES {
+                ring.push((ring_hash(format!("{}#{}", name, node).as_bytes()), i));
+            }
+        }
+        ring.sort_unstable();
+
+        Self { shards, ring }
+    }
+
+    /// `shard_name` returns the name of the shard that `resource` is routed to.
+    pub fn shard_name(&self, resource: impl AsRef<[u8]>) -> &str {
+        &self.shards[self.route(resource.as_ref())].0
+    }
+
+    /// `shard` returns the manager of the shard that `resource` is routed to.
+    pub fn shard(&self, resource: impl AsRef<[u8]>) -> &Redsync<I> {
+        &self.shards[self.route(resource.as_ref())].1
+    }
+
+    /// `lock` locks `resource` on the shard it is routed to. See `Redsync::lock`.
+    pub fn lock(
+        &self,
+        resource: impl AsRef<[u8]>,
+        ttl: impl IntoLeaseTtl,
+    ) -> Result<Lock, RedsyncError> {
+        let resource = resource.as_ref();
+        self.shard(resource).lock(resource, ttl)
+    }
+
+    /// `extend` extends `lock` on the shard its resource is routed to. See `Redsync::extend`.
+    pub fn extend(&self, lock: &Lock, ttl: impl IntoLeaseTtl) -> Result<Lock, RedsyncError> {
+        self.shard(&lock.resource).extend(lock, ttl)
+    }
+
+    /// `unlock` releases `lock` on the shard its resource is routed to.
+    pub fn unlock(&self, lock: &Lock) -> Result<(), RedsyncError> {
+        self.shard(&lock.resource).unlock(lock)
+    }
+
+    /// `route` returns the index of the shard owning the first ring point at or after the hash
+    /// of `resource`, wrapping around to the start of the ring.
+    fn route(&self, resource: &[u8]) -> usize {
+        let hash = ring_hash(resource);
+        let i = self.ring.partition_point(|&(point, _)| point < hash);
+        self.ring[i % self.ring.len()].1
+    }
+}
+
+/// `ring_hash` places `bytes` on the ring, mixing the FNV-1a hash so that similar names, such as
+/// the virtual nodes of a shard, are spread across the ring.
+fn ring_hash(bytes: &[u8]) -> u64 {
+    let mut hash = fnv1a(bytes);
+    hash = (hash ^ (hash >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    hash = (hash ^ (hash >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    hash ^ (hash >> 31)
+}
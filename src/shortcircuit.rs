@@ -0,0 +1,44 @@
+/// `ShortCircuit` configures whether lock and extend attempts stop contacting the remaining
+/// instances once their outcome is known.
+///
+/// Contacting every instance is the default, since a lock held on more instances than the quorum
+/// tolerates more failures when it is later extended.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ShortCircuit {
+    /// Every instance is contacted on each attempt.
+    Disabled,
+    /// Attempts stop once too many instances have failed for the quorum to be reached.
+    Impossible,
+    /// Attempts stop once the quorum is either reached or impossible, minimizing latency.
+    Decided,
+}
+
+impl ShortCircuit {
+    /// `is_decided` returns true if an attempt with `votes` successes and `pending` instances left
+    /// to contact can stop early. `reached` is true if the attempt already meets its quorum.
+    pub(crate) fn is_decided(&self, votes: u32, pending: u32, quorum: u32, reached: bool) -> bool {
+        let impossible = votes + pending < quorum;
+        match self {
+            ShortCircuit::Disabled => false,
+            ShortCircuit::Impossible => impossible,
+            ShortCircuit::Decided => impossible || reached,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn is_decided() {
+        assert!(!ShortCircuit::Disabled.is_decided(0, 0, 2, false));
+
+        assert!(ShortCircuit::Impossible.is_decided(0, 1, 2, false));
+        assert!(!ShortCircuit::Impossible.is_decided(1, 1, 2, false));
+        assert!(!ShortCircuit::Impossible.is_decided(2, 1, 2, true));
+
+        assert!(ShortCircuit::Decided.is_decided(0, 1, 2, false));
+        assert!(ShortCircuit::Decided.is_decided(2, 1, 2, true));
+    }
+}
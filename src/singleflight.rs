@@ -0,0 +1,205 @@
+use crate::errors::{ErrorKind, RedsyncError};
+
+use std::collections::HashMap;
+use std::sync::{Arc, Condvar, Mutex};
+
+/// `SingleFlight` configures how concurrent in-process lock calls on the same resource behave.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SingleFlight {
+    /// Every call contacts the cluster independently.
+    Disabled,
+    /// Calls wait for any in-flight call on the same resource and share its outcome instead of
+    /// contacting the cluster themselves. If it acquired the lock, which then belongs to that call
+    /// alone, they fail with `ResourceLockedLocally`; if it failed, they fail with
+    /// `InFlightFailed` and the kind of its error. Calls waiting behind one that panicked, or
+    /// that made a single attempt through `Redsync::lock_attempts` and failed, go on to contact
+    /// the cluster.
+    Share,
+    /// Calls fail with `ResourceLockedLocally` while another call on the same resource is in flight.
+    Reject,
+}
+
+/// `InFlight` tracks the resources with a lock call currently in progress.
+#[derive(Debug, Default)]
+pub(crate) struct InFlight {
+    flights: Mutex<HashMap<Vec<u8>, Arc<Flight>>>,
+}
+
+/// `Flight` is a lock call in progress, whose outcome is shared with the calls waiting on it.
+#[derive(Debug, Default)]
+struct Flight {
+    state: Mutex<FlightState>,
+    done: Condvar,
+}
+
+#[derive(Debug, Default)]
+struct FlightState {
+    done: bool,
+    outcome: Option<Result<(), ErrorKind>>,
+}
+
+impl Flight {
+    /// `wait` blocks until the call completes, and returns its outcome if it recorded one.
+    fn wait(&self) -> Option<Result<(), ErrorKind>> {
+        let mut state = self.state.lock().unwrap();
+        while !state.done {
+            state = self.done.wait(state).unwrap();
+        }
+        state.outcome
+    }
+}
+
+pub(crate) struct InFlightGuard<'a> {
+    inflight: &'a InFlight,
+    resource: Vec<u8>,
+    flight: Arc<Flight>,
+}
+
+impl InFlight {
+    pub(crate) fn new() -> Self {
+        Default::default()
+    }
+
+    pub(crate) fn enter(
+        &self,
+        resource: &[u8],
+        mode: SingleFlight,
+    ) -> Result<Option<InFlightGuard<'_>>, RedsyncError> {
+        if mode == SingleFlight::Disabled {
+            return Ok(None);
+        }
+
+        let mut flights = self.flights.lock().unwrap();
+        while let Some(flight) = flights.get(resource) {
+            if mode == SingleFlight::Reject {
+                return Err(RedsyncError::ResourceLockedLocally);
+            }
+
+            let flight = Arc::clone(flight);
+            drop(flights);
+            match flight.wait() {
+                Some(Ok(())) => return Err(RedsyncError::ResourceLockedLocally),
+                Some(Err(kind)) => return Err(RedsyncError::InFlightFailed(kind)),
+                None => flights = self.flights.lock().unwrap(),
+            }
+        }
+
+        let flight = Arc::new(Flight::default());
+        flights.insert(resource.to_vec(), Arc::clone(&flight));
+
+        Ok(Some(InFlightGuard {
+            inflight: self,
+            resource: resource.to_vec(),
+            flight,
+        }))
+    }
+}
+
+impl InFlightGuard<'_> {
+    /// `finish` records the outcome of the call, to be shared with the calls waiting on it.
+    pub(crate) fn finish<T>(self, result: &Result<T, RedsyncError>) {
+        self.flight.state.lock().unwrap().outcome =
+            Some(result.as_ref().map(|_| ()).map_err(RedsyncError::kind));
+    }
+}
+
+impl Drop for InFlightGuard<'_> {
+    fn drop(&mut self) {
+        self.inflight.flights.lock().unwrap().remove(&self.resource);
+        self.flight.state.lock().unwrap().done = true;
+        self.flight.done.notify_all();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::matches;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn disabled() -> Result<(), RedsyncError> {
+        let inflight = InFlight::new();
+
+        let _guard = inflight.enter(b"test", SingleFlight::Disabled)?;
+        let attempt = inflight.enter(b"test", SingleFlight::Disabled);
+        assert!(matches!(attempt, Ok(None)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn reject() -> Result<(), RedsyncError> {
+        let inflight = InFlight::new();
+
+        let guard = inflight.enter(b"test", SingleFlight::Reject)?;
+        let attempt = inflight.enter(b"test", SingleFlight::Reject);
+        assert!(matches!(attempt, Err(RedsyncError::ResourceLockedLocally)));
+
+        let attempt = inflight.enter(b"other", SingleFlight::Reject);
+        assert!(matches!(attempt, Ok(Some(_))));
+
+        drop(guard);
+        let attempt = inflight.enter(b"test", SingleFlight::Reject);
+        assert!(matches!(attempt, Ok(Some(_))));
+
+        Ok(())
+    }
+
+    /// `share` enters `resource` from another thread while `guard` is in flight, finishing `guard`
+    /// with `result` if any, and returns what the other thread got.
+    fn share(
+        inflight: &Arc<InFlight>,
+        result: Option<Result<(), RedsyncError>>,
+    ) -> Result<bool, RedsyncError> {
+        let guard = inflight.enter(b"test", SingleFlight::Share)?.unwrap();
+
+        let handle = {
+            let inflight = inflight.clone();
+            thread::spawn(move || {
+                inflight
+                    .enter(b"test", SingleFlight::Share)
+                    .map(|g| g.is_some())
+            })
+        };
+
+        thread::sleep(Duration::from_millis(50));
+        assert!(!handle.is_finished());
+
+        match result {
+            Some(result) => guard.finish(&result),
+            None => drop(guard),
+        }
+        handle.join().unwrap()
+    }
+
+    #[test]
+    fn share_acquired() {
+        let inflight = Arc::new(InFlight::new());
+
+        let attempt = share(&inflight, Some(Ok(())));
+        assert!(matches!(attempt, Err(RedsyncError::ResourceLockedLocally)));
+    }
+
+    #[test]
+    fn share_failed() {
+        let inflight = Arc::new(InFlight::new());
+
+        let attempt = share(&inflight, Some(Err(RedsyncError::ResourceLocked)));
+        assert!(matches!(
+            attempt,
+            Err(RedsyncError::InFlightFailed(ErrorKind::Contention))
+        ));
+    }
+
+    #[test]
+    fn share_without_outcome() {
+        let inflight = Arc::new(InFlight::new());
+
+        // The waiting call goes on to make the call itself
+        let attempt = share(&inflight, None);
+        assert!(matches!(attempt, Ok(true)));
+        assert!(inflight.flights.lock().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,63 @@
+use std::fmt;
+use std::time::Duration;
+
+/// `ConfigSnapshot` records the configuration of a manager that shapes its calls, attached to
+/// the errors of failed calls when enabled with `RedsyncBuilder::config_snapshot`. It holds no
+/// instance addresses or credentials, so that it can be logged as is.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConfigSnapshot {
+    pub cluster_size: usize,
+    pub quorum: u32,
+    pub retry_count: u32,
+    pub retry_delay: Duration,
+    pub retry_jitter: f64,
+    pub extend_retry_count: u32,
+    pub extend_retry_delay: Duration,
+    pub drift_factor: f64,
+    pub interop: bool,
+}
+
+impl fmt::Display for ConfigSnapshot {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "quorum={}/{} retry_count={} retry_delay={:?} retry_jitter={} extend_retry_count={} \
+             extend_retry_delay={:?} drift_factor={} interop={}",
+            self.quorum,
+            self.cluster_size,
+            self.retry_count,
+            self.retry_delay,
+            self.retry_jitter,
+            self.extend_retry_count,
+            self.extend_retry_delay,
+            self.drift_factor,
+            self.interop
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display() {
+        let snapshot = ConfigSnapshot {
+            cluster_size: 3,
+            quorum: 2,
+            retry_count: 3,
+            retry_delay: Duration::from_millis(200),
+            retry_jitter: 0.5,
+            extend_retry_count: 3,
+            extend_retry_delay: Duration::from_millis(200),
+            drift_factor: 0.01,
+            interop: false,
+        };
+
+        assert_eq!(
+            snapshot.to_string(),
+            "quorum=2/3 retry_count=3 retry_delay=200ms retry_jitter=0.5 extend_retry_count=3 \
+             extend_retry_delay=200ms drift_factor=0.01 interop=false"
+        );
+    }
+}
@@ -0,0 +1,70 @@
+use std::time::Duration;
+
+/// `STATS_WINDOW` is how long per-resource statistics are kept in Redis after their last update.
+pub const STATS_WINDOW: Duration = Duration::from_secs(24 * 60 * 60);
+
+pub(crate) const ACQUISITIONS: &str = "acquisitions";
+pub(crate) const CONTENTIONS: &str = "contentions";
+pub(crate) const RELEASES: &str = "releases";
+pub(crate) const HOLD_MS: &str = "hold_ms";
+
+/// `stats_key` returns the key of the Redis hash holding the statistics of `resource`.
+#[cfg(any(feature = "redis", feature = "resp"))]
+pub(crate) fn stats_key(resource: &[u8]) -> Vec<u8> {
+    [b"redsync:stats:", resource].concat()
+}
+
+/// `ResourceStats` holds the sampled lock statistics of a resource, as estimated totals over the
+/// statistics window.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ResourceStats {
+    pub acquisitions: u64,
+    pub contentions: u64,
+    pub releases: u64,
+    pub total_hold: Duration,
+}
+
+impl ResourceStats {
+    pub(crate) fn from_fields(fields: Vec<(String, i64)>) -> Self {
+        let mut stats = Self::default();
+        for (field, value) in fields {
+            let value = value.max(0) as u64;
+            match field.as_str() {
+                ACQUISITIONS => stats.acquisitions = value,
+                CONTENTIONS => stats.contentions = value,
+                RELEASES => stats.releases = value,
+                HOLD_MS => stats.total_hold = Duration::from_millis(value),
+                _ => {}
+            }
+        }
+
+        stats
+    }
+
+    /// `average_hold` returns the average time locks on the resource were held before release.
+    pub fn average_hold(&self) -> Option<Duration> {
+        match self.releases {
+            0 => None,
+            n => Some(self.total_hold / n as u32),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_fields() {
+        let stats = ResourceStats::from_fields(vec![
+            (String::from("acquisitions"), 4),
+            (String::from("releases"), 2),
+            (String::from("hold_ms"), 300),
+            (String::from("unknown"), 1),
+        ]);
+
+        assert_eq!(stats.acquisitions, 4);
+        assert_eq!(stats.contentions, 0);
+        assert_eq!(stats.average_hold(), Some(Duration::from_millis(150)));
+    }
+}
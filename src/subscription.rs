@@ -0,0 +1,42 @@
+use crate::errors::RedsyncError;
+use crate::instance::release_channel;
+
+use std::time::Duration;
+
+/// `ReleaseSubscription` receives the release events published when locks on a resource are
+/// released.
+pub struct ReleaseSubscription {
+    conn: redis::Connection,
+    channel: Vec<u8>,
+}
+
+impl ReleaseSubscription {
+    pub(crate) fn new(mut conn: redis::Connection, resource: &[u8]) -> Result<Self, RedsyncError> {
+        let channel = release_channel(resource);
+        redis::cmd("SUBSCRIBE")
+            .arg(&channel)
+            .query::<redis::Value>(&mut conn)?;
+
+        Ok(Self { conn, channel })
+    }
+
+    /// `wait` blocks until a lock on the resource is released, returning the value of the
+    /// released lock, or `None` if `timeout` elapses first.
+    pub fn wait(&mut self, timeout: Duration) -> Result<Option<Vec<u8>>, RedsyncError> {
+        self.conn.set_read_timeout(Some(timeout))?;
+
+        loop {
+            let value = match self.conn.recv_response() {
+                Ok(value) => value,
+                Err(e) if e.is_timeout() => return Ok(None),
+                Err(e) => return Err(RedsyncError::from(e)),
+            };
+
+            if let Some(msg) = redis::Msg::from_value(&value) {
+                if msg.get_channel::<Vec<u8>>()? == self.channel {
+                    return Ok(Some(msg.get_payload()?));
+                }
+            }
+        }
+    }
+}
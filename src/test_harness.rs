@@ -0,0 +1,132 @@
+//! Multi-node Redis clusters for integration tests, backed by Docker containers.
+//!
+//! ```no_run
+//! use std::time::Duration;
+//! use testcontainers::clients::Cli;
+//! use redsync::test_harness::TestCluster;
+//!
+//! let docker = Cli::default();
+//! let cluster = TestCluster::start(&docker, 3);
+//! let dlm = cluster.redsync().unwrap();
+//!
+//! cluster.kill(0);
+//! let lock = dlm.lock("resource", Duration::from_secs(1)).unwrap();
+//! ```
+use crate::errors::RedsyncError;
+use crate::instance::RedisInstance;
+use crate::redsync::Redsync;
+
+use std::thread;
+use std::time::Duration;
+
+use testcontainers::clients::Cli;
+use testcontainers::core::WaitFor;
+use testcontainers::{Container, GenericImage, RunnableImage};
+
+const REDIS_PORT: u16 = 6379;
+
+/// `TestCluster` runs a set of standalone Redis containers that are removed once it is dropped.
+pub struct TestCluster<'d> {
+    nodes: Vec<Container<'d, GenericImage>>,
+}
+
+impl<'d> TestCluster<'d> {
+    /// `start` runs `size` Redis containers using the `redis:7` image, waiting until each
+    /// accepts connections.
+    pub fn start(docker: &'d Cli, size: usize) -> Self {
+        let nodes = (0..size)
+            .map(|_| {
+                let image = GenericImage::new("redis", "7")
+                    .with_exposed_port(REDIS_PORT)
+                    .with_wait_for(WaitFor::message_on_stdout("Ready to accept connections"));
+                let args = vec![
+                    String::from("redis-server"),
+                    String::from("--enable-debug-command"),
+                    String::from("yes"),
+                ];
+                docker.run(RunnableImage::from((image, args)))
+            })
+            .collect();
+
+        Self { nodes }
+    }
+
+    /// `urls` returns the connection URL of each node.
+    pub fn urls(&self) -> Vec<String> {
+        (0..self.nodes.len()).map(|i| self.url(i)).collect()
+    }
+
+    /// `instances` returns a new RedisInstance for each node.
+    pub fn instances(&self) -> Result<Vec<RedisInstance>, RedsyncError> {
+        self.urls()
+            .iter()
+            .map(|url| RedisInstance::new(url.as_str()))
+            .collect()
+    }
+
+    /// `redsync` builds a Redsync manager against every node.
+    pub fn redsync(&self) -> Result<Redsync<RedisInstance>, RedsyncError> {
+        Ok(Redsync::new(self.instances()?))
+    }
+
+    /// `pause` suspends command processing on node `i` for `duration`, using CLIENT PAUSE.
+    pub fn pause(&self, i: usize, duration: Duration) -> Result<(), RedsyncError> {
+        let mut conn = self.connection(i)?;
+        redis::cmd("CLIENT")
+            .arg("PAUSE")
+            .arg(duration.as_millis() as u64)
+            .query::<()>(&mut conn)?;
+
+        Ok(())
+    }
+
+    /// `sleep` blocks node `i` for `duration` in the background, using DEBUG SLEEP, to simulate
+    /// a slow node.
+    pub fn sleep(&self, i: usize, duration: Duration) -> Result<(), RedsyncError> {
+        let mut conn = self.connection(i)?;
+        thread::spawn(move || {
+            let _ = redis::cmd("DEBUG")
+                .arg("SLEEP")
+                .arg(duration.as_secs_f64())
+                .query::<()>(&mut conn);
+        });
+
+        Ok(())
+    }
+
+    /// `kill` stops node `i`'s container.
+    pub fn kill(&self, i: usize) {
+        self.nodes[i].stop();
+    }
+
+    fn url(&self, i: usize) -> String {
+        format!(
+            "redis://127.0.0.1:{}",
+            self.nodes[i].get_host_port_ipv4(REDIS_PORT)
+        )
+    }
+
+    fn connection(&self, i: usize) -> Result<redis::Connection, RedsyncError> {
+        let client = redis::Client::open(self.url(i)).map_err(RedsyncError::ConnectionFailed)?;
+        Ok(client.get_connection()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kill_minority() -> Result<(), RedsyncError> {
+        // This test requires a running Docker daemon
+        let docker = Cli::default();
+        let cluster = TestCluster::start(&docker, 3);
+        let dlm = cluster.redsync()?;
+
+        cluster.kill(0);
+        let lock = dlm.lock("kill_minority", Duration::from_secs(1))?;
+        dlm.unlock(&lock)?;
+
+        Ok(())
+    }
+}
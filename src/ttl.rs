@@ -0,0 +1,146 @@
+use crate::errors::RedsyncError;
+
+use std::time::Duration;
+
+/// `DEFAULT_DRIFT_FACTOR` is the fraction of a lock's TTL reserved for clock drift between
+/// instances, unless a measured drift factor is used instead.
+pub const DEFAULT_DRIFT_FACTOR: f64 = 0.01;
+
+/// `DRIFT_MARGIN` is added to the clock drift reserved from every lock's TTL, to account for
+/// the millisecond precision of Redis expiry.
+pub const DRIFT_MARGIN: Duration = Duration::from_millis(2);
+
+/// `min_safe_ttl` returns the smallest TTL for which a lock acquired from `cluster_size`
+/// instances, each taking up to `per_node_timeout`, still has a positive validity window after
+/// reserving `drift_factor` of the TTL for clock drift.
+///
+/// Returns `Duration::MAX` if `drift_factor` leaves no validity window for any TTL.
+pub fn min_safe_ttl(
+    cluster_size: usize,
+    per_node_timeout: Duration,
+    drift_factor: f64,
+) -> Duration {
+    if !(0.0..1.0).contains(&drift_factor) {
+        return Duration::MAX;
+    }
+
+    let elapsed = per_node_timeout.as_secs_f64() * cluster_size as f64;
+    let ttl = (elapsed + DRIFT_MARGIN.as_secs_f64()) / (1.0 - drift_factor);
+
+    // Round up to the millisecond precision used for lock expiry
+    Duration::from_millis((ttl * 1000.0).ceil() as u64)
+}
+
+/// `LeaseTtl` is a lock TTL validated against sanity bounds.
+///
+/// By default, TTLs must lie between `LeaseTtl::MIN` and `LeaseTtl::MAX`. The const constructors
+/// enforce these bounds at compile time when used in a const context.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct LeaseTtl(Duration);
+
+impl LeaseTtl {
+    pub const MIN: Duration = Duration::from_millis(10);
+    pub const MAX: Duration = Duration::from_secs(24 * 60 * 60);
+
+    pub fn new(ttl: Duration) -> Result<Self, RedsyncError> {
+        Self::with_bounds(ttl, Self::MIN, Self::MAX)
+    }
+
+    pub fn with_bounds(ttl: Duration, min: Duration, max: Duration) -> Result<Self, RedsyncError> {
+        if ttl < min || ttl > max {
+            return Err(RedsyncError::InvalidTtl(ttl));
+        }
+
+        Ok(Self(ttl))
+    }
+
+    pub const fn from_millis(millis: u64) -> Self {
+        assert!(
+            millis >= Self::MIN.as_millis() as u64 && millis <= Self::MAX.as_millis() as u64,
+            "lease ttl out of bounds"
+        );
+        Self(Duration::from_millis(millis))
+    }
+
+    pub const fn from_secs(secs: u64) -> Self {
+        Self::from_millis(secs * 1000)
+    }
+
+    pub fn as_duration(&self) -> Duration {
+        self.0
+    }
+}
+
+impl From<LeaseTtl> for Duration {
+    fn from(ttl: LeaseTtl) -> Self {
+        ttl.0
+    }
+}
+
+/// `IntoLeaseTtl` converts a value into a validated `LeaseTtl`.
+pub trait IntoLeaseTtl {
+    fn into_lease_ttl(self) -> Result<LeaseTtl, RedsyncError>;
+}
+
+impl IntoLeaseTtl for LeaseTtl {
+    fn into_lease_ttl(self) -> Result<LeaseTtl, RedsyncError> {
+        Ok(self)
+    }
+}
+
+impl IntoLeaseTtl for Duration {
+    fn into_lease_ttl(self) -> Result<LeaseTtl, RedsyncError> {
+        LeaseTtl::new(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new() -> Result<(), RedsyncError> {
+        let ttl = LeaseTtl::new(Duration::from_secs(1))?;
+        assert_eq!(ttl.as_duration(), Duration::from_secs(1));
+
+        Ok(())
+    }
+
+    #[test]
+    fn new_error() {
+        let ttl = LeaseTtl::new(Duration::from_secs(0));
+        assert_eq!(ttl, Err(RedsyncError::InvalidTtl(Duration::from_secs(0))));
+
+        let ttl = LeaseTtl::new(Duration::from_secs(48 * 60 * 60));
+        assert!(ttl.is_err());
+    }
+
+    #[test]
+    fn with_bounds() {
+        let (min, max) = (Duration::from_secs(1), Duration::from_secs(10));
+
+        assert!(LeaseTtl::with_bounds(Duration::from_secs(5), min, max).is_ok());
+        assert!(LeaseTtl::with_bounds(Duration::from_millis(500), min, max).is_err());
+    }
+
+    #[test]
+    fn min_safe_ttl() {
+        let ttl = super::min_safe_ttl(3, Duration::from_millis(50), DEFAULT_DRIFT_FACTOR);
+        assert_eq!(ttl, Duration::from_millis(154));
+
+        let ttl = super::min_safe_ttl(3, Duration::from_millis(50), 1.0);
+        assert_eq!(ttl, Duration::MAX);
+    }
+
+    #[test]
+    fn from_secs() {
+        const TTL: LeaseTtl = LeaseTtl::from_secs(30);
+        assert_eq!(TTL.as_duration(), Duration::from_secs(30));
+    }
+
+    #[test]
+    #[should_panic(expected = "lease ttl out of bounds")]
+    fn from_millis_out_of_bounds() {
+        LeaseTtl::from_millis(0);
+    }
+}
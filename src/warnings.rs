@@ -0,0 +1,114 @@
+use crate::ttl::min_safe_ttl;
+
+use std::time::Duration;
+
+/// `ConfigWarning` describes a configuration that violates the assumptions of Redlock.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ConfigWarning {
+    /// The cluster has a single instance, so locks are lost whenever it fails or restarts.
+    SingleInstance,
+    /// The cluster has an even number of instances, which tolerates no more failures than the
+    /// next smaller odd cluster.
+    EvenClusterSize(usize),
+    /// The retry delay is shorter than the round trip of an attempt, so retries contend with the
+    /// attempts of other callers that are still in flight.
+    RetryDelayBelowRoundTrip {
+        retry_delay: Duration,
+        round_trip: Duration,
+    },
+    /// The TTL is shorter than the minimum safe TTL for the measured per-instance latency, so
+    /// acquired locks have little or no validity left.
+    TtlBelowSafeMinimum {
+        ttl: Duration,
+        min_safe_ttl: Duration,
+    },
+}
+
+/// `ConfigHook` is called with each `ConfigWarning` detected for a manager.
+pub(crate) type ConfigHook = Box<dyn Fn(&ConfigWarning) + Send + Sync>;
+
+/// `static_warnings` checks the parts of the configuration known before any lock is acquired.
+pub(crate) fn static_warnings(cluster_size: usize) -> Vec<ConfigWarning> {
+    let mut warnings = Vec::new();
+
+    if cluster_size == 1 {
+        warnings.push(ConfigWarning::SingleInstance);
+    }
+    if cluster_size > 0 && cluster_size.is_multiple_of(2) {
+        warnings.push(ConfigWarning::EvenClusterSize(cluster_size));
+    }
+
+    warnings
+}
+
+/// `latency_warnings` checks the configuration against the round trip measured by an attempt
+/// that contacted `cluster_size` instances.
+pub(crate) fn latency_warnings(
+    cluster_size: usize,
+    round_trip: Duration,
+    retry_delay: Duration,
+    ttl: Duration,
+    drift_factor: f64,
+) -> Vec<ConfigWarning> {
+    let mut warnings = Vec::new();
+
+    if retry_delay < round_trip {
+        warnings.push(ConfigWarning::RetryDelayBelowRoundTrip {
+            retry_delay,
+            round_trip,
+        });
+    }
+
+    let per_node = round_trip / cluster_size.max(1) as u32;
+    let min_safe_ttl = min_safe_ttl(cluster_size, per_node, drift_factor);
+    if ttl < min_safe_ttl {
+        warnings.push(ConfigWarning::TtlBelowSafeMinimum { ttl, min_safe_ttl });
+    }
+
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn static_warnings_cluster_size() {
+        assert_eq!(static_warnings(1), [ConfigWarning::SingleInstance]);
+        assert_eq!(static_warnings(4), [ConfigWarning::EvenClusterSize(4)]);
+        assert!(static_warnings(3).is_empty());
+    }
+
+    #[test]
+    fn latency_warnings_slow_cluster() {
+        let warnings = latency_warnings(
+            3,
+            Duration::from_millis(300),
+            Duration::from_millis(200),
+            Duration::from_millis(250),
+            0.01,
+        );
+
+        assert_eq!(warnings.len(), 2);
+        assert_eq!(
+            warnings[0],
+            ConfigWarning::RetryDelayBelowRoundTrip {
+                retry_delay: Duration::from_millis(200),
+                round_trip: Duration::from_millis(300),
+            }
+        );
+        assert!(matches!(
+            warnings[1],
+            ConfigWarning::TtlBelowSafeMinimum { .. }
+        ));
+
+        let warnings = latency_warnings(
+            3,
+            Duration::from_millis(3),
+            Duration::from_millis(200),
+            Duration::from_secs(1),
+            0.01,
+        );
+        assert!(warnings.is_empty());
+    }
+}